@@ -0,0 +1,45 @@
+use crate::linear_algebra::Vec4;
+
+// Parses "#rrggbb" or "#rrggbbaa"
+pub fn hex_to_rgba(hex: &str) -> Option<Vec4> {
+    if (hex.len() != 7 && hex.len() != 9) || !hex.starts_with('#') {
+        return None;
+    }
+
+    let channel = |i: usize| -> Option<f32> {
+        Some(i32::from_str_radix(hex.get(i..i + 2)?, 16).ok()? as f32 / 255.0)
+    };
+    let alpha = if hex.len() == 9 { channel(7)? } else { 1.0 };
+
+    Some(Vec4(channel(1)?, channel(3)?, channel(5)?, alpha))
+}
+
+// "#rrggbb" for opaque colors, "#rrggbbaa" otherwise
+pub fn rgba_to_hex(color: Vec4) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let rgb = format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.0),
+        channel(color.1),
+        channel(color.2)
+    );
+    match channel(color.3) {
+        255 => rgb,
+        alpha => format!("{}{:02x}", rgb, alpha),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_round_trip() {
+        assert_eq!(hex_to_rgba("#ff0000"), Some(Vec4(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(rgba_to_hex(Vec4(1.0, 0.0, 0.0, 1.0)), "#ff0000");
+        assert_eq!(rgba_to_hex(hex_to_rgba("#12abef80").unwrap()), "#12abef80");
+        for hex in &["ff0000", "#ff00", "#gg0000", "#ff0000ff00"] {
+            assert_eq!(hex_to_rgba(hex), None, "{} was accepted", hex);
+        }
+    }
+}
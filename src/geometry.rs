@@ -0,0 +1,569 @@
+use crate::linear_algebra::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// Limits on segment counts, keeping every mesh within the 65536 vertices a
+// u16 index can address
+pub const MAX_SEGMENTS: usize = 180;
+pub const MAX_SUBDIVISIONS: usize = 6;
+
+// An indexed TRIANGLES mesh, counterclockwise when seen from outside. Meshes
+// are centered on the origin with y up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub indices: Vec<u16>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    fn vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) -> u16 {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.uvs.push(uv);
+        (self.positions.len() - 1) as u16
+    }
+
+    // Triangles with two corners in the same place, such as those at the
+    // poles of a sphere, are left out
+    fn triangle(&mut self, a: u16, b: u16, c: u16) {
+        let position = |i: u16| self.positions[i as usize];
+        if position(a) != position(b) && position(b) != position(c) && position(c) != position(a) {
+            self.indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    // A `columns` by `rows` grid of quads from a surface `f(u, v)` returning
+    // position and normal, with u and v running from 0 to 1. `f` must turn
+    // counterclockwise from the u direction to the v direction when seen
+    // from outside.
+    fn parametric<F>(&mut self, columns: usize, rows: usize, f: F)
+    where
+        F: Fn(f32, f32) -> (Vec3, Vec3),
+    {
+        let first = self.positions.len();
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+                let (position, normal) = f(u, v);
+                self.vertex(position, normal, Vec2(u, v));
+            }
+        }
+
+        let index = |row: usize, column: usize| (first + row * (columns + 1) + column) as u16;
+        for row in 0..rows {
+            for column in 0..columns {
+                let (a, b) = (index(row, column), index(row, column + 1));
+                let (c, d) = (index(row + 1, column + 1), index(row + 1, column));
+                self.triangle(a, b, c);
+                self.triangle(a, c, d);
+            }
+        }
+    }
+
+    // A disc of `radius` at height `y` facing up or down
+    fn disc(&mut self, radius: f32, y: f32, segments: usize, up: bool) {
+        let normal = Vec3(0.0, if up { 1.0 } else { -1.0 }, 0.0);
+        let center = self.vertex(Vec3(0.0, y, 0.0), normal, Vec2(0.5, 0.5));
+        let first = self.positions.len() as u16;
+        for i in 0..=segments {
+            let (sin, cos) = angle(i as f32 / segments as f32).sin_cos();
+            self.vertex(
+                Vec3(radius * cos, y, -radius * sin),
+                normal,
+                Vec2(0.5 + 0.5 * cos, 0.5 + 0.5 * sin),
+            );
+        }
+        for i in 0..segments as u16 {
+            if up {
+                self.triangle(center, first + i, first + i + 1);
+            } else {
+                self.triangle(center, first + i + 1, first + i);
+            }
+        }
+    }
+}
+
+// A full turn in radians for `t` from 0 to 1, with 1 giving exactly the same
+// angle as 0 so that seams close
+fn angle(t: f32) -> f32 {
+    2.0 * PI * (t % 1.0)
+}
+
+// Outward normals of the cube's faces, in the order `cube` builds them
+pub const CUBE_FACES: [Vec3; 6] = [
+    Vec3(1.0, 0.0, 0.0),
+    Vec3(-1.0, 0.0, 0.0),
+    Vec3(0.0, 1.0, 0.0),
+    Vec3(0.0, -1.0, 0.0),
+    Vec3(0.0, 0.0, 1.0),
+    Vec3(0.0, 0.0, -1.0),
+];
+
+// Box with sides of length `size`. Each face has its own four vertices, so
+// face `i` is vertices `4 * i..4 * i + 4` and can be colored or lit flat.
+pub fn cuboid(size: Vec3) -> Mesh {
+    let mut mesh = Mesh::new();
+    let scale = |p: Vec3| Vec3(p.0 * size.0, p.1 * size.1, p.2 * size.2);
+
+    for &normal in CUBE_FACES.iter() {
+        // Any axis other than the normal, then a third with u × v = normal
+        let u = Vec3(normal.1.abs(), normal.2.abs(), normal.0.abs());
+        let v = normal.cross(&u);
+        let center = normal * 0.5;
+
+        let first = mesh.positions.len() as u16;
+        for &(a, b) in &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let position = center + u * (a - 0.5) + v * (b - 0.5);
+            mesh.vertex(scale(position), normal, Vec2(a, b));
+        }
+        mesh.triangle(first, first + 1, first + 2);
+        mesh.triangle(first, first + 2, first + 3);
+    }
+
+    mesh
+}
+
+// Unit cube
+pub fn cube() -> Mesh {
+    cuboid(Vec3(1.0, 1.0, 1.0))
+}
+
+// Sphere of `segments` slices around the y axis and `rings` bands from pole
+// to pole
+pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    let segments = segments.clamp(3, MAX_SEGMENTS);
+    let rings = rings.clamp(2, MAX_SEGMENTS);
+    mesh.parametric(segments, rings, |u, v| {
+        let (sin_phi, cos_phi) = angle(u).sin_cos();
+        let latitude = PI * (v - 0.5);
+        // Exactly zero at the poles, so their triangles are recognised as
+        // degenerate
+        let ring = if v == 0.0 || v == 1.0 {
+            0.0
+        } else {
+            latitude.cos()
+        };
+        let normal = Vec3(ring * cos_phi, latitude.sin(), -ring * sin_phi);
+        (normal * radius, normal)
+    });
+    mesh
+}
+
+fn midpoint(mesh: &mut Mesh, cache: &mut HashMap<(u16, u16), u16>, a: u16, b: u16) -> u16 {
+    let key = (a.min(b), a.max(b));
+    if let Some(&i) = cache.get(&key) {
+        return i;
+    }
+    let radius = mesh.positions[a as usize].length();
+    let direction = mesh.positions[a as usize]
+        .mix(&mesh.positions[b as usize], 0.5)
+        .normalize();
+    let i = sphere_vertex(mesh, direction, radius);
+    cache.insert(key, i);
+    i
+}
+
+fn sphere_vertex(mesh: &mut Mesh, direction: Vec3, radius: f32) -> u16 {
+    let uv = Vec2(
+        0.5 + (-direction.2).atan2(direction.0) / (2.0 * PI),
+        0.5 + direction.1.clamp(-1.0, 1.0).asin() / PI,
+    );
+    mesh.vertex(direction * radius, direction, uv)
+}
+
+fn divide_triangle(
+    mesh: &mut Mesh,
+    cache: &mut HashMap<(u16, u16), u16>,
+    a: u16,
+    b: u16,
+    c: u16,
+    count: usize,
+) {
+    if count == 0 {
+        mesh.triangle(a, b, c);
+    } else {
+        let ab = midpoint(mesh, cache, a, b);
+        let bc = midpoint(mesh, cache, b, c);
+        let ca = midpoint(mesh, cache, c, a);
+
+        divide_triangle(mesh, cache, a, ab, ca, count - 1);
+        divide_triangle(mesh, cache, ab, b, bc, count - 1);
+        divide_triangle(mesh, cache, ca, bc, c, count - 1);
+        divide_triangle(mesh, cache, ab, bc, ca, count - 1);
+    }
+}
+
+// Sphere from an icosahedron with each face split in four `subdivisions`
+// times. Vertices are shared, so texture coordinates wrap across the seam
+// at the back.
+pub fn icosphere(radius: f32, subdivisions: usize) -> Mesh {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let corners = [
+        Vec3(-1.0, t, 0.0),
+        Vec3(1.0, t, 0.0),
+        Vec3(-1.0, -t, 0.0),
+        Vec3(1.0, -t, 0.0),
+        Vec3(0.0, -1.0, t),
+        Vec3(0.0, 1.0, t),
+        Vec3(0.0, -1.0, -t),
+        Vec3(0.0, 1.0, -t),
+        Vec3(t, 0.0, -1.0),
+        Vec3(t, 0.0, 1.0),
+        Vec3(-t, 0.0, -1.0),
+        Vec3(-t, 0.0, 1.0),
+    ];
+    let faces: [[u16; 3]; 20] = [
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    let mut mesh = Mesh::new();
+    for corner in corners.iter() {
+        sphere_vertex(&mut mesh, corner.normalize(), radius);
+    }
+    let mut cache = HashMap::new();
+    for &[a, b, c] in faces.iter() {
+        divide_triangle(
+            &mut mesh,
+            &mut cache,
+            a,
+            b,
+            c,
+            subdivisions.min(MAX_SUBDIVISIONS),
+        );
+    }
+    mesh
+}
+
+// Capped cylinder along the y axis
+pub fn cylinder(radius: f32, height: f32, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    let segments = segments.clamp(3, MAX_SEGMENTS);
+    mesh.parametric(segments, 1, |u, v| {
+        let (sin, cos) = angle(u).sin_cos();
+        let normal = Vec3(cos, 0.0, -sin);
+        (
+            Vec3(radius * cos, height * (v - 0.5), -radius * sin),
+            normal,
+        )
+    });
+    mesh.disc(radius, height / 2.0, segments, true);
+    mesh.disc(radius, -height / 2.0, segments, false);
+    mesh
+}
+
+// Cone along the y axis with its base at the bottom. The apex has a vertex
+// per segment so that each keeps the normal of its side.
+pub fn cone(radius: f32, height: f32, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    let segments = segments.clamp(3, MAX_SEGMENTS);
+    mesh.parametric(segments, 1, |u, v| {
+        let (sin, cos) = angle(u).sin_cos();
+        let normal = Vec3(height * cos, radius, -height * sin).normalize();
+        let r = radius * (1.0 - v);
+        (Vec3(r * cos, height * (v - 0.5), -r * sin), normal)
+    });
+    mesh.disc(radius, -height / 2.0, segments, false);
+    mesh
+}
+
+// Torus around the y axis. `segments` go around the y axis and `sides`
+// around the tube.
+pub fn torus(major_radius: f32, minor_radius: f32, segments: usize, sides: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    let segments = segments.clamp(3, MAX_SEGMENTS);
+    let sides = sides.clamp(3, MAX_SEGMENTS);
+    mesh.parametric(segments, sides, |u, v| {
+        let (sin_phi, cos_phi) = angle(u).sin_cos();
+        let (sin_theta, cos_theta) = angle(v).sin_cos();
+        let normal = Vec3(cos_theta * cos_phi, sin_theta, -cos_theta * sin_phi);
+        let center = Vec3(major_radius * cos_phi, 0.0, -major_radius * sin_phi);
+        (center + normal * minor_radius, normal)
+    });
+    mesh
+}
+
+// Flat grid in the xz plane facing up
+pub fn plane(width: f32, depth: f32, columns: usize, rows: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    let columns = columns.clamp(1, MAX_SEGMENTS);
+    let rows = rows.clamp(1, MAX_SEGMENTS);
+    mesh.parametric(columns, rows, |u, v| {
+        (
+            Vec3(width * (u - 0.5), 0.0, depth * (0.5 - v)),
+            Vec3(0.0, 1.0, 0.0),
+        )
+    });
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangles(mesh: &Mesh) -> impl Iterator<Item = [usize; 3]> + '_ {
+        mesh.indices
+            .chunks(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+    }
+
+    // Twice the area of the triangle, along its counterclockwise normal
+    fn face_normal(mesh: &Mesh, [a, b, c]: [usize; 3]) -> Vec3 {
+        let p = &mesh.positions;
+        (p[b] - p[a]).cross(&(p[c] - p[a]))
+    }
+
+    // Indices in range, unit normals, and every triangle wound
+    // counterclockwise around the normals of its corners
+    fn assert_well_formed(mesh: &Mesh) {
+        let count = mesh.positions.len();
+        assert!(count <= u16::MAX as usize + 1);
+        assert_eq!(mesh.normals.len(), count);
+        assert_eq!(mesh.uvs.len(), count);
+        assert_eq!(mesh.indices.len() % 3, 0);
+        assert!(mesh.indices.iter().all(|&i| (i as usize) < count));
+        for n in &mesh.normals {
+            assert!(
+                (n.length() - 1.0).abs() < 1e-5,
+                "{:?} is not a unit normal",
+                n
+            );
+        }
+        for t in triangles(mesh) {
+            let normal = mesh.normals[t[0]] + mesh.normals[t[1]] + mesh.normals[t[2]];
+            assert!(
+                face_normal(mesh, t).dot(&normal) > 0.0,
+                "{:?} is inside out",
+                t
+            );
+        }
+    }
+
+    // For the meshes that are convex around the origin
+    fn assert_normals_point_outward(mesh: &Mesh) {
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!(p.dot(n) > 0.0, "{:?} points inward at {:?}", n, p);
+        }
+    }
+
+    // Every edge, with vertices matched by position so that UV seams and
+    // repeated poles don't count as boundaries, is shared by exactly two
+    // triangles that run along it in opposite directions
+    fn assert_watertight(mesh: &Mesh) {
+        let mut welded = HashMap::new();
+        let ids: Vec<usize> = mesh
+            .positions
+            .iter()
+            .map(|p| {
+                let key = [p.0, p.1, p.2].map(|c| (c * 1e4).round() as i32);
+                let next = welded.len();
+                *welded.entry(key).or_insert(next)
+            })
+            .collect();
+
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for t in triangles(mesh) {
+            let [a, b, c] = t.map(|i| ids[i]);
+            assert!(a != b && b != c && c != a, "{:?} is degenerate", t);
+            for edge in [(a, b), (b, c), (c, a)].iter() {
+                *edges.entry(*edge).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1, "edge {:?} is used {} times", (a, b), count);
+            assert!(
+                edges.contains_key(&(b, a)),
+                "edge {:?} is on a boundary",
+                (a, b)
+            );
+        }
+    }
+
+    #[test]
+    fn closed_meshes_are_watertight() {
+        assert_watertight(&uv_sphere(1.0, 8, 4));
+        assert_watertight(&uv_sphere(2.0, 24, 12));
+        for subdivisions in 0..3 {
+            assert_watertight(&icosphere(1.5, subdivisions));
+        }
+        assert_watertight(&cylinder(1.0, 2.0, 12));
+        assert_watertight(&cylinder(0.5, 3.0, 5));
+        assert_watertight(&cone(1.0, 2.0, 12));
+        assert_watertight(&cone(2.0, 0.5, 7));
+        assert_watertight(&torus(2.0, 0.5, 16, 8));
+        assert_watertight(&torus(1.0, 0.25, 5, 3));
+    }
+
+    fn triangle_count(mesh: &Mesh) -> usize {
+        mesh.indices.len() / 3
+    }
+
+    #[test]
+    fn uv_sphere_counts_and_winding() {
+        let sphere = uv_sphere(2.0, 8, 4);
+        assert_eq!(sphere.positions.len(), 9 * 5);
+        // The triangles touching the poles collapse, one per segment
+        assert_eq!(triangle_count(&sphere), 2 * 8 * 4 - 2 * 8);
+        assert_well_formed(&sphere);
+        assert_normals_point_outward(&sphere);
+        for p in &sphere.positions {
+            assert!((p.length() - 2.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn icosphere_counts_and_winding() {
+        for subdivisions in 0..3 {
+            let sphere = icosphere(1.5, subdivisions);
+            let faces = 20 * 4usize.pow(subdivisions as u32);
+            assert_eq!(triangle_count(&sphere), faces);
+            // Euler: V - E + F = 2 with every edge shared by two faces
+            assert_eq!(sphere.positions.len(), faces / 2 + 2);
+            assert_well_formed(&sphere);
+            assert_normals_point_outward(&sphere);
+        }
+    }
+
+    #[test]
+    fn cylinder_counts_and_winding() {
+        let cylinder = cylinder(1.0, 2.0, 12);
+        // Two rows for the side, a center and a ring for each cap
+        assert_eq!(cylinder.positions.len(), 2 * 13 + 2 * 14);
+        assert_eq!(triangle_count(&cylinder), 2 * 12 + 2 * 12);
+        assert_well_formed(&cylinder);
+        assert_normals_point_outward(&cylinder);
+    }
+
+    #[test]
+    fn cone_counts_and_winding() {
+        let cone = cone(1.0, 2.0, 12);
+        assert_eq!(cone.positions.len(), 2 * 13 + 14);
+        // The side's upper triangles collapse into the apex
+        assert_eq!(triangle_count(&cone), 12 + 12);
+        assert_well_formed(&cone);
+        assert_normals_point_outward(&cone);
+    }
+
+    #[test]
+    fn torus_counts_and_winding() {
+        let (major, minor) = (2.0, 0.5);
+        let torus = torus(major, minor, 16, 8);
+        assert_eq!(torus.positions.len(), 17 * 9);
+        assert_eq!(triangle_count(&torus), 2 * 16 * 8);
+        assert_well_formed(&torus);
+        for (p, n) in torus.positions.iter().zip(&torus.normals) {
+            // Outward from the circle through the middle of the tube
+            let ring = Vec3(p.0, 0.0, p.2).normalize() * major;
+            assert!((*p - ring).dot(n) > 0.0);
+        }
+    }
+
+    #[test]
+    fn plane_counts_and_winding() {
+        let plane = plane(4.0, 2.0, 4, 3);
+        assert_eq!(plane.positions.len(), 5 * 4);
+        assert_eq!(triangle_count(&plane), 2 * 4 * 3);
+        assert_well_formed(&plane);
+        for t in triangles(&plane) {
+            assert!(face_normal(&plane, t).1 > 0.0);
+        }
+    }
+
+    #[test]
+    fn segment_counts_are_clamped() {
+        assert_eq!(uv_sphere(1.0, 0, 0), uv_sphere(1.0, 3, 2));
+        assert_eq!(cylinder(1.0, 1.0, 1), cylinder(1.0, 1.0, 3));
+        assert_eq!(plane(1.0, 1.0, 0, 0), plane(1.0, 1.0, 1, 1));
+        assert_eq!(
+            torus(1.0, 0.5, usize::MAX, 1000),
+            torus(1.0, 0.5, MAX_SEGMENTS, MAX_SEGMENTS)
+        );
+        assert_eq!(icosphere(1.0, 100), icosphere(1.0, MAX_SUBDIVISIONS));
+    }
+
+    #[test]
+    fn largest_meshes_fit_u16_indices() {
+        for mesh in &[
+            uv_sphere(1.0, MAX_SEGMENTS, MAX_SEGMENTS),
+            torus(1.0, 0.5, MAX_SEGMENTS, MAX_SEGMENTS),
+            plane(1.0, 1.0, MAX_SEGMENTS, MAX_SEGMENTS),
+            cylinder(1.0, 1.0, MAX_SEGMENTS),
+            icosphere(1.0, MAX_SUBDIVISIONS),
+        ] {
+            assert!(mesh.positions.len() <= u16::MAX as usize + 1);
+            assert_eq!(
+                mesh.indices.iter().map(|&i| i as usize).max(),
+                Some(mesh.positions.len() - 1)
+            );
+        }
+    }
+
+    #[test]
+    fn cube_has_four_vertices_and_two_triangles_per_face() {
+        let cube = cube();
+        assert_eq!(cube.positions.len(), 24);
+        assert_eq!(cube.normals.len(), 24);
+        assert_eq!(cube.uvs.len(), 24);
+        assert_eq!(cube.indices.len(), 36);
+        assert!(cube.indices.iter().all(|&i| i < 24));
+    }
+
+    #[test]
+    fn cube_faces_wind_counterclockwise_from_outside() {
+        let cube = cube();
+        for (i, t) in triangles(&cube).enumerate() {
+            let face = CUBE_FACES[i / 2];
+            assert!(t.iter().all(|&v| v / 4 == i / 2));
+            assert!(face_normal(&cube, t).dot(&face) > 0.0, "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn cube_faces_lie_on_their_planes() {
+        let cube = cube();
+        for (i, (p, n)) in cube.positions.iter().zip(&cube.normals).enumerate() {
+            let face = CUBE_FACES[i / 4];
+            assert_eq!(*n, face);
+            assert!((p.dot(&face) - 0.5).abs() < 1e-6);
+            assert!(p.0.abs() <= 0.5 && p.1.abs() <= 0.5 && p.2.abs() <= 0.5);
+        }
+    }
+
+    #[test]
+    fn cuboid_scales_each_axis() {
+        let cuboid = cuboid(Vec3(2.0, 4.0, 6.0));
+        for (i, p) in cuboid.positions.iter().enumerate() {
+            let extent = match i / 8 {
+                0 => p.0,
+                1 => p.1,
+                _ => p.2,
+            };
+            assert_eq!(extent.abs(), (i / 8 + 1) as f32);
+        }
+    }
+}
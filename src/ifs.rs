@@ -0,0 +1,320 @@
+use crate::linear_algebra::Vec2;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+pub const PRESETS: [&str; 5] = [
+    "barnsley-fern",
+    "sierpinski-gasket",
+    "sierpinski-carpet",
+    "dragon-curve",
+    "levy-c-curve",
+];
+
+// Top two rows of a 3x3 homogeneous matrix:
+// x' = a * x + b * y + c
+// y' = d * x + e * y + f
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AffineMap(pub [f32; 6]);
+
+impl AffineMap {
+    pub fn contraction(fixed_point: Vec2, ratio: f32) -> AffineMap {
+        let t = 1.0 - ratio;
+        AffineMap([ratio, 0.0, fixed_point.0 * t, 0.0, ratio, fixed_point.1 * t])
+    }
+
+    pub fn apply(&self, p: Vec2) -> Vec2 {
+        let [a, b, c, d, e, f] = self.0;
+        Vec2(a * p.0 + b * p.1 + c, d * p.0 + e * p.1 + f)
+    }
+}
+
+// JSON representation of a map set:
+// {"maps": [{"matrix": [a, b, c, d, e, f], "probability": p}, ...]}
+#[derive(Serialize, Deserialize)]
+struct MapSet {
+    maps: Vec<WeightedMap>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WeightedMap {
+    matrix: AffineMap,
+    probability: f32,
+}
+
+pub struct Ifs {
+    maps: Vec<AffineMap>,
+    probabilities: Vec<f32>,
+    distribution: WeightedIndex<f32>,
+}
+
+impl Ifs {
+    pub fn new(maps: Vec<(AffineMap, f32)>) -> Result<Ifs, String> {
+        let (maps, probabilities): (Vec<_>, Vec<_>) = maps.into_iter().unzip();
+        let distribution = WeightedIndex::new(&probabilities)
+            .map_err(|e| format!("invalid IFS probabilities: {}", e))?;
+
+        Ok(Ifs {
+            maps,
+            probabilities,
+            distribution,
+        })
+    }
+
+    pub fn sierpinski_gasket(vertices: &[Vec2; 3]) -> Ifs {
+        Ifs::new(
+            vertices
+                .iter()
+                .map(|v| (AffineMap::contraction(*v, 0.5), 1.0))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    pub fn barnsley_fern() -> Ifs {
+        Ifs::new(vec![
+            (AffineMap([0.0, 0.0, 0.0, 0.0, 0.16, 0.0]), 0.01),
+            (AffineMap([0.85, 0.04, 0.0, -0.04, 0.85, 1.6]), 0.85),
+            (AffineMap([0.2, -0.26, 0.0, 0.23, 0.22, 1.6]), 0.07),
+            (AffineMap([-0.15, 0.28, 0.0, 0.26, 0.24, 0.44]), 0.07),
+        ])
+        .unwrap()
+    }
+
+    pub fn sierpinski_carpet() -> Ifs {
+        let mut maps = vec![];
+        for i in 0..3 {
+            for j in 0..3 {
+                if i != 1 || j != 1 {
+                    let t = Vec2(i as f32, j as f32) * (1.0 / 3.0);
+                    maps.push((AffineMap([1.0 / 3.0, 0.0, t.0, 0.0, 1.0 / 3.0, t.1]), 1.0));
+                }
+            }
+        }
+        Ifs::new(maps).unwrap()
+    }
+
+    pub fn dragon_curve() -> Ifs {
+        Ifs::new(vec![
+            (AffineMap([0.5, -0.5, 0.0, 0.5, 0.5, 0.0]), 0.5),
+            (AffineMap([-0.5, -0.5, 1.0, 0.5, -0.5, 0.0]), 0.5),
+        ])
+        .unwrap()
+    }
+
+    pub fn levy_c_curve() -> Ifs {
+        Ifs::new(vec![
+            (AffineMap([0.5, -0.5, 0.0, 0.5, 0.5, 0.0]), 0.5),
+            (AffineMap([0.5, 0.5, 0.5, -0.5, 0.5, 0.5]), 0.5),
+        ])
+        .unwrap()
+    }
+
+    pub fn preset(name: &str) -> Option<Ifs> {
+        match name {
+            "barnsley-fern" => Some(Ifs::barnsley_fern()),
+            "sierpinski-gasket" => Some(Ifs::sierpinski_gasket(&[
+                Vec2(-1.0, -1.0),
+                Vec2(0.0, 1.0),
+                Vec2(1.0, -1.0),
+            ])),
+            "sierpinski-carpet" => Some(Ifs::sierpinski_carpet()),
+            "dragon-curve" => Some(Ifs::dragon_curve()),
+            "levy-c-curve" => Some(Ifs::levy_c_curve()),
+            _ => None,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Ifs, String> {
+        let map_set: MapSet =
+            serde_json::from_str(json).map_err(|e| format!("invalid IFS JSON: {}", e))?;
+        Ifs::new(
+            map_set
+                .maps
+                .into_iter()
+                .map(|m| (m.matrix, m.probability))
+                .collect(),
+        )
+    }
+
+    // One map per line so that the matrices stay readable in an editor
+    pub fn to_json(&self) -> String {
+        let maps: Vec<String> = self
+            .maps()
+            .map(|(matrix, probability)| {
+                serde_json::to_string(&WeightedMap {
+                    matrix: *matrix,
+                    probability,
+                })
+                .unwrap()
+            })
+            .collect();
+        format!("{{\n  \"maps\": [\n    {}\n  ]\n}}", maps.join(",\n    "))
+    }
+
+    pub fn maps(&self) -> impl Iterator<Item = (&AffineMap, f32)> {
+        self.maps.iter().zip(self.probabilities.iter().cloned())
+    }
+
+    pub fn chaos_game<R: Rng>(&self, rng: &mut R, start: Vec2, iterations: usize) -> Vec<Vec2> {
+        chaos_game(rng, &self.distribution, start, iterations, |i, p| {
+            self.maps[i].apply(p)
+        })
+    }
+}
+
+// Repeatedly picks a map index from `distribution` and applies it to the last
+// point, returning the starting point followed by every iterate.
+pub fn chaos_game<P, R, D, F>(
+    rng: &mut R,
+    distribution: &D,
+    start: P,
+    iterations: usize,
+    mut step: F,
+) -> Vec<P>
+where
+    P: Copy,
+    R: Rng,
+    D: Distribution<usize>,
+    F: FnMut(usize, P) -> P,
+{
+    let mut points = Vec::with_capacity(iterations + 1);
+    let mut p = start;
+    points.push(p);
+
+    for _ in 0..iterations {
+        p = step(distribution.sample(rng), p);
+        points.push(p);
+    }
+
+    points
+}
+
+// Bins `points` into a `width` x `height` grid (row 0 at the bottom), scaled
+// uniformly so that their bounding box fits with a small margin.
+pub fn density(points: &[Vec2], width: usize, height: usize) -> Vec<u32> {
+    let mut bins = vec![0; width * height];
+    if points.is_empty() {
+        return bins;
+    }
+
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in points {
+        min = Vec2(min.0.min(p.0), min.1.min(p.1));
+        max = Vec2(max.0.max(p.0), max.1.max(p.1));
+    }
+
+    let extent = (max.0 - min.0).max(max.1 - min.1).max(f32::EPSILON);
+    let scale = 0.95 * (width.min(height) as f32) / extent;
+    let offset = Vec2(
+        (width as f32 - (max.0 - min.0) * scale) / 2.0,
+        (height as f32 - (max.1 - min.1) * scale) / 2.0,
+    );
+
+    for p in points {
+        let x = ((p.0 - min.0) * scale + offset.0) as usize;
+        let y = ((p.1 - min.1) * scale + offset.1) as usize;
+        if x < width && y < height {
+            bins[y * width + x] += 1;
+        }
+    }
+
+    bins
+}
+
+// Log-density tone mapping into RGBA pixels, from white (empty) to red.
+pub fn tone_map(density: &[u32]) -> Vec<u8> {
+    let max = density.iter().cloned().max().unwrap_or(0);
+    let log_max = (1.0 + max as f32).ln().max(f32::EPSILON);
+
+    let mut pixels = Vec::with_capacity(density.len() * 4);
+    for &count in density {
+        let intensity = (1.0 + count as f32).ln() / log_max;
+        let shade = (255.0 * (1.0 - intensity)).round() as u8;
+        pixels.extend_from_slice(&[255, shade, shade, 255]);
+    }
+
+    pixels
+}
+
+pub fn seeded_rng(seed: Option<u32>) -> Pcg32 {
+    Pcg32::seed_from_u64(seed.map_or_else(rand::random, u64::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_of_no_points_is_empty() {
+        assert_eq!(density(&[], 3, 2), vec![0; 6]);
+    }
+
+    #[test]
+    fn density_centers_a_single_point() {
+        let bins = density(&[Vec2(5.0, -3.0)], 4, 4);
+        assert_eq!(bins.iter().sum::<u32>(), 1);
+        assert_eq!(bins[2 * 4 + 2], 1);
+    }
+
+    #[test]
+    fn tone_map_shades_from_white_to_red() {
+        assert!(tone_map(&[]).is_empty());
+        assert_eq!(tone_map(&[0, 0]), vec![255; 8]);
+        assert_eq!(tone_map(&[0, 1]), vec![255, 255, 255, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn presets_load_by_name() {
+        for name in PRESETS.iter() {
+            assert!(Ifs::preset(name).is_some(), "{}", name);
+        }
+        assert!(Ifs::preset("mandelbrot").is_none());
+    }
+
+    #[test]
+    fn presets_round_trip_through_json() {
+        for name in PRESETS.iter() {
+            let ifs = Ifs::preset(name).unwrap();
+            let loaded = Ifs::from_json(&ifs.to_json()).unwrap();
+            assert_eq!(
+                loaded.maps().collect::<Vec<_>>(),
+                ifs.maps().collect::<Vec<_>>(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn map_sets_are_read_from_json() {
+        let ifs = Ifs::from_json(
+            r#"{"maps": [
+                {"matrix": [0.5, 0, 0, 0, 0.5, 0], "probability": 1},
+                {"matrix": [0.5, 0, 0.5, 0, 0.5, 0], "probability": 3}
+            ]}"#,
+        )
+        .unwrap();
+        let maps: Vec<_> = ifs.maps().collect();
+        assert_eq!(maps.len(), 2);
+        assert_eq!(*maps[1].0, AffineMap([0.5, 0.0, 0.5, 0.0, 0.5, 0.0]));
+        assert_eq!(maps[1].1, 3.0);
+    }
+
+    #[test]
+    fn invalid_map_sets_are_rejected() {
+        for json in &[
+            "",
+            "not json",
+            "{}",
+            r#"{"maps": []}"#,
+            r#"{"maps": [{"matrix": [1, 0, 0, 0, 1], "probability": 1}]}"#,
+            r#"{"maps": [{"matrix": [1, 0, 0, 0, 1, 0]}]}"#,
+            r#"{"maps": [{"matrix": [1, 0, 0, 0, 1, 0], "probability": -1}]}"#,
+            r#"{"maps": [{"matrix": [1, 0, 0, 0, 1, 0], "probability": 0}]}"#,
+        ] {
+            assert!(Ifs::from_json(json).is_err(), "{}", json);
+        }
+    }
+}
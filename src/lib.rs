@@ -1,6 +1,16 @@
+mod color;
+mod geometry;
+mod ifs;
 mod linear_algebra;
+mod mesh;
+mod picking;
+mod post;
 mod programs;
+mod render_target;
+mod texture;
+mod triangulation;
 mod utils;
+mod wireframe;
 
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -38,8 +48,8 @@ pub fn triangle() -> Result<(), JsValue> {
 }
 
 #[wasm_bindgen]
-pub fn sierpinski_points() -> Result<(), JsValue> {
-    programs::sierpinski_points::run(get_context()?)
+pub fn sierpinski_points(seed: Option<u32>) -> Result<(), JsValue> {
+    programs::sierpinski_points::run(get_context()?, seed)
 }
 
 #[wasm_bindgen]
@@ -48,8 +58,8 @@ pub fn sierpinski() -> Result<(), JsValue> {
 }
 
 #[wasm_bindgen]
-pub fn sierpinski_3d_points() -> Result<(), JsValue> {
-    programs::sierpinski_3d_points::run(get_context()?)
+pub fn sierpinski_3d_points(seed: Option<u32>) -> Result<(), JsValue> {
+    programs::sierpinski_3d_points::run(get_context()?, seed)
 }
 
 #[wasm_bindgen]
@@ -57,6 +67,11 @@ pub fn sierpinski_3d() -> Result<(), JsValue> {
     programs::sierpinski_3d::run(get_context()?)
 }
 
+#[wasm_bindgen]
+pub fn ifs(seed: Option<u32>) -> Result<(), JsValue> {
+    programs::ifs::run(get_context()?, seed)
+}
+
 #[wasm_bindgen]
 pub fn twist() -> Result<(), JsValue> {
     programs::twist::run(get_context()?)
@@ -86,3 +101,33 @@ pub fn triangles() -> Result<(), JsValue> {
 pub fn cad() -> Result<(), JsValue> {
     programs::cad::run(get_context()?)
 }
+
+#[wasm_bindgen]
+pub fn cube() -> Result<(), JsValue> {
+    programs::cube::run(get_context()?)
+}
+
+#[wasm_bindgen]
+pub fn lighting() -> Result<(), JsValue> {
+    programs::lighting::run(get_context()?)
+}
+
+#[wasm_bindgen]
+pub fn textured_cube() -> Result<(), JsValue> {
+    programs::textured_cube::run(get_context()?)
+}
+
+#[wasm_bindgen]
+pub fn render_to_texture() -> Result<(), JsValue> {
+    programs::render_to_texture::run(get_context()?)
+}
+
+#[wasm_bindgen]
+pub fn picking() -> Result<(), JsValue> {
+    programs::picking::run(get_context()?)
+}
+
+#[wasm_bindgen]
+pub fn life() -> Result<(), JsValue> {
+    programs::life::run(get_context()?)
+}
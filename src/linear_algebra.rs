@@ -1,6 +1,6 @@
 use js_sys::Float32Array;
-use std::ops::{Add, Mul};
-#[derive(Copy, Clone)]
+use std::ops::{Add, Mul, Sub};
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec2(pub f32, pub f32);
 
 impl Vec2 {
@@ -9,7 +9,7 @@ impl Vec2 {
     pub fn flatten(vectors: &[Vec2]) -> Float32Array {
         let f32array = Float32Array::new_with_length((vectors.len() * 2) as u32);
 
-        for (idx, v) in vectors.into_iter().enumerate() {
+        for (idx, v) in vectors.iter().enumerate() {
             f32array.set_index((idx * 2) as u32, v.0);
             f32array.set_index((idx * 2 + 1) as u32, v.1);
         }
@@ -20,6 +20,19 @@ impl Vec2 {
     pub fn mix(&self, rhs: &Vec2, scale: f32) -> Vec2 {
         *self * scale + *rhs * (1.0 - scale)
     }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn dot(&self, rhs: &Vec2) -> f32 {
+        self.0 * rhs.0 + self.1 * rhs.1
+    }
+
+    // z component of the 3D cross product
+    pub fn cross(&self, rhs: &Vec2) -> f32 {
+        self.0 * rhs.1 - self.1 * rhs.0
+    }
 }
 
 impl Add for Vec2 {
@@ -29,21 +42,27 @@ impl Add for Vec2 {
     }
 }
 
+impl Sub for Vec2 {
+    type Output = Vec2;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec2(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
 impl Mul<f32> for Vec2 {
     type Output = Vec2;
     fn mul(self, rhs: f32) -> Self::Output {
         Vec2(self.0 * rhs, self.1 * rhs)
     }
 }
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec3(pub f32, pub f32, pub f32);
 
 impl Vec3 {
-    pub const SIZE: i32 = 32 / 8 * 3;
     pub fn flatten(vectors: &[Vec3]) -> Float32Array {
         let f32array = Float32Array::new_with_length((vectors.len() * 3) as u32);
 
-        for (idx, v) in vectors.into_iter().enumerate() {
+        for (idx, v) in vectors.iter().enumerate() {
             f32array.set_index((idx * 3) as u32, v.0);
             f32array.set_index((idx * 3 + 1) as u32, v.1);
             f32array.set_index((idx * 3 + 2) as u32, v.2);
@@ -55,6 +74,26 @@ impl Vec3 {
     pub fn mix(&self, rhs: &Vec3, scale: f32) -> Vec3 {
         *self * scale + *rhs * (1.0 - scale)
     }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn dot(&self, rhs: &Vec3) -> f32 {
+        self.0 * rhs.0 + self.1 * rhs.1 + self.2 * rhs.2
+    }
+
+    pub fn cross(&self, rhs: &Vec3) -> Vec3 {
+        Vec3(
+            self.1 * rhs.2 - self.2 * rhs.1,
+            self.2 * rhs.0 - self.0 * rhs.2,
+            self.0 * rhs.1 - self.1 * rhs.0,
+        )
+    }
+
+    pub fn normalize(&self) -> Vec3 {
+        *self * (1.0 / self.length())
+    }
 }
 
 impl Add for Vec3 {
@@ -64,21 +103,27 @@ impl Add for Vec3 {
     }
 }
 
+impl Sub for Vec3 {
+    type Output = Vec3;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec3(self.0 - rhs.0, self.1 - rhs.1, self.2 - rhs.2)
+    }
+}
+
 impl Mul<f32> for Vec3 {
     type Output = Vec3;
     fn mul(self, rhs: f32) -> Self::Output {
         Vec3(self.0 * rhs, self.1 * rhs, self.2 * rhs)
     }
 }
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec4(pub f32, pub f32, pub f32, pub f32);
 
 impl Vec4 {
-    pub const SIZE: i32 = 32 / 8 * 4;
     pub fn flatten(vectors: &[Vec4]) -> Float32Array {
         let f32array = Float32Array::new_with_length((vectors.len() * 4) as u32);
 
-        for (idx, v) in vectors.into_iter().enumerate() {
+        for (idx, v) in vectors.iter().enumerate() {
             f32array.set_index((idx * 4) as u32, v.0);
             f32array.set_index((idx * 4 + 1) as u32, v.1);
             f32array.set_index((idx * 4 + 2) as u32, v.2);
@@ -87,10 +132,6 @@ impl Vec4 {
 
         f32array
     }
-
-    pub fn mix(&self, rhs: &Vec4, scale: f32) -> Vec4 {
-        *self * scale + *rhs * (1.0 - scale)
-    }
 }
 
 impl Add for Vec4 {
@@ -105,9 +146,322 @@ impl Add for Vec4 {
     }
 }
 
+impl Sub for Vec4 {
+    type Output = Vec4;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec4(
+            self.0 - rhs.0,
+            self.1 - rhs.1,
+            self.2 - rhs.2,
+            self.3 - rhs.3,
+        )
+    }
+}
+
 impl Mul<f32> for Vec4 {
     type Output = Vec4;
     fn mul(self, rhs: f32) -> Self::Output {
         Vec4(self.0 * rhs, self.1 * rhs, self.2 * rhs, self.3 * rhs)
     }
 }
+
+// Column-major, as `uniform_matrix4fv` expects with `transpose` false
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4(pub [f32; 16]);
+
+impl Mat4 {
+    #[cfg(test)]
+    pub fn identity() -> Mat4 {
+        Mat4([
+            1.0, 0.0, 0.0, 0.0, //
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, //
+        ])
+    }
+
+    // Counterclockwise rotations by `angle` radians, looking down the axis
+    // towards the origin
+    pub fn rotation_x(angle: f32) -> Mat4 {
+        let (s, c) = angle.sin_cos();
+        Mat4([
+            1.0, 0.0, 0.0, 0.0, //
+            0.0, c, s, 0.0, //
+            0.0, -s, c, 0.0, //
+            0.0, 0.0, 0.0, 1.0, //
+        ])
+    }
+
+    pub fn rotation_y(angle: f32) -> Mat4 {
+        let (s, c) = angle.sin_cos();
+        Mat4([
+            c, 0.0, -s, 0.0, //
+            0.0, 1.0, 0.0, 0.0, //
+            s, 0.0, c, 0.0, //
+            0.0, 0.0, 0.0, 1.0, //
+        ])
+    }
+
+    pub fn rotation_z(angle: f32) -> Mat4 {
+        let (s, c) = angle.sin_cos();
+        Mat4([
+            c, s, 0.0, 0.0, //
+            -s, c, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, //
+        ])
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        Mat4([
+            1.0, 0.0, 0.0, 0.0, //
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0, //
+            offset.0, offset.1, offset.2, 1.0, //
+        ])
+    }
+
+    // Perspective projection looking down -z, like `gluPerspective`, with
+    // `fov_y` in radians
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let d = near - far;
+        Mat4([
+            f / aspect,
+            0.0,
+            0.0,
+            0.0, //
+            0.0,
+            f,
+            0.0,
+            0.0, //
+            0.0,
+            0.0,
+            (far + near) / d,
+            -1.0, //
+            0.0,
+            0.0,
+            2.0 * far * near / d,
+            0.0, //
+        ])
+    }
+
+    // Maps the box from (left, bottom, -near) to (right, top, -far) onto
+    // clip space, like `glOrtho`
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+        let (w, h, d) = (right - left, top - bottom, far - near);
+        Mat4([
+            2.0 / w,
+            0.0,
+            0.0,
+            0.0, //
+            0.0,
+            2.0 / h,
+            0.0,
+            0.0, //
+            0.0,
+            0.0,
+            -2.0 / d,
+            0.0, //
+            -(right + left) / w,
+            -(top + bottom) / h,
+            -(far + near) / d,
+            1.0, //
+        ])
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [0.0; 16];
+        for column in 0..4 {
+            for row in 0..4 {
+                m[column * 4 + row] = (0..4)
+                    .map(|k| self.0[k * 4 + row] * rhs.0[column * 4 + k])
+                    .sum();
+            }
+        }
+        Mat4(m)
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+    fn mul(self, rhs: Vec4) -> Self::Output {
+        let m = &self.0;
+        let row = |i: usize| m[i] * rhs.0 + m[4 + i] * rhs.1 + m[8 + i] * rhs.2 + m[12 + i] * rhs.3;
+        Vec4(row(0), row(1), row(2), row(3))
+    }
+}
+
+// Column-major, like `Mat4`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat3(pub [f32; 9]);
+
+impl Mat3 {
+    // The rotation and scale part of a transform
+    pub fn from_mat4(m: &Mat4) -> Mat3 {
+        let m = &m.0;
+        Mat3([
+            m[0], m[1], m[2], //
+            m[4], m[5], m[6], //
+            m[8], m[9], m[10], //
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat3 {
+        let m = &self.0;
+        Mat3([
+            m[0], m[3], m[6], //
+            m[1], m[4], m[7], //
+            m[2], m[5], m[8], //
+        ])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        m[0] * (m[4] * m[8] - m[7] * m[5]) - m[3] * (m[1] * m[8] - m[7] * m[2])
+            + m[6] * (m[1] * m[5] - m[4] * m[2])
+    }
+
+    // `None` when the columns are too close to lying in a plane. The test is
+    // relative to the largest volume columns of their lengths could span, so
+    // small but well-shaped transforms, such as a scale by 0.001, still invert.
+    pub fn inverse(&self) -> Option<Mat3> {
+        let m = &self.0;
+        let det = self.determinant();
+        let column = |i: usize| Vec3(m[3 * i], m[3 * i + 1], m[3 * i + 2]).length();
+        if det == 0.0 || det.abs() <= f32::EPSILON * column(0) * column(1) * column(2) {
+            return None;
+        }
+        // Transposed cofactors over the determinant
+        let adjugate = [
+            m[4] * m[8] - m[5] * m[7],
+            m[2] * m[7] - m[1] * m[8],
+            m[1] * m[5] - m[2] * m[4],
+            m[5] * m[6] - m[3] * m[8],
+            m[0] * m[8] - m[2] * m[6],
+            m[2] * m[3] - m[0] * m[5],
+            m[3] * m[7] - m[4] * m[6],
+            m[1] * m[6] - m[0] * m[7],
+            m[0] * m[4] - m[1] * m[3],
+        ];
+        Some(Mat3(adjugate.map(|c| c / det)))
+    }
+
+    // Transforms normals the way `model_view` transforms positions: the
+    // inverse transpose of its upper 3x3, which keeps normals perpendicular
+    // to surfaces under non-uniform scaling. A singular transform flattens
+    // everything anyway, so its 3x3 is used as it is.
+    pub fn normal_matrix(model_view: &Mat4) -> Mat3 {
+        let m = Mat3::from_mat4(model_view);
+        m.inverse().map_or(m, |inverse| inverse.transpose())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Mat3, b: &Mat3) {
+        for (x, y) in a.0.iter().zip(&b.0) {
+            assert!(
+                (x - y).abs() < 1e-5 * y.abs().max(1.0),
+                "{:?} != {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    fn identity() -> Mat3 {
+        Mat3::from_mat4(&Mat4::identity())
+    }
+
+    fn scale(s: Vec3) -> Mat4 {
+        let mut m = Mat4::identity();
+        m.0[0] = s.0;
+        m.0[5] = s.1;
+        m.0[10] = s.2;
+        m
+    }
+
+    fn product(a: &Mat3, b: &Mat3) -> Mat3 {
+        let mut m = [0.0; 9];
+        for column in 0..3 {
+            for row in 0..3 {
+                m[column * 3 + row] = (0..3).map(|k| a.0[k * 3 + row] * b.0[column * 3 + k]).sum();
+            }
+        }
+        Mat3(m)
+    }
+
+    #[test]
+    fn identity_is_its_own_inverse() {
+        assert_eq!(identity().inverse(), Some(identity()));
+        assert_eq!(Mat3::normal_matrix(&Mat4::identity()), identity());
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mat3::from_mat4(
+            &(Mat4::rotation_x(0.3) * Mat4::rotation_y(1.2) * scale(Vec3(2.0, 0.5, 3.0))),
+        );
+        let inverse = m.inverse().unwrap();
+        assert_close(&product(&m, &inverse), &identity());
+        assert_close(&product(&inverse, &m), &identity());
+    }
+
+    #[test]
+    fn small_uniform_scales_are_invertible() {
+        let m = Mat3::from_mat4(&scale(Vec3(0.001, 0.001, 0.001)));
+        assert!(m.determinant().abs() < f32::EPSILON);
+        assert_close(
+            &m.inverse().unwrap(),
+            &Mat3::from_mat4(&scale(Vec3(1000.0, 1000.0, 1000.0))),
+        );
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(Mat3::from_mat4(&scale(Vec3(1.0, 0.0, 1.0))).inverse(), None);
+        // Two parallel columns
+        let m = Mat3([1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 0.0, 1.0, 0.0]);
+        assert_eq!(m.inverse(), None);
+        // A singular transform's normals are left to its 3x3
+        let flat = scale(Vec3(1.0, 0.0, 1.0));
+        assert_eq!(Mat3::normal_matrix(&flat), Mat3::from_mat4(&flat));
+    }
+
+    #[test]
+    fn rotation_normal_matrix_is_the_rotation() {
+        let rotation = Mat4::rotation_z(0.7) * Mat4::rotation_x(-1.1);
+        let model_view = Mat4::translation(Vec3(1.0, -2.0, 3.0)) * rotation;
+        assert_close(
+            &Mat3::normal_matrix(&model_view),
+            &Mat3::from_mat4(&rotation),
+        );
+    }
+
+    #[test]
+    fn non_uniform_scale_keeps_normals_perpendicular() {
+        let model_view = Mat4::rotation_y(0.4) * scale(Vec3(4.0, 1.0, 0.5));
+        let normal_matrix = Mat3::normal_matrix(&model_view);
+        let (m, n) = (Mat3::from_mat4(&model_view).0, normal_matrix.0);
+        let apply = |m: &[f32; 9], v: Vec3| {
+            Vec3(
+                m[0] * v.0 + m[3] * v.1 + m[6] * v.2,
+                m[1] * v.0 + m[4] * v.1 + m[7] * v.2,
+                m[2] * v.0 + m[5] * v.1 + m[8] * v.2,
+            )
+        };
+
+        // A tangent and a normal of the plane x + y + z = 0
+        let tangent = Vec3(1.0, -1.0, 0.0);
+        let normal = Vec3(1.0, 1.0, 1.0);
+        assert!(apply(&m, tangent).dot(&apply(&n, normal)).abs() < 1e-5);
+        // Transforming the normal like a position would tilt it
+        assert!(apply(&m, tangent).dot(&apply(&m, normal)).abs() > 1.0);
+    }
+}
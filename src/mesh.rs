@@ -0,0 +1,266 @@
+use crate::linear_algebra::{Vec2, Vec3};
+use js_sys::Float32Array;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use web_sys::WebGlRenderingContext as Gl;
+
+pub trait Vertex: Copy {
+    const COMPONENTS: i32;
+    type Key: Eq + Hash;
+
+    // Identifies vertices at the same position, so that triangles sharing a
+    // corner in a non-indexed stream are recognised as sharing an edge.
+    fn key(&self) -> Self::Key;
+    fn flatten(vertices: &[Self]) -> Float32Array;
+}
+
+impl Vertex for Vec2 {
+    const COMPONENTS: i32 = 2;
+    type Key = [u32; 2];
+
+    fn key(&self) -> Self::Key {
+        // Adding 0.0 folds -0.0 into 0.0
+        [(self.0 + 0.0).to_bits(), (self.1 + 0.0).to_bits()]
+    }
+
+    fn flatten(vertices: &[Self]) -> Float32Array {
+        Vec2::flatten(vertices)
+    }
+}
+
+impl Vertex for Vec3 {
+    const COMPONENTS: i32 = 3;
+    type Key = [u32; 3];
+
+    fn key(&self) -> Self::Key {
+        [
+            (self.0 + 0.0).to_bits(),
+            (self.1 + 0.0).to_bits(),
+            (self.2 + 0.0).to_bits(),
+        ]
+    }
+
+    fn flatten(vertices: &[Self]) -> Float32Array {
+        Vec3::flatten(vertices)
+    }
+}
+
+// Index triples of the triangles `draw_arrays(mode, 0, count)` would assemble,
+// keeping the winding of every triangle consistent with the first.
+pub fn triangle_indices(mode: u32, count: usize) -> Result<Vec<[usize; 3]>, String> {
+    match mode {
+        Gl::TRIANGLES => Ok((0..count / 3)
+            .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+            .collect()),
+        Gl::TRIANGLE_STRIP => Ok((0..count.saturating_sub(2))
+            .map(|i| {
+                if i % 2 == 0 {
+                    [i, i + 1, i + 2]
+                } else {
+                    [i + 1, i, i + 2]
+                }
+            })
+            .collect()),
+        Gl::TRIANGLE_FAN => Ok((1..count.saturating_sub(1))
+            .map(|i| [0, i, i + 1])
+            .collect()),
+        _ => Err(format!("{:#x} is not a triangle primitive", mode)),
+    }
+}
+
+// Vertex indices of every primitive `draw_arrays(mode, 0, count)` would
+// assemble, in drawing order: one per point, two per line, three per triangle.
+pub fn primitive_indices(mode: u32, count: usize) -> Result<Vec<Vec<usize>>, String> {
+    match mode {
+        Gl::POINTS => Ok((0..count).map(|i| vec![i]).collect()),
+        Gl::LINES => Ok((0..count / 2).map(|i| vec![2 * i, 2 * i + 1]).collect()),
+        Gl::LINE_STRIP => Ok((0..count.saturating_sub(1))
+            .map(|i| vec![i, i + 1])
+            .collect()),
+        Gl::LINE_LOOP => {
+            let mut lines = primitive_indices(Gl::LINE_STRIP, count)?;
+            if count >= 2 {
+                lines.push(vec![count - 1, 0]);
+            }
+            Ok(lines)
+        }
+        Gl::TRIANGLES | Gl::TRIANGLE_STRIP | Gl::TRIANGLE_FAN => Ok(triangle_indices(mode, count)?
+            .iter()
+            .map(|t| t.to_vec())
+            .collect()),
+        _ => Err(format!("{:#x} is not a primitive mode", mode)),
+    }
+}
+
+// Converts a triangle stream into a LINES stream with every shared edge drawn
+// once.
+pub fn edges<V: Vertex>(mode: u32, vertices: &[V]) -> Result<Vec<V>, String> {
+    let mut ids = HashMap::new();
+    let ids: Vec<usize> = vertices
+        .iter()
+        .enumerate()
+        .map(|(i, v)| *ids.entry(v.key()).or_insert(i))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut lines = vec![];
+    for [a, b, c] in triangle_indices(mode, vertices.len())? {
+        for &(from, to) in &[(a, b), (b, c), (c, a)] {
+            let (from, to) = (ids[from], ids[to]);
+            if from != to && seen.insert((from.min(to), from.max(to))) {
+                lines.push(vertices[from]);
+                lines.push(vertices[to]);
+            }
+        }
+    }
+
+    Ok(lines)
+}
+
+// Per-vertex barycentric coordinates for a TRIANGLES stream of `triangles`
+// triangles.
+pub fn barycentric(triangles: usize) -> Vec<Vec3> {
+    let corners = [
+        Vec3(1.0, 0.0, 0.0),
+        Vec3(0.0, 1.0, 0.0),
+        Vec3(0.0, 0.0, 1.0),
+    ];
+    corners
+        .iter()
+        .cloned()
+        .cycle()
+        .take(triangles * 3)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangles_are_taken_three_at_a_time() {
+        assert_eq!(
+            triangle_indices(Gl::TRIANGLES, 7),
+            Ok(vec![[0, 1, 2], [3, 4, 5]])
+        );
+    }
+
+    #[test]
+    fn strip_winding_alternates() {
+        assert_eq!(
+            triangle_indices(Gl::TRIANGLE_STRIP, 5),
+            Ok(vec![[0, 1, 2], [2, 1, 3], [2, 3, 4]])
+        );
+        assert_eq!(triangle_indices(Gl::TRIANGLE_STRIP, 2), Ok(vec![]));
+    }
+
+    #[test]
+    fn fan_shares_the_first_vertex() {
+        assert_eq!(
+            triangle_indices(Gl::TRIANGLE_FAN, 5),
+            Ok(vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]])
+        );
+    }
+
+    #[test]
+    fn points_and_lines() {
+        assert_eq!(
+            primitive_indices(Gl::POINTS, 3),
+            Ok(vec![vec![0], vec![1], vec![2]])
+        );
+        // A trailing vertex without a partner is dropped
+        assert_eq!(
+            primitive_indices(Gl::LINES, 5),
+            Ok(vec![vec![0, 1], vec![2, 3]])
+        );
+        assert_eq!(
+            primitive_indices(Gl::LINE_STRIP, 3),
+            Ok(vec![vec![0, 1], vec![1, 2]])
+        );
+        assert_eq!(primitive_indices(Gl::LINE_STRIP, 1), Ok(vec![]));
+    }
+
+    #[test]
+    fn line_loop_closes() {
+        assert_eq!(
+            primitive_indices(Gl::LINE_LOOP, 3),
+            Ok(vec![vec![0, 1], vec![1, 2], vec![2, 0]])
+        );
+        assert_eq!(
+            primitive_indices(Gl::LINE_LOOP, 2),
+            Ok(vec![vec![0, 1], vec![1, 0]])
+        );
+        assert_eq!(primitive_indices(Gl::LINE_LOOP, 1), Ok(vec![]));
+    }
+
+    #[test]
+    fn triangle_primitives_match_triangle_indices() {
+        // The remainder past the last whole triangle is dropped
+        assert_eq!(
+            primitive_indices(Gl::TRIANGLES, 8),
+            Ok(vec![vec![0, 1, 2], vec![3, 4, 5]])
+        );
+        // Odd strip triangles swap their first two vertices
+        assert_eq!(
+            primitive_indices(Gl::TRIANGLE_STRIP, 6),
+            Ok(vec![
+                vec![0, 1, 2],
+                vec![2, 1, 3],
+                vec![2, 3, 4],
+                vec![4, 3, 5],
+            ])
+        );
+        assert_eq!(
+            primitive_indices(Gl::TRIANGLE_FAN, 4),
+            Ok(vec![vec![0, 1, 2], vec![0, 2, 3]])
+        );
+        assert!(primitive_indices(0x7f, 3).is_err());
+    }
+
+    #[test]
+    fn lines_are_not_triangles() {
+        assert!(triangle_indices(Gl::LINES, 6).is_err());
+        assert!(edges(Gl::LINE_STRIP, &[Vec2(0.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn shared_edges_are_drawn_once() {
+        // Two triangles of a unit square, sharing the diagonal
+        let (a, b, c, d) = (
+            Vec2(0.0, 0.0),
+            Vec2(1.0, 0.0),
+            Vec2(1.0, 1.0),
+            Vec2(0.0, 1.0),
+        );
+        let lines = edges(Gl::TRIANGLES, &[a, b, c, a, c, d]).unwrap();
+        assert_eq!(lines, vec![a, b, b, c, c, a, c, d, d, a]);
+    }
+
+    #[test]
+    fn negative_zero_is_the_same_vertex() {
+        let triangles = [
+            Vec2(0.0, 0.0),
+            Vec2(1.0, 0.0),
+            Vec2(0.0, 1.0),
+            Vec2(-0.0, 0.0),
+            Vec2(0.0, 1.0),
+            Vec2(-1.0, 0.0),
+        ];
+        assert_eq!(edges(Gl::TRIANGLES, &triangles).unwrap().len(), 2 * 5);
+    }
+
+    #[test]
+    fn degenerate_edges_are_dropped() {
+        let p = Vec2(0.5, 0.5);
+        let lines = edges(Gl::TRIANGLE_STRIP, &[p, p, Vec2(1.0, 0.0)]).unwrap();
+        assert_eq!(lines, vec![p, Vec2(1.0, 0.0)]);
+    }
+
+    #[test]
+    fn barycentric_corners_repeat_per_triangle() {
+        let corners = barycentric(2);
+        assert_eq!(corners.len(), 6);
+        assert_eq!(corners[0], corners[3]);
+        assert_eq!(corners[2], Vec3(0.0, 0.0, 1.0));
+    }
+}
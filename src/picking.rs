@@ -0,0 +1,261 @@
+use crate::{
+    linear_algebra::Mat4,
+    render_target::RenderTarget,
+    texture::{Filter, Options, Wrap},
+    utils,
+};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGlProgram, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+// Ids are written as colors, with their 24 bits in red, green and blue and
+// an opaque alpha. The buffer is cleared to transparent black, so id 0 is
+// still told apart from the background.
+pub const MAX_ID: u32 = 0xff_ffff;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 a_position;
+
+uniform mat4 u_matrix;
+
+void main() {
+    gl_Position = u_matrix * a_position;
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform vec4 u_id;
+
+void main() {
+    gl_FragColor = u_id;
+}
+"#;
+
+// Filtering would blend neighbouring ids into ids of other objects
+const OPTIONS: Options = Options {
+    wrap_s: Wrap::ClampToEdge,
+    wrap_t: Wrap::ClampToEdge,
+    min_filter: Filter::Nearest,
+    mag_filter: Filter::Nearest,
+};
+
+pub fn id_to_rgba(id: u32) -> Option<[u8; 4]> {
+    if id > MAX_ID {
+        return None;
+    }
+    Some([(id >> 16) as u8, (id >> 8) as u8, id as u8, 255])
+}
+
+// None for the background
+pub fn rgba_to_id(rgba: [u8; 4]) -> Option<u32> {
+    match rgba {
+        [r, g, b, 255] => Some((r as u32) << 16 | (g as u32) << 8 | b as u32),
+        _ => None,
+    }
+}
+
+// The distinct ids in RGBA pixels, in increasing order
+pub fn pixels_to_ids(pixels: &[u8]) -> Vec<u32> {
+    pixels
+        .chunks_exact(4)
+        .filter_map(|p| rgba_to_id([p[0], p[1], p[2], p[3]]))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+// The pixels between corners `a` and `b`, inclusive, as `(x, y, width,
+// height)` for `read_pixels`. Corners are in canvas pixels with y down, while
+// `read_pixels` counts y up from the bottom. None if the rectangle lies
+// outside the `width` by `height` buffer.
+pub fn read_region(
+    a: (i32, i32),
+    b: (i32, i32),
+    width: u32,
+    height: u32,
+) -> Option<(i32, i32, i32, i32)> {
+    let left = a.0.min(b.0).max(0);
+    let right = a.0.max(b.0).min(width as i32 - 1);
+    let top = a.1.min(b.1).max(0);
+    let bottom = a.1.max(b.1).min(height as i32 - 1);
+    if left > right || top > bottom {
+        return None;
+    }
+    Some((
+        left,
+        height as i32 - 1 - bottom,
+        right - left + 1,
+        bottom - top + 1,
+    ))
+}
+
+// Finds which object is at a pixel by drawing every object in a flat color
+// encoding its id into an offscreen buffer, then reading the buffer back.
+// A frame of picking goes:
+//
+//     picker.begin(context);
+//     for each object: picker.object(context, id, matrix)?, then draw it
+//         with its positions in `picker.a_position`
+//     picker.pick(context, point)? or picker.pick_rect(context, a, b)?
+//     picker.end(context, width, height);
+//
+// `begin` changes the clear color and program, and leaves blending off.
+pub struct Picker {
+    target: RenderTarget,
+    program: WebGlProgram,
+    pub a_position: u32,
+    u_matrix: WebGlUniformLocation,
+    u_id: WebGlUniformLocation,
+}
+
+impl Picker {
+    pub fn new(context: &Gl, width: u32, height: u32) -> Result<Picker, JsValue> {
+        let vertex_shader = utils::compile_shader(context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+        let fragment_shader =
+            utils::compile_shader(context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let a_position = match context.get_attrib_location(&program, "a_position") {
+            -1 => Err("unable to get location for a_position"),
+            p => Ok(p as u32),
+        }?;
+        let u_matrix = context
+            .get_uniform_location(&program, "u_matrix")
+            .ok_or("unable to get location for u_matrix")?;
+        let u_id = context
+            .get_uniform_location(&program, "u_id")
+            .ok_or("unable to get location for u_id")?;
+
+        Ok(Picker {
+            target: RenderTarget::new(context, width, height, OPTIONS, true)?,
+            program,
+            a_position,
+            u_matrix,
+            u_id,
+        })
+    }
+
+    pub fn begin(&self, context: &Gl) {
+        self.target.bind(context);
+        // Dithering and blending would change the ids as they are written
+        context.disable(Gl::DITHER);
+        context.disable(Gl::BLEND);
+        context.enable(Gl::DEPTH_TEST);
+        context.clear_color(0.0, 0.0, 0.0, 0.0);
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        context.use_program(Some(&self.program));
+    }
+
+    // Sets the id and transform of the object drawn next
+    pub fn object(&self, context: &Gl, id: u32, matrix: &Mat4) -> Result<(), String> {
+        let [r, g, b, a] = id_to_rgba(id).ok_or(format!("id {} is above {}", id, MAX_ID))?;
+        let channel = |c: u8| c as f32 / 255.0;
+        context.uniform4f(
+            Some(&self.u_id),
+            channel(r),
+            channel(g),
+            channel(b),
+            channel(a),
+        );
+        context.uniform_matrix4fv_with_f32_array(Some(&self.u_matrix), false, &matrix.0);
+        Ok(())
+    }
+
+    // The object at `point` in canvas pixels, if any
+    pub fn pick(&self, context: &Gl, point: (i32, i32)) -> Result<Option<u32>, JsValue> {
+        Ok(self.pick_rect(context, point, point)?.first().copied())
+    }
+
+    // Every object with a pixel showing between corners `a` and `b`
+    pub fn pick_rect(
+        &self,
+        context: &Gl,
+        a: (i32, i32),
+        b: (i32, i32),
+    ) -> Result<Vec<u32>, JsValue> {
+        let (x, y, width, height) = match read_region(a, b, self.target.width, self.target.height) {
+            Some(region) => region,
+            None => return Ok(vec![]),
+        };
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        context.read_pixels_with_opt_u8_array(
+            x,
+            y,
+            width,
+            height,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        )?;
+        Ok(pixels_to_ids(&pixels))
+    }
+
+    // Goes back to drawing to the canvas, `width` by `height` pixels
+    pub fn end(&self, context: &Gl, width: u32, height: u32) {
+        context.enable(Gl::DITHER);
+        RenderTarget::unbind(context, width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_id_round_trips() {
+        for id in 0..=MAX_ID {
+            assert_eq!(id_to_rgba(id).and_then(rgba_to_id), Some(id));
+        }
+    }
+
+    #[test]
+    fn ids_are_split_across_the_color_channels() {
+        assert_eq!(id_to_rgba(0), Some([0, 0, 0, 255]));
+        assert_eq!(id_to_rgba(0xff), Some([0, 0, 0xff, 255]));
+        assert_eq!(id_to_rgba(0x100), Some([0, 1, 0, 255]));
+        assert_eq!(id_to_rgba(0x1_0000), Some([1, 0, 0, 255]));
+        assert_eq!(id_to_rgba(MAX_ID), Some([0xff, 0xff, 0xff, 255]));
+    }
+
+    #[test]
+    fn ids_past_the_maximum_have_no_color() {
+        assert_eq!(id_to_rgba(MAX_ID + 1), None);
+        assert_eq!(id_to_rgba(u32::MAX), None);
+    }
+
+    #[test]
+    fn transparent_pixels_are_background() {
+        assert_eq!(rgba_to_id([0, 0, 0, 0]), None);
+        assert_eq!(rgba_to_id([1, 2, 3, 0]), None);
+        assert_eq!(rgba_to_id([1, 2, 3, 128]), None);
+    }
+
+    #[test]
+    fn pixels_give_distinct_sorted_ids() {
+        let mut pixels = vec![];
+        for &id in &[7, 3, 7, 0] {
+            pixels.extend_from_slice(&id_to_rgba(id).unwrap());
+        }
+        pixels.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(pixels_to_ids(&pixels), vec![0, 3, 7]);
+        assert_eq!(pixels_to_ids(&[0; 16]), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn read_region_flips_y() {
+        assert_eq!(read_region((0, 0), (0, 0), 10, 8), Some((0, 7, 1, 1)));
+        assert_eq!(read_region((9, 7), (9, 7), 10, 8), Some((9, 0, 1, 1)));
+        assert_eq!(read_region((3, 5), (1, 2), 10, 8), Some((1, 2, 3, 4)));
+        assert_eq!(read_region((1, 2), (3, 5), 10, 8), Some((1, 2, 3, 4)));
+    }
+
+    #[test]
+    fn read_region_clips_to_the_buffer() {
+        assert_eq!(read_region((-5, -5), (20, 20), 10, 8), Some((0, 0, 10, 8)));
+        assert_eq!(read_region((8, 6), (12, 12), 10, 8), Some((8, 0, 2, 2)));
+        assert_eq!(read_region((11, 0), (15, 3), 10, 8), None);
+        assert_eq!(read_region((-3, -3), (-1, -1), 10, 8), None);
+    }
+}
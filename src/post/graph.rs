@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+
+// Fragment shaders a pass can run. They all read their inputs at the same
+// texture coordinate the pass writes to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Shader {
+    Copy,
+    Grayscale,
+    Invert,
+    // One direction of a separable gaussian blur
+    Blur,
+    Sobel,
+    // Keeps only colors brighter than a threshold, for bloom
+    Threshold,
+    // Adds a scaled second input to the first, for bloom
+    Combine,
+    Fxaa,
+}
+
+impl Shader {
+    pub fn inputs(self) -> usize {
+        match self {
+            Shader::Combine => 2,
+            _ => 1,
+        }
+    }
+
+    // The float uniforms the shader reads, with their defaults
+    pub fn uniforms(self) -> &'static [(&'static str, f32)] {
+        match self {
+            Shader::Grayscale => &[("amount", 1.0)],
+            Shader::Blur => &[("sigma", 2.0), ("horizontal", 1.0)],
+            Shader::Sobel => &[("strength", 1.0)],
+            Shader::Threshold => &[("threshold", 0.7)],
+            Shader::Combine => &[("intensity", 1.0)],
+            Shader::Copy | Shader::Invert | Shader::Fxaa => &[],
+        }
+    }
+}
+
+// An image passes read and write. Only `Scene`, the captured frame, exists
+// up front; each `Buffer` is written by exactly one pass.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Scene,
+    Buffer(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pass {
+    pub shader: Shader,
+    pub inputs: Vec<Resource>,
+    pub output: Resource,
+    pub uniforms: Vec<(String, f32)>,
+}
+
+// Where a planned pass reads from or draws to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Scene,
+    // Index of an offscreen render target
+    Target(usize),
+    Screen,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub shader: Shader,
+    pub inputs: Vec<Slot>,
+    pub output: Slot,
+    pub uniforms: Vec<(String, f32)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+    // Render targets the steps need at once
+    pub targets: usize,
+}
+
+// Effects that can be named in a chain, with the settings each accepts
+const EFFECTS: [(&str, &[&str]); 6] = [
+    ("grayscale", &["amount"]),
+    ("invert", &[]),
+    ("blur", &["sigma"]),
+    ("sobel", &["strength"]),
+    ("bloom", &["threshold", "intensity", "sigma"]),
+    ("fxaa", &[]),
+];
+
+struct Builder {
+    passes: Vec<Pass>,
+    buffers: usize,
+}
+
+impl Builder {
+    // Adds a pass with the shader's default uniforms overridden by any of
+    // `settings` it reads, returning what it writes
+    fn pass(
+        &mut self,
+        shader: Shader,
+        inputs: Vec<Resource>,
+        settings: &[(String, f32)],
+    ) -> Resource {
+        let output = Resource::Buffer(self.buffers);
+        self.buffers += 1;
+        let uniforms = shader
+            .uniforms()
+            .iter()
+            .map(|&(name, default)| {
+                let value = settings
+                    .iter()
+                    .rev()
+                    .find(|(key, _)| key == name)
+                    .map_or(default, |&(_, value)| value);
+                (String::from(name), value)
+            })
+            .collect();
+        self.passes.push(Pass {
+            shader,
+            inputs,
+            output,
+            uniforms,
+        });
+        output
+    }
+
+    fn blur(&mut self, input: Resource, settings: &[(String, f32)]) -> Resource {
+        let mut settings = settings.to_vec();
+        settings.push((String::from("horizontal"), 1.0));
+        let horizontal = self.pass(Shader::Blur, vec![input], &settings);
+        settings.push((String::from("horizontal"), 0.0));
+        self.pass(Shader::Blur, vec![horizontal], &settings)
+    }
+
+    fn effect(
+        &mut self,
+        name: &str,
+        input: Resource,
+        settings: &[(String, f32)],
+    ) -> Result<Resource, String> {
+        Ok(match name {
+            "grayscale" => self.pass(Shader::Grayscale, vec![input], settings),
+            "invert" => self.pass(Shader::Invert, vec![input], settings),
+            "blur" => self.blur(input, settings),
+            "sobel" => self.pass(Shader::Sobel, vec![input], settings),
+            "bloom" => {
+                let bright = self.pass(Shader::Threshold, vec![input], settings);
+                let glow = self.blur(bright, settings);
+                self.pass(Shader::Combine, vec![input, glow], settings)
+            }
+            "fxaa" => self.pass(Shader::Fxaa, vec![input], settings),
+            _ => return Err(format!("unknown effect {:?}", name)),
+        })
+    }
+}
+
+// Parses a chain like "bloom threshold=0.6, blur sigma=3, fxaa" into passes,
+// each effect reading what the one before it wrote. Returns the passes and
+// the resource holding the final image.
+pub fn parse(chain: &str) -> Result<(Vec<Pass>, Resource), String> {
+    let mut builder = Builder {
+        passes: vec![],
+        buffers: 0,
+    };
+    let mut current = Resource::Scene;
+
+    for effect in chain.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let mut words = effect.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let keys = EFFECTS
+            .iter()
+            .find(|(effect, _)| *effect == name)
+            .map(|(_, keys)| *keys)
+            .ok_or(format!("unknown effect {:?}", name))?;
+
+        let settings = words
+            .map(|setting| {
+                let (key, value) = setting
+                    .split_once('=')
+                    .ok_or(format!("expected key=value in {:?}", setting))?;
+                if !keys.contains(&key) {
+                    return Err(format!("{} has no setting {:?}", name, key));
+                }
+                let value: f32 = value
+                    .parse()
+                    .ok()
+                    .filter(|v: &f32| v.is_finite())
+                    .ok_or(format!("invalid value for {} {}", name, key))?;
+                Ok((String::from(key), value))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        current = builder.effect(name, current, &settings)?;
+    }
+
+    Ok((builder.passes, current))
+}
+
+// Orders the passes needed for `output` so that each runs after the passes
+// it reads from, and assigns every intermediate image a render target.
+// Targets are reused once nothing later reads them, but a pass never draws
+// into a target it is reading.
+pub fn plan(passes: &[Pass], output: Resource) -> Result<Plan, String> {
+    let mut writers = HashMap::new();
+    for (i, pass) in passes.iter().enumerate() {
+        if pass.output == Resource::Scene {
+            return Err(format!("pass {} writes to the scene", i));
+        }
+        if pass.inputs.len() != pass.shader.inputs() {
+            return Err(format!(
+                "pass {} has {} inputs, {:?} needs {}",
+                i,
+                pass.inputs.len(),
+                pass.shader,
+                pass.shader.inputs()
+            ));
+        }
+        if writers.insert(pass.output, i).is_some() {
+            return Err(format!(
+                "{:?} is written by more than one pass",
+                pass.output
+            ));
+        }
+    }
+
+    // Depth first from the output, so unused passes are left out
+    fn visit(
+        resource: Resource,
+        passes: &[Pass],
+        writers: &HashMap<Resource, usize>,
+        state: &mut HashMap<usize, bool>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        if resource == Resource::Scene {
+            return Ok(());
+        }
+        let &i = writers
+            .get(&resource)
+            .ok_or(format!("nothing writes {:?}", resource))?;
+        match state.get(&i) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(format!("pass {} depends on itself", i)),
+            None => {}
+        }
+        state.insert(i, false);
+        for &input in &passes[i].inputs {
+            visit(input, passes, writers, state, order)?;
+        }
+        state.insert(i, true);
+        order.push(i);
+        Ok(())
+    }
+
+    let mut order = vec![];
+    visit(output, passes, &writers, &mut HashMap::new(), &mut order)?;
+
+    let mut last_read = HashMap::new();
+    for (step, &i) in order.iter().enumerate() {
+        for &input in &passes[i].inputs {
+            last_read.insert(input, step);
+        }
+    }
+
+    let mut slots = HashMap::new();
+    let mut free: Vec<usize> = vec![];
+    let mut targets = 0;
+    let mut steps = vec![];
+    for (step, &i) in order.iter().enumerate() {
+        let pass = &passes[i];
+        let inputs = pass
+            .inputs
+            .iter()
+            .map(|input| match input {
+                Resource::Scene => Slot::Scene,
+                buffer => slots[buffer],
+            })
+            .collect();
+
+        let output = if pass.output == output {
+            Slot::Screen
+        } else {
+            // Lowest free target first, so allocation is predictable
+            free.sort_unstable_by(|a, b| b.cmp(a));
+            let target = free.pop().unwrap_or_else(|| {
+                targets += 1;
+                targets - 1
+            });
+            slots.insert(pass.output, Slot::Target(target));
+            Slot::Target(target)
+        };
+
+        for input in &pass.inputs {
+            if last_read.get(input) == Some(&step) {
+                if let Some(Slot::Target(target)) = slots.get(input) {
+                    if !free.contains(target) {
+                        free.push(*target);
+                    }
+                }
+            }
+        }
+
+        steps.push(Step {
+            shader: pass.shader,
+            inputs,
+            output,
+            uniforms: pass.uniforms.clone(),
+        });
+    }
+
+    // An empty chain still has to put the scene on screen
+    if steps.is_empty() {
+        steps.push(Step {
+            shader: Shader::Copy,
+            inputs: vec![Slot::Scene],
+            output: Slot::Screen,
+            uniforms: vec![],
+        });
+    }
+
+    Ok(Plan { steps, targets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_chain(chain: &str) -> Plan {
+        let (passes, output) = parse(chain).unwrap();
+        plan(&passes, output).unwrap()
+    }
+
+    fn pass(shader: Shader, inputs: Vec<Resource>, output: Resource) -> Pass {
+        Pass {
+            shader,
+            inputs,
+            output,
+            uniforms: vec![],
+        }
+    }
+
+    #[test]
+    fn empty_chain_copies_the_scene() {
+        for chain in &["", " , "] {
+            let plan = plan_chain(chain);
+            assert_eq!(plan.targets, 0);
+            assert_eq!(
+                plan.steps,
+                vec![Step {
+                    shader: Shader::Copy,
+                    inputs: vec![Slot::Scene],
+                    output: Slot::Screen,
+                    uniforms: vec![],
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn blur_is_two_passes() {
+        let plan = plan_chain("blur sigma=3");
+        let shaders: Vec<Shader> = plan.steps.iter().map(|s| s.shader).collect();
+        assert_eq!(shaders, vec![Shader::Blur, Shader::Blur]);
+        assert_eq!(plan.steps[0].inputs, vec![Slot::Scene]);
+        assert_eq!(plan.steps[1].inputs, vec![plan.steps[0].output]);
+        assert_eq!(plan.steps[1].output, Slot::Screen);
+
+        let uniform =
+            |step: &Step, name: &str| step.uniforms.iter().find(|(key, _)| key == name).unwrap().1;
+        assert_eq!(uniform(&plan.steps[0], "horizontal"), 1.0);
+        assert_eq!(uniform(&plan.steps[1], "horizontal"), 0.0);
+        assert!(plan.steps.iter().all(|s| uniform(s, "sigma") == 3.0));
+    }
+
+    #[test]
+    fn bloom_combines_the_scene_with_its_blurred_highlights() {
+        let plan = plan_chain("bloom");
+        let shaders: Vec<Shader> = plan.steps.iter().map(|s| s.shader).collect();
+        assert_eq!(
+            shaders,
+            vec![
+                Shader::Threshold,
+                Shader::Blur,
+                Shader::Blur,
+                Shader::Combine
+            ]
+        );
+        let combine = &plan.steps[3];
+        assert_eq!(combine.inputs, vec![Slot::Scene, plan.steps[2].output]);
+        assert_eq!(combine.output, Slot::Screen);
+
+        // Bloom on an earlier effect combines with that effect's output
+        let plan = plan_chain("invert, bloom");
+        let combine = plan.steps.last().unwrap();
+        assert_eq!(combine.shader, Shader::Combine);
+        assert_eq!(combine.inputs[0], plan.steps[0].output);
+    }
+
+    #[test]
+    fn targets_are_reused() {
+        assert_eq!(plan_chain("invert").targets, 0);
+        assert_eq!(plan_chain("invert, grayscale, sobel").targets, 2);
+        assert_eq!(plan_chain("blur, blur").targets, 2);
+    }
+
+    #[test]
+    fn no_step_draws_into_a_target_it_reads() {
+        for chain in &[
+            "blur, blur",
+            "bloom, fxaa",
+            "grayscale, bloom sigma=4, invert, blur",
+            "bloom, bloom",
+        ] {
+            let plan = plan_chain(chain);
+            for step in &plan.steps {
+                assert!(
+                    !step.inputs.contains(&step.output),
+                    "{:?} in {:?}",
+                    step,
+                    chain
+                );
+                if let Slot::Target(target) = step.output {
+                    assert!(target < plan.targets);
+                }
+            }
+            assert_eq!(plan.steps.last().unwrap().output, Slot::Screen);
+        }
+    }
+
+    #[test]
+    fn invalid_chains_are_rejected() {
+        assert!(parse("sharpen").is_err());
+        assert!(parse("blur radius=2").is_err());
+        assert!(parse("blur sigma").is_err());
+        assert!(parse("blur sigma=fast").is_err());
+        assert!(parse("blur sigma=inf").is_err());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let passes = vec![
+            pass(
+                Shader::Invert,
+                vec![Resource::Buffer(1)],
+                Resource::Buffer(0),
+            ),
+            pass(
+                Shader::Invert,
+                vec![Resource::Buffer(0)],
+                Resource::Buffer(1),
+            ),
+        ];
+        assert!(plan(&passes, Resource::Buffer(1)).is_err());
+
+        let own_input = vec![pass(
+            Shader::Invert,
+            vec![Resource::Buffer(0)],
+            Resource::Buffer(0),
+        )];
+        assert!(plan(&own_input, Resource::Buffer(0)).is_err());
+    }
+
+    #[test]
+    fn duplicate_writers_are_rejected() {
+        let passes = vec![
+            pass(Shader::Invert, vec![Resource::Scene], Resource::Buffer(0)),
+            pass(Shader::Sobel, vec![Resource::Scene], Resource::Buffer(0)),
+        ];
+        assert!(plan(&passes, Resource::Buffer(0)).is_err());
+    }
+
+    #[test]
+    fn malformed_passes_are_rejected() {
+        let writes_scene = vec![pass(Shader::Invert, vec![Resource::Scene], Resource::Scene)];
+        assert!(plan(&writes_scene, Resource::Scene).is_err());
+
+        let missing_input = vec![pass(
+            Shader::Combine,
+            vec![Resource::Scene],
+            Resource::Buffer(0),
+        )];
+        assert!(plan(&missing_input, Resource::Buffer(0)).is_err());
+
+        assert!(plan(&[], Resource::Buffer(0)).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+pub mod graph;
+mod shaders;
+
+use self::graph::{Plan, Shader, Slot};
+use crate::{
+    render_target::RenderTarget,
+    texture::{Filter, Options, Texture, Wrap},
+    utils,
+};
+use std::collections::HashMap;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlTexture,
+    WebGlUniformLocation,
+};
+
+// Intermediate images are the size of the canvas, which is rarely a power of
+// two
+const TARGET_OPTIONS: Options = Options {
+    wrap_s: Wrap::ClampToEdge,
+    wrap_t: Wrap::ClampToEdge,
+    min_filter: Filter::Linear,
+    mag_filter: Filter::Linear,
+};
+
+// Switched off while passes run, since they draw every pixel as it is
+const CAPABILITIES: [u32; 5] = [
+    Gl::BLEND,
+    Gl::CULL_FACE,
+    Gl::DEPTH_TEST,
+    Gl::SCISSOR_TEST,
+    Gl::STENCIL_TEST,
+];
+
+struct PassProgram {
+    program: WebGlProgram,
+    a_position: u32,
+    // Uniforms the compiler optimized away have no location
+    u_inputs: [Option<WebGlUniformLocation>; 2],
+    u_texel: Option<WebGlUniformLocation>,
+    uniforms: HashMap<&'static str, Option<WebGlUniformLocation>>,
+}
+
+impl PassProgram {
+    fn new(context: &Gl, shader: Shader) -> Result<PassProgram, JsValue> {
+        let vertex_shader =
+            utils::compile_shader(context, Gl::VERTEX_SHADER, shaders::VERTEX_SHADER_SRC)?;
+        let fragment_shader = utils::compile_shader(
+            context,
+            Gl::FRAGMENT_SHADER,
+            &shaders::fragment_shader_src(shader),
+        )?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let a_position = match context.get_attrib_location(&program, "a_position") {
+            -1 => Err("unable to get location for a_position"),
+            p => Ok(p as u32),
+        }?;
+        let uniforms = shader
+            .uniforms()
+            .iter()
+            .map(|&(name, _)| {
+                let location = context.get_uniform_location(&program, &format!("u_{}", name));
+                (name, location)
+            })
+            .collect();
+
+        Ok(PassProgram {
+            u_inputs: [
+                context.get_uniform_location(&program, "u_input0"),
+                context.get_uniform_location(&program, "u_input1"),
+            ],
+            u_texel: context.get_uniform_location(&program, "u_texel"),
+            a_position,
+            uniforms,
+            program,
+        })
+    }
+}
+
+// The WebGL state passes change, so that the program whose frame is being
+// post-processed finds everything as it left it. WebGL 1 has no vertex array
+// objects, so attribute pointers are global and have to be put back too.
+struct SavedState {
+    program: Option<WebGlProgram>,
+    array_buffer: Option<WebGlBuffer>,
+    framebuffer: Option<WebGlFramebuffer>,
+    active_texture: u32,
+    textures: [Option<WebGlTexture>; 2],
+    viewport: Vec<i32>,
+    capabilities: Vec<(u32, bool)>,
+    attributes: Vec<SavedAttribute>,
+}
+
+struct SavedAttribute {
+    index: u32,
+    enabled: bool,
+    buffer: Option<WebGlBuffer>,
+    size: i32,
+    kind: u32,
+    normalized: bool,
+    stride: i32,
+    offset: f64,
+}
+
+impl SavedAttribute {
+    fn save(context: &Gl, index: u32) -> Result<SavedAttribute, JsValue> {
+        let number = |pname| -> Result<f64, JsValue> {
+            Ok(context
+                .get_vertex_attrib(index, pname)?
+                .as_f64()
+                .unwrap_or_default())
+        };
+        let flag = |pname| -> Result<bool, JsValue> {
+            Ok(context
+                .get_vertex_attrib(index, pname)?
+                .as_bool()
+                .unwrap_or_default())
+        };
+        Ok(SavedAttribute {
+            index,
+            enabled: flag(Gl::VERTEX_ATTRIB_ARRAY_ENABLED)?,
+            buffer: context
+                .get_vertex_attrib(index, Gl::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING)?
+                .dyn_into()
+                .ok(),
+            size: number(Gl::VERTEX_ATTRIB_ARRAY_SIZE)? as i32,
+            kind: number(Gl::VERTEX_ATTRIB_ARRAY_TYPE)? as u32,
+            normalized: flag(Gl::VERTEX_ATTRIB_ARRAY_NORMALIZED)?,
+            stride: number(Gl::VERTEX_ATTRIB_ARRAY_STRIDE)? as i32,
+            offset: context.get_vertex_attrib_offset(index, Gl::VERTEX_ATTRIB_ARRAY_POINTER),
+        })
+    }
+
+    fn restore(&self, context: &Gl) {
+        if let Some(buffer) = &self.buffer {
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(buffer));
+            context.vertex_attrib_pointer_with_f64(
+                self.index,
+                self.size,
+                self.kind,
+                self.normalized,
+                self.stride,
+                self.offset,
+            );
+        }
+        if self.enabled {
+            context.enable_vertex_attrib_array(self.index);
+        } else {
+            context.disable_vertex_attrib_array(self.index);
+        }
+    }
+}
+
+impl SavedState {
+    fn save(context: &Gl, attributes: &[u32]) -> Result<SavedState, JsValue> {
+        let active_texture = context
+            .get_parameter(Gl::ACTIVE_TEXTURE)?
+            .as_f64()
+            .unwrap_or(Gl::TEXTURE0 as f64) as u32;
+        let mut textures = [None, None];
+        for (unit, texture) in textures.iter_mut().enumerate() {
+            context.active_texture(Gl::TEXTURE0 + unit as u32);
+            *texture = context
+                .get_parameter(Gl::TEXTURE_BINDING_2D)?
+                .dyn_into()
+                .ok();
+        }
+        context.active_texture(active_texture);
+
+        Ok(SavedState {
+            program: context.get_parameter(Gl::CURRENT_PROGRAM)?.dyn_into().ok(),
+            array_buffer: context
+                .get_parameter(Gl::ARRAY_BUFFER_BINDING)?
+                .dyn_into()
+                .ok(),
+            framebuffer: context
+                .get_parameter(Gl::FRAMEBUFFER_BINDING)?
+                .dyn_into()
+                .ok(),
+            active_texture,
+            textures,
+            viewport: context
+                .get_parameter(Gl::VIEWPORT)?
+                .dyn_into::<js_sys::Int32Array>()?
+                .to_vec(),
+            capabilities: CAPABILITIES
+                .iter()
+                .map(|&capability| (capability, context.is_enabled(capability)))
+                .collect(),
+            attributes: attributes
+                .iter()
+                .map(|&index| SavedAttribute::save(context, index))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn restore(&self, context: &Gl) {
+        for attribute in &self.attributes {
+            attribute.restore(context);
+        }
+        context.bind_buffer(Gl::ARRAY_BUFFER, self.array_buffer.as_ref());
+        context.use_program(self.program.as_ref());
+        context.bind_framebuffer(Gl::FRAMEBUFFER, self.framebuffer.as_ref());
+        for (unit, texture) in self.textures.iter().enumerate() {
+            context.active_texture(Gl::TEXTURE0 + unit as u32);
+            context.bind_texture(Gl::TEXTURE_2D, texture.as_ref());
+        }
+        context.active_texture(self.active_texture);
+        if let [x, y, width, height] = self.viewport[..] {
+            context.viewport(x, y, width, height);
+        }
+        for &(capability, enabled) in &self.capabilities {
+            if enabled {
+                context.enable(capability);
+            } else {
+                context.disable(capability);
+            }
+        }
+    }
+}
+
+// Runs a planned chain of full-screen passes over a copy of the canvas
+pub struct Chain {
+    context: Gl,
+    plan: Plan,
+    programs: HashMap<Shader, PassProgram>,
+    triangle: WebGlBuffer,
+    scene: Texture,
+    targets: Vec<RenderTarget>,
+}
+
+impl Chain {
+    pub fn new(context: &Gl, chain: &str) -> Result<Chain, JsValue> {
+        let triangle = context
+            .create_buffer()
+            .ok_or("failed to create a_position buffer")?;
+        let bound = context
+            .get_parameter(Gl::ARRAY_BUFFER_BINDING)?
+            .dyn_into::<WebGlBuffer>()
+            .ok();
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&triangle));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0][..]),
+            Gl::STATIC_DRAW,
+        );
+        context.bind_buffer(Gl::ARRAY_BUFFER, bound.as_ref());
+
+        let scene = Texture::empty(
+            context,
+            context.drawing_buffer_width() as u32,
+            context.drawing_buffer_height() as u32,
+            TARGET_OPTIONS,
+        )?;
+        let mut post = Chain {
+            context: context.clone(),
+            plan: graph::plan(&[], graph::Resource::Scene)?,
+            programs: HashMap::new(),
+            triangle,
+            scene,
+            targets: vec![],
+        };
+        post.set_chain(chain)?;
+        Ok(post)
+    }
+
+    pub fn set_chain(&mut self, chain: &str) -> Result<(), JsValue> {
+        let (passes, output) = graph::parse(chain)?;
+        let plan = graph::plan(&passes, output)?;
+        for step in &plan.steps {
+            if !self.programs.contains_key(&step.shader) {
+                let program = PassProgram::new(&self.context, step.shader)?;
+                self.programs.insert(step.shader, program);
+            }
+        }
+        self.plan = plan;
+        self.allocate()
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (
+            self.context.drawing_buffer_width() as u32,
+            self.context.drawing_buffer_height() as u32,
+        )
+    }
+
+    // Makes the scene texture and render targets match the plan and canvas
+    fn allocate(&mut self) -> Result<(), JsValue> {
+        let context = &self.context;
+        let (width, height) = self.size();
+        if (self.scene.width, self.scene.height) != (width, height) {
+            let scene = Texture::empty(context, width, height, TARGET_OPTIONS)?;
+            std::mem::replace(&mut self.scene, scene).delete(context);
+        }
+        for target in &mut self.targets {
+            target.resize(context, width, height)?;
+        }
+        while self.targets.len() < self.plan.targets {
+            let target = RenderTarget::new(context, width, height, TARGET_OPTIONS, false)?;
+            self.targets.push(target);
+        }
+        for target in self.targets.drain(self.plan.targets..) {
+            target.delete(context);
+        }
+        Ok(())
+    }
+
+    fn attributes(&self) -> Vec<u32> {
+        let mut attributes: Vec<u32> = self.programs.values().map(|p| p.a_position).collect();
+        attributes.sort_unstable();
+        attributes.dedup();
+        attributes
+    }
+
+    // Copies what the canvas currently shows. Unless the context preserves
+    // its drawing buffer, this has to happen in the same frame the program
+    // drew in.
+    pub fn capture(&mut self) -> Result<(), JsValue> {
+        self.allocate()?;
+        let context = &self.context;
+        let state = SavedState::save(context, &[])?;
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        context.active_texture(Gl::TEXTURE0);
+        context.bind_texture(Gl::TEXTURE_2D, Some(&self.scene.texture));
+        context.copy_tex_sub_image_2d(
+            Gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            0,
+            0,
+            self.scene.width as i32,
+            self.scene.height as i32,
+        );
+        state.restore(context);
+        Ok(())
+    }
+
+    fn texture(&self, slot: Slot) -> Result<&WebGlTexture, String> {
+        match slot {
+            Slot::Scene => Ok(&self.scene.texture),
+            Slot::Target(i) => Ok(&self.targets[i].color.texture),
+            Slot::Screen => Err(String::from("the screen can't be read by a pass")),
+        }
+    }
+
+    // Draws the captured frame through the passes onto the canvas
+    pub fn render(&self) -> Result<(), JsValue> {
+        let state = SavedState::save(&self.context, &self.attributes())?;
+        let result = self.draw_steps();
+        // Even after a failed step, so the program's own drawing carries on
+        state.restore(&self.context);
+        result
+    }
+
+    fn draw_steps(&self) -> Result<(), JsValue> {
+        let context = &self.context;
+        for &capability in CAPABILITIES.iter() {
+            context.disable(capability);
+        }
+        let (width, height) = self.size();
+
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.triangle));
+        for step in &self.plan.steps {
+            let program = &self.programs[&step.shader];
+            context.use_program(Some(&program.program));
+            context.vertex_attrib_pointer_with_i32(program.a_position, 2, Gl::FLOAT, false, 0, 0);
+            context.enable_vertex_attrib_array(program.a_position);
+
+            for (unit, &input) in step.inputs.iter().enumerate() {
+                context.active_texture(Gl::TEXTURE0 + unit as u32);
+                context.bind_texture(Gl::TEXTURE_2D, Some(self.texture(input)?));
+                context.uniform1i(program.u_inputs[unit].as_ref(), unit as i32);
+            }
+            context.uniform2f(
+                program.u_texel.as_ref(),
+                1.0 / width as f32,
+                1.0 / height as f32,
+            );
+            for (name, value) in &step.uniforms {
+                if let Some(location) = program.uniforms.get(name.as_str()) {
+                    context.uniform1f(location.as_ref(), *value);
+                }
+            }
+
+            match step.output {
+                Slot::Screen => RenderTarget::unbind(context, width, height),
+                Slot::Target(i) => self.targets[i].bind(context),
+                Slot::Scene => return Err(JsValue::from_str("a pass can't draw to the scene")),
+            }
+            context.draw_arrays(Gl::TRIANGLES, 0, 3);
+        }
+        Ok(())
+    }
+}
+
+// Post-processing for whichever program is drawing on the page's canvas,
+// driven from JavaScript:
+//
+//     const post = new PostProcessing("bloom threshold=0.5, fxaa")
+//     post.capture(); post.render()  // once, straight after a still image
+//     post.start(console.error)      // every frame, after an animated program
+#[wasm_bindgen]
+pub struct PostProcessing {
+    chain: Rc<RefCell<Chain>>,
+}
+
+#[wasm_bindgen]
+impl PostProcessing {
+    #[wasm_bindgen(constructor)]
+    pub fn new(chain: &str) -> Result<PostProcessing, JsValue> {
+        let context = crate::get_context()?;
+        Ok(PostProcessing {
+            chain: Rc::new(RefCell::new(Chain::new(&context, chain)?)),
+        })
+    }
+
+    pub fn set_chain(&self, chain: &str) -> Result<(), JsValue> {
+        self.chain.borrow_mut().set_chain(chain)
+    }
+
+    pub fn capture(&self) -> Result<(), JsValue> {
+        self.chain.borrow_mut().capture()
+    }
+
+    pub fn render(&self) -> Result<(), JsValue> {
+        self.chain.borrow().render()
+    }
+
+    // Must be called after the program's own render loop has started, so
+    // that each frame is captured after the program draws it. The first error
+    // stops post-processing and is passed to `on_error`.
+    pub fn start(&self, on_error: Option<js_sys::Function>) {
+        let chain = self.chain.clone();
+        let mut failed = false;
+        utils::render_loop(move || {
+            if failed {
+                return;
+            }
+            let mut chain = chain.borrow_mut();
+            if let Err(e) = chain.capture().and_then(|()| chain.render()) {
+                failed = true;
+                if let Some(on_error) = &on_error {
+                    let _ = on_error.call1(&JsValue::NULL, &e);
+                }
+            }
+        });
+    }
+}
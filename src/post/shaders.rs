@@ -0,0 +1,163 @@
+use super::graph::Shader;
+
+// A single triangle covering the whole viewport, from a_position of
+// (-1, -1), (3, -1) and (-1, 3)
+pub const VERTEX_SHADER_SRC: &str = r#"
+attribute vec2 a_position;
+
+varying vec2 f_uv;
+
+void main() {
+    f_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+// Declarations every fragment shader starts with. `u_texel` is the size of
+// one pixel of the inputs in texture coordinates.
+const HEADER_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_input0;
+uniform sampler2D u_input1;
+uniform vec2 u_texel;
+
+varying vec2 f_uv;
+
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+"#;
+
+const COPY_SRC: &str = r#"
+void main() {
+    gl_FragColor = texture2D(u_input0, f_uv);
+}
+"#;
+
+const GRAYSCALE_SRC: &str = r#"
+uniform float u_amount;
+
+void main() {
+    vec4 color = texture2D(u_input0, f_uv);
+    vec3 gray = vec3(dot(color.rgb, LUMA));
+    gl_FragColor = vec4(mix(color.rgb, gray, u_amount), color.a);
+}
+"#;
+
+const INVERT_SRC: &str = r#"
+void main() {
+    vec4 color = texture2D(u_input0, f_uv);
+    gl_FragColor = vec4(1.0 - color.rgb, color.a);
+}
+"#;
+
+// Taps out to 3 sigma, with weights normalized so that the image keeps its
+// brightness
+const BLUR_SRC: &str = r#"
+uniform float u_sigma;
+uniform float u_horizontal;
+
+const int RADIUS = 12;
+
+void main() {
+    vec2 direction = u_texel * vec2(u_horizontal, 1.0 - u_horizontal);
+    float sigma = max(u_sigma, 0.01);
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int i = -RADIUS; i <= RADIUS; i++) {
+        float x = float(i);
+        if (abs(x) > 3.0 * sigma) {
+            continue;
+        }
+        float weight = exp(-x * x / (2.0 * sigma * sigma));
+        sum += weight * texture2D(u_input0, f_uv + x * direction);
+        total += weight;
+    }
+    gl_FragColor = sum / total;
+}
+"#;
+
+const SOBEL_SRC: &str = r#"
+uniform float u_strength;
+
+float luma(float x, float y) {
+    return dot(texture2D(u_input0, f_uv + vec2(x, y) * u_texel).rgb, LUMA);
+}
+
+void main() {
+    float gx = -luma(-1.0, -1.0) - 2.0 * luma(-1.0, 0.0) - luma(-1.0, 1.0)
+        + luma(1.0, -1.0) + 2.0 * luma(1.0, 0.0) + luma(1.0, 1.0);
+    float gy = -luma(-1.0, -1.0) - 2.0 * luma(0.0, -1.0) - luma(1.0, -1.0)
+        + luma(-1.0, 1.0) + 2.0 * luma(0.0, 1.0) + luma(1.0, 1.0);
+    float edge = clamp(length(vec2(gx, gy)) * u_strength, 0.0, 1.0);
+    gl_FragColor = vec4(vec3(edge), 1.0);
+}
+"#;
+
+const THRESHOLD_SRC: &str = r#"
+uniform float u_threshold;
+
+void main() {
+    vec4 color = texture2D(u_input0, f_uv);
+    float luma = dot(color.rgb, LUMA);
+    gl_FragColor = vec4(color.rgb * max(luma - u_threshold, 0.0) / max(luma, 0.0001), 1.0);
+}
+"#;
+
+const COMBINE_SRC: &str = r#"
+uniform float u_intensity;
+
+void main() {
+    vec4 color = texture2D(u_input0, f_uv);
+    vec3 glow = texture2D(u_input1, f_uv).rgb;
+    gl_FragColor = vec4(color.rgb + u_intensity * glow, color.a);
+}
+"#;
+
+// The widely used compact FXAA: blur along the local edge direction, unless
+// that reaches outside the range of the neighbourhood
+const FXAA_SRC: &str = r#"
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+vec3 tap(vec2 offset) {
+    return texture2D(u_input0, f_uv + offset).rgb;
+}
+
+void main() {
+    float nw = dot(tap(vec2(-1.0, -1.0) * u_texel), LUMA);
+    float ne = dot(tap(vec2(1.0, -1.0) * u_texel), LUMA);
+    float sw = dot(tap(vec2(-1.0, 1.0) * u_texel), LUMA);
+    float se = dot(tap(vec2(1.0, 1.0) * u_texel), LUMA);
+    vec4 center = texture2D(u_input0, f_uv);
+    float m = dot(center.rgb, LUMA);
+
+    float luma_min = min(m, min(min(nw, ne), min(sw, se)));
+    float luma_max = max(m, max(max(nw, ne), max(sw, se)));
+
+    vec2 dir = vec2(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+    float reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, -SPAN_MAX, SPAN_MAX) * u_texel;
+
+    vec3 a = 0.5 * (tap(dir * (1.0 / 3.0 - 0.5)) + tap(dir * (2.0 / 3.0 - 0.5)));
+    vec3 b = 0.5 * a + 0.25 * (tap(dir * -0.5) + tap(dir * 0.5));
+    float luma_b = dot(b, LUMA);
+
+    gl_FragColor = vec4(luma_b < luma_min || luma_b > luma_max ? a : b, center.a);
+}
+"#;
+
+pub fn fragment_shader_src(shader: Shader) -> String {
+    let body = match shader {
+        Shader::Copy => COPY_SRC,
+        Shader::Grayscale => GRAYSCALE_SRC,
+        Shader::Invert => INVERT_SRC,
+        Shader::Blur => BLUR_SRC,
+        Shader::Sobel => SOBEL_SRC,
+        Shader::Threshold => THRESHOLD_SRC,
+        Shader::Combine => COMBINE_SRC,
+        Shader::Fxaa => FXAA_SRC,
+    };
+    format!("{}{}", HEADER_SRC, body)
+}
@@ -0,0 +1,170 @@
+use crate::{
+    linear_algebra::{Vec2, Vec4},
+    triangulation,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Vertex {
+    pub position: Vec2,
+    pub color: Vec4,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub vertices: Vec<Vertex>,
+    // Index into `Document::layers`
+    pub layer: usize,
+}
+
+impl Polygon {
+    pub fn positions(&self) -> Vec<Vec2> {
+        self.vertices.iter().map(|v| v.position).collect()
+    }
+
+    pub fn triangles(&self) -> Result<Vec<Vertex>, String> {
+        Ok(triangulation::triangulate(&self.positions())?
+            .iter()
+            .flatten()
+            .map(|&i| self.vertices[i])
+            .collect())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    // Polygons on a locked layer are drawn but can't be selected
+    pub locked: bool,
+}
+
+impl Layer {
+    pub fn new(name: &str) -> Layer {
+        Layer {
+            name: String::from(name),
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+// Finished polygons, plus the one being clicked out. Layers are drawn bottom
+// to top and the polygons of a layer in the order they are listed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub polygons: Vec<Polygon>,
+    pub current: Vec<Vertex>,
+    pub layers: Vec<Layer>,
+}
+
+impl Default for Document {
+    fn default() -> Document {
+        Document {
+            polygons: vec![],
+            current: vec![],
+            layers: vec![Layer::new("Layer 1")],
+        }
+    }
+}
+
+impl Document {
+    pub fn new() -> Document {
+        Document::default()
+    }
+
+    // Indices of the polygons on visible layers, back to front
+    pub fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.polygons.len())
+            .filter(|&i| {
+                self.layers
+                    .get(self.polygons[i].layer)
+                    .is_some_and(|layer| layer.visible)
+            })
+            .collect();
+        // Stable, so polygons keep their order within a layer
+        order.sort_by_key(|&i| self.polygons[i].layer);
+        order
+    }
+
+    // Polygons that can be picked, front to back
+    pub fn pickable(&self) -> Vec<usize> {
+        self.draw_order()
+            .into_iter()
+            .rev()
+            .filter(|&i| !self.layers[self.polygons[i].layer].locked)
+            .collect()
+    }
+
+    // The next polygon in front of or behind `polygon` on the same layer
+    pub fn neighbour(&self, polygon: usize, forward: bool) -> Option<usize> {
+        let layer = self.polygons.get(polygon)?.layer;
+        let same_layer = |&i: &usize| self.polygons[i].layer == layer;
+        if forward {
+            (polygon + 1..self.polygons.len()).find(same_layer)
+        } else {
+            (0..polygon).rev().find(same_layer)
+        }
+    }
+
+    // Every visible polygon as a TRIANGLES stream, back to front
+    pub fn triangles(&self) -> Vec<Vertex> {
+        self.draw_order()
+            .into_iter()
+            .flat_map(|i| self.polygons[i].triangles().unwrap_or_default())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(layer: usize) -> Polygon {
+        let color = Vec4(0.0, 0.0, 0.0, 1.0);
+        Polygon {
+            vertices: [Vec2(0.0, 0.0), Vec2(1.0, 0.0), Vec2(0.0, 1.0)]
+                .iter()
+                .map(|&position| Vertex { position, color })
+                .collect(),
+            layer,
+        }
+    }
+
+    // Polygons 0 and 3 on the bottom layer, 1 on the top and 2 on a middle
+    // layer that the tests hide or lock
+    fn document() -> Document {
+        let mut document = Document::new();
+        document.layers.push(Layer::new("Layer 2"));
+        document.layers.push(Layer::new("Layer 3"));
+        document.polygons = vec![triangle(0), triangle(2), triangle(1), triangle(0)];
+        document
+    }
+
+    #[test]
+    fn draws_layers_bottom_to_top() {
+        let mut document = document();
+        assert_eq!(document.draw_order(), vec![0, 3, 2, 1]);
+        assert_eq!(document.triangles().len(), 4 * 3);
+
+        document.layers[1].visible = false;
+        assert_eq!(document.draw_order(), vec![0, 3, 1]);
+    }
+
+    #[test]
+    fn locked_layers_are_not_pickable() {
+        let mut document = document();
+        assert_eq!(document.pickable(), vec![1, 2, 3, 0]);
+
+        document.layers[1].locked = true;
+        assert_eq!(document.pickable(), vec![1, 3, 0]);
+    }
+
+    #[test]
+    fn neighbours_stay_on_the_same_layer() {
+        let document = document();
+        assert_eq!(document.neighbour(0, true), Some(3));
+        assert_eq!(document.neighbour(3, false), Some(0));
+        assert_eq!(document.neighbour(1, true), None);
+        assert_eq!(document.neighbour(7, true), None);
+    }
+}
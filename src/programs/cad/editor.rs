@@ -0,0 +1,876 @@
+use super::{
+    document::{Document, Layer, Polygon, Vertex},
+    format,
+    history::{Command, History},
+    hit_test::{self, Hit},
+    shapes::{self, Shape},
+    snap::{self, Settings},
+    view::{self, View},
+};
+use crate::{
+    linear_algebra::{Vec2, Vec4},
+    utils,
+};
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGlBuffer, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+pub const AUTOSAVE_KEY: &str = "webgl-rs-cad";
+
+const OUTLINE_COLOR: Vec4 = Vec4(1.0, 1.0, 1.0, 1.0);
+const HANDLE_COLOR: Vec4 = Vec4(1.0, 1.0, 1.0, 1.0);
+const SELECTED_HANDLE_COLOR: Vec4 = Vec4(1.0, 1.0, 0.0, 1.0);
+const GRID_COLOR: Vec4 = Vec4(0.6, 0.6, 0.6, 1.0);
+const CURSOR_COLOR: Vec4 = Vec4(1.0, 1.0, 0.0, 1.0);
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Tool {
+    Draw,
+    Select,
+    Shape(Shape),
+}
+
+impl Tool {
+    pub fn parse(tool: &str) -> Option<Tool> {
+        match tool {
+            "draw" => Some(Tool::Draw),
+            "select" => Some(Tool::Select),
+            "rectangle" => Some(Tool::Shape(Shape::Rectangle)),
+            "circle" => Some(Tool::Shape(Shape::Circle)),
+            "ellipse" => Some(Tool::Shape(Shape::Ellipse)),
+            "regular-polygon" => Some(Tool::Shape(Shape::RegularPolygon)),
+            "line" => Some(Tool::Shape(Shape::Line)),
+            _ => None,
+        }
+    }
+}
+
+// A shape being created. The last point follows the cursor.
+struct Draft {
+    shape: Shape,
+    color: Vec4,
+    layer: usize,
+    points: Vec<Vec2>,
+}
+
+impl Draft {
+    fn anchor(&self) -> Option<Vec2> {
+        self.points.iter().rev().nth(1).copied()
+    }
+
+    fn command(&self, options: &shapes::Options) -> Option<Command> {
+        let polygons: Vec<Polygon> = shapes::build(self.shape, &self.points, options)
+            .into_iter()
+            .map(|positions| Polygon {
+                vertices: positions
+                    .into_iter()
+                    .map(|position| Vertex {
+                        position,
+                        color: self.color,
+                    })
+                    .collect(),
+                layer: self.layer,
+            })
+            .filter(|polygon| polygon.triangles().is_ok())
+            .collect();
+
+        if polygons.is_empty() {
+            None
+        } else {
+            Some(Command::AddPolygons(polygons))
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Selection {
+    Polygon(usize),
+    Vertex(usize, usize),
+}
+
+impl Selection {
+    fn polygon(&self) -> usize {
+        match *self {
+            Selection::Polygon(polygon) => polygon,
+            Selection::Vertex(polygon, _) => polygon,
+        }
+    }
+}
+
+pub struct Editor {
+    pub document: RefCell<Document>,
+    history: RefCell<History>,
+    tool: Cell<Tool>,
+    selection: Cell<Option<Selection>>,
+    // Where the drag started and the move it would commit on release
+    drag: RefCell<Option<(Vec2, Command)>>,
+    draft: RefCell<Option<Draft>>,
+    // The layer new polygons go on
+    active_layer: Cell<usize>,
+    shape_options: Cell<shapes::Options>,
+    snap: Cell<Settings>,
+    view: Cell<View>,
+    // Last pointer position while panning
+    pan: Cell<Option<Vec2>>,
+    context: Gl,
+    grid: Buffers,
+    triangles: Buffers,
+    current: Buffers,
+    overlay: Buffers,
+    cursor: Buffers,
+    undo_btn: web_sys::HtmlButtonElement,
+    redo_btn: web_sys::HtmlButtonElement,
+    layer_panel: LayerPanel,
+    pub status: web_sys::Element,
+}
+
+impl Editor {
+    pub fn new(
+        context: Gl,
+        document: Document,
+        view: View,
+        undo_btn: web_sys::HtmlButtonElement,
+        redo_btn: web_sys::HtmlButtonElement,
+        layer_panel: LayerPanel,
+        status: web_sys::Element,
+    ) -> Result<Editor, JsValue> {
+        let editor = Editor {
+            document: RefCell::new(document),
+            history: RefCell::new(History::new()),
+            tool: Cell::new(Tool::Draw),
+            selection: Cell::new(None),
+            drag: RefCell::new(None),
+            draft: RefCell::new(None),
+            active_layer: Cell::new(0),
+            shape_options: Cell::new(shapes::Options::default()),
+            snap: Cell::new(Settings::default()),
+            view: Cell::new(view),
+            pan: Cell::new(None),
+            grid: Buffers::new(&context)?,
+            triangles: Buffers::new(&context)?,
+            current: Buffers::new(&context)?,
+            overlay: Buffers::new(&context)?,
+            cursor: Buffers::new(&context)?,
+            context,
+            undo_btn,
+            redo_btn,
+            layer_panel,
+            status,
+        };
+        editor.sync();
+        Ok(editor)
+    }
+
+    pub fn execute(&self, command: Command) {
+        if let Command::Replace { .. } = command {
+            self.forget_indices();
+        }
+        let result = self
+            .history
+            .borrow_mut()
+            .execute(&mut self.document.borrow_mut(), command);
+        match result {
+            Ok(()) => self.status.set_text_content(None),
+            Err(e) => self.status.set_text_content(Some(&e)),
+        }
+        self.autosave();
+        self.sync();
+    }
+
+    // Appends the polygons that can be triangulated as a single undoable step
+    pub fn import(&self, polygons: Vec<Polygon>) {
+        let before = self.document.borrow().clone();
+        let mut after = before.clone();
+
+        let total = polygons.len();
+        let layer = self.active_layer.get();
+        after.polygons.extend(
+            polygons
+                .into_iter()
+                .filter(|p| p.triangles().is_ok())
+                .map(|p| Polygon { layer, ..p }),
+        );
+        let skipped = total - (after.polygons.len() - before.polygons.len());
+
+        self.execute(Command::Replace { before, after });
+        if skipped > 0 {
+            self.status.set_text_content(Some(&format!(
+                "Skipped {} of {} shapes that are not simple polygons",
+                skipped, total
+            )));
+        }
+    }
+
+    pub fn undo(&self) {
+        let result = self
+            .history
+            .borrow_mut()
+            .undo(&mut self.document.borrow_mut());
+        if let Err(e) = result {
+            self.status.set_text_content(Some(&e));
+        }
+        self.forget_indices();
+        self.autosave();
+        self.sync();
+    }
+
+    pub fn redo(&self) {
+        let result = self
+            .history
+            .borrow_mut()
+            .redo(&mut self.document.borrow_mut());
+        if let Err(e) = result {
+            self.status.set_text_content(Some(&e));
+        }
+        self.forget_indices();
+        self.autosave();
+        self.sync();
+    }
+
+    // The selection, a drag and a shape being created refer to polygons and
+    // vertices by index, which mean something else once the history moves
+    fn forget_indices(&self) {
+        self.selection.set(None);
+        *self.drag.borrow_mut() = None;
+        *self.draft.borrow_mut() = None;
+    }
+
+    pub fn set_tool(&self, tool: Tool) {
+        self.tool.set(tool);
+        self.selection.set(None);
+        *self.draft.borrow_mut() = None;
+        self.cursor.upload(&self.context, &[]);
+        self.sync();
+    }
+
+    pub fn set_snap(&self, settings: Settings) {
+        self.snap.set(settings);
+        self.upload_grid();
+    }
+
+    // The grid only covers the visible area, so it follows the view
+    fn upload_grid(&self) {
+        let settings = self.snap.get();
+        let lines = if settings.show_grid {
+            let (min, max) = self.view.get().bounds();
+            snap::grid_lines(settings.grid_spacing, min, max)
+        } else {
+            vec![]
+        };
+        let vertices: Vec<Vertex> = lines
+            .into_iter()
+            .map(|position| Vertex {
+                position,
+                color: GRID_COLOR,
+            })
+            .collect();
+        self.grid.upload(&self.context, &vertices);
+    }
+
+    pub fn set_shape_options(&self, options: shapes::Options) {
+        self.shape_options.set(options);
+        self.sync();
+    }
+
+    // Snaps `p` to every vertex in the document except `exclude`, the one
+    // being dragged
+    fn snap(&self, p: Vec2, exclude: Option<(usize, usize)>, anchor: Option<Vec2>) -> Vec2 {
+        let document = self.document.borrow();
+        let vertices: Vec<Vec2> = document
+            .draw_order()
+            .into_iter()
+            .flat_map(|i| {
+                document.polygons[i]
+                    .vertices
+                    .iter()
+                    .enumerate()
+                    .filter(move |(j, _)| exclude != Some((i, *j)))
+                    .map(|(_, v)| v.position)
+            })
+            .chain(document.current.iter().map(|v| v.position))
+            .collect();
+
+        // The snapping radius is a distance on screen
+        let mut settings = self.snap.get();
+        settings.radius = self.view.get().world_length(settings.radius);
+        snap::snap(p, &settings, &vertices, anchor)
+    }
+
+    pub fn view(&self) -> View {
+        self.view.get()
+    }
+
+    fn set_view(&self, view: View) {
+        self.view.set(view);
+        self.upload_grid();
+    }
+
+    pub fn zoom_at(&self, ndc: Vec2, factor: f32) {
+        self.set_view(self.view.get().zoom_about(ndc, factor));
+    }
+
+    pub fn zoom_to_fit(&self) {
+        let document = self.document.borrow();
+        let points: Vec<Vec2> = document
+            .draw_order()
+            .into_iter()
+            .flat_map(|i| document.polygons[i].positions())
+            .chain(document.current.iter().map(|v| v.position))
+            .collect();
+
+        let view = self.view.get();
+        self.set_view(match view::bounding_box(&points) {
+            Some((min, max)) => view.fit(min, max),
+            None => View::new(view.aspect),
+        });
+    }
+
+    pub fn begin_pan(&self, ndc: Vec2) {
+        self.pan.set(Some(ndc));
+    }
+
+    // Hit-testing uses the same tolerance as snapping to vertices
+    fn pick_radius(&self) -> f32 {
+        self.view.get().world_length(self.snap.get().radius)
+    }
+
+    fn last_vertex(&self) -> Option<Vec2> {
+        self.document.borrow().current.last().map(|v| v.position)
+    }
+
+    // Adds a vertex at typed coordinates, see `snap::parse_point`
+    pub fn add_vertex(&self, input: &str, color: Vec4) -> bool {
+        if !self.check_active_layer() {
+            return false;
+        }
+        match snap::parse_point(input, self.last_vertex()) {
+            Ok(position) => {
+                self.execute(Command::AddVertex(Vertex { position, color }));
+                true
+            }
+            Err(e) => {
+                self.status.set_text_content(Some(&e));
+                false
+            }
+        }
+    }
+
+    // New polygons can only go on a layer that is visible and unlocked
+    fn check_active_layer(&self) -> bool {
+        let document = self.document.borrow();
+        let layer = &document.layers[self.active_layer.get()];
+        let problem = if !layer.visible {
+            "hidden"
+        } else if layer.locked {
+            "locked"
+        } else {
+            return true;
+        };
+        self.status
+            .set_text_content(Some(&format!("{} is {}", layer.name, problem)));
+        false
+    }
+
+    pub fn mouse_down(&self, ndc: Vec2, color: Vec4) {
+        let p = self.view.get().ndc_to_world(ndc);
+        if self.tool.get() != Tool::Select && !self.check_active_layer() {
+            return;
+        }
+        match self.tool.get() {
+            Tool::Draw => {
+                let position = self.snap(p, None, self.last_vertex());
+                self.execute(Command::AddVertex(Vertex { position, color }));
+                return;
+            }
+            Tool::Shape(shape) => {
+                let mut draft = self.draft.borrow_mut();
+                match draft.as_mut() {
+                    // Each click fixes the point under the cursor and starts
+                    // the next segment
+                    Some(line) if shape == Shape::Line => {
+                        let p = self.snap(p, None, line.anchor());
+                        line.points.pop();
+                        line.points.extend_from_slice(&[p, p]);
+                    }
+                    _ => {
+                        let p = self.snap(p, None, None);
+                        *draft = Some(Draft {
+                            shape,
+                            color,
+                            layer: self.active_layer.get(),
+                            points: vec![p, p],
+                        });
+                    }
+                }
+                drop(draft);
+                self.sync();
+                return;
+            }
+            Tool::Select => {}
+        }
+
+        let hit = hit_test::hit_test(&self.document.borrow(), p, self.pick_radius());
+        let (selection, command) = match hit {
+            Some(Hit::Vertex { polygon, vertex }) => (
+                Some(Selection::Vertex(polygon, vertex)),
+                Some(Command::MoveVertex {
+                    polygon,
+                    vertex,
+                    delta: Vec2(0.0, 0.0),
+                }),
+            ),
+            Some(hit) => (
+                Some(Selection::Polygon(hit.polygon())),
+                Some(Command::MovePolygon {
+                    polygon: hit.polygon(),
+                    delta: Vec2(0.0, 0.0),
+                }),
+            ),
+            None => (None, None),
+        };
+
+        self.selection.set(selection);
+        *self.drag.borrow_mut() = command.map(|command| (p, command));
+        self.sync();
+    }
+
+    // Returns the world position under the cursor after snapping
+    pub fn mouse_move(&self, ndc: Vec2) -> Vec2 {
+        if let Some(last) = self.pan.get() {
+            self.set_view(self.view.get().pan(ndc - last));
+            self.pan.set(Some(ndc));
+            return self.view.get().ndc_to_world(ndc);
+        }
+
+        let p = self.view.get().ndc_to_world(ndc);
+        let mut drag = self.drag.borrow_mut();
+        let position = match drag.as_mut() {
+            Some((
+                start,
+                Command::MoveVertex {
+                    polygon,
+                    vertex,
+                    delta,
+                },
+            )) => {
+                let original = self
+                    .document
+                    .borrow()
+                    .polygons
+                    .get(*polygon)
+                    .and_then(|p| p.vertices.get(*vertex))
+                    .map(|v| v.position);
+                match original {
+                    Some(original) => {
+                        let target =
+                            self.snap(original + (p - *start), Some((*polygon, *vertex)), None);
+                        *delta = target - original;
+                        target
+                    }
+                    None => p,
+                }
+            }
+            Some((start, Command::MovePolygon { delta, .. })) => {
+                *delta = p - *start;
+                p
+            }
+            Some(_) => p,
+            None => match (self.tool.get(), self.draft.borrow_mut().as_mut()) {
+                (Tool::Draw, _) => self.snap(p, None, self.last_vertex()),
+                (Tool::Shape(_), Some(draft)) => {
+                    let p = self.snap(p, None, draft.anchor());
+                    if let Some(last) = draft.points.last_mut() {
+                        *last = p;
+                    }
+                    p
+                }
+                (Tool::Shape(_), None) => self.snap(p, None, None),
+                (Tool::Select, _) => p,
+            },
+        };
+        let dragging = drag.is_some() || self.draft.borrow().is_some();
+        drop(drag);
+
+        let cursor = if self.tool.get() != Tool::Select {
+            vec![Vertex {
+                position,
+                color: CURSOR_COLOR,
+            }]
+        } else {
+            vec![]
+        };
+        self.cursor.upload(&self.context, &cursor);
+        if dragging {
+            self.sync();
+        }
+        position
+    }
+
+    pub fn mouse_up(&self) {
+        if self.pan.take().is_some() {
+            return;
+        }
+
+        let drag = self.drag.borrow_mut().take();
+        match drag {
+            Some((_, Command::MoveVertex { delta, .. }))
+            | Some((_, Command::MovePolygon { delta, .. }))
+                if delta == Vec2(0.0, 0.0) =>
+            {
+                self.sync()
+            }
+            Some((_, command)) => self.execute(command),
+            None => {}
+        }
+
+        let dragged_shape = match self.draft.borrow().as_ref() {
+            Some(draft) => draft.shape != Shape::Line,
+            None => false,
+        };
+        if dragged_shape {
+            self.end_shape();
+        }
+    }
+
+    // Adds the shape being created to the document, or finishes the current
+    // polygon when there is none
+    pub fn end_shape(&self) {
+        let draft = self.draft.borrow_mut().take();
+        match draft {
+            Some(mut draft) => {
+                if draft.shape == Shape::Line {
+                    draft.points.pop();
+                }
+                match draft.command(&self.shape_options.get()) {
+                    Some(command) => self.execute(command),
+                    None => self.sync(),
+                }
+            }
+            None => self.execute(Command::EndPolygon {
+                layer: self.active_layer.get(),
+            }),
+        }
+    }
+
+    pub fn mouse_leave(&self) {
+        self.mouse_up();
+        self.cursor.upload(&self.context, &[]);
+    }
+
+    // Double clicking an edge with the select tool splits it at that point,
+    // and double clicking with the line tool ends the line
+    pub fn double_click(&self, ndc: Vec2) {
+        let p = self.view.get().ndc_to_world(ndc);
+        match self.tool.get() {
+            Tool::Shape(Shape::Line) => return self.end_shape(),
+            Tool::Select => {}
+            _ => return,
+        }
+
+        let hit = hit_test::hit_test(&self.document.borrow(), p, self.pick_radius());
+        if let Some(Hit::Edge {
+            polygon,
+            edge,
+            point,
+        }) = hit
+        {
+            let color = self.document.borrow().polygons[polygon].vertices[edge].color;
+            self.execute(Command::InsertVertex {
+                polygon,
+                index: edge + 1,
+                vertex: Vertex {
+                    position: point,
+                    color,
+                },
+            });
+            self.selection
+                .set(Some(Selection::Vertex(polygon, edge + 1)));
+            self.sync();
+        }
+    }
+
+    // A polygon left with fewer than three vertices is deleted as a whole
+    pub fn delete_selection(&self) {
+        let command = match self.selection.get() {
+            Some(Selection::Vertex(polygon, index)) => {
+                let document = self.document.borrow();
+                let selected = match document.polygons.get(polygon) {
+                    Some(selected) => selected,
+                    None => return,
+                };
+                match selected.vertices.get(index) {
+                    Some(&vertex) if selected.vertices.len() > 3 => Command::DeleteVertex {
+                        polygon,
+                        index,
+                        vertex,
+                    },
+                    Some(_) => Command::DeletePolygon {
+                        index: polygon,
+                        polygon: selected.clone(),
+                    },
+                    None => return,
+                }
+            }
+            Some(Selection::Polygon(index)) => match self.document.borrow().polygons.get(index) {
+                Some(polygon) => Command::DeletePolygon {
+                    index,
+                    polygon: polygon.clone(),
+                },
+                None => return,
+            },
+            None => return,
+        };
+
+        self.selection.set(None);
+        self.execute(command);
+    }
+
+    // Drops the selection and any shape being created
+    pub fn cancel(&self) {
+        self.selection.set(None);
+        *self.draft.borrow_mut() = None;
+        self.sync();
+    }
+
+    // Moves the selected polygon one step up or down within its layer
+    pub fn reorder_selection(&self, forward: bool) {
+        let selection = match self.selection.get() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let polygon = selection.polygon();
+        let neighbour = self.document.borrow().neighbour(polygon, forward);
+        if let Some(other) = neighbour {
+            self.selection.set(Some(match selection {
+                Selection::Polygon(_) => Selection::Polygon(other),
+                Selection::Vertex(_, vertex) => Selection::Vertex(other, vertex),
+            }));
+            self.execute(Command::Swap(polygon, other));
+        }
+    }
+
+    pub fn add_layer(&self) {
+        let count = self.document.borrow().layers.len();
+        self.active_layer.set(count);
+        self.execute(Command::AddLayer(Layer::new(&format!(
+            "Layer {}",
+            count + 1
+        ))));
+    }
+
+    pub fn set_active_layer(&self, index: usize) {
+        if index < self.document.borrow().layers.len() {
+            self.active_layer.set(index);
+        }
+        self.sync();
+    }
+
+    pub fn update_active_layer(&self, visible: bool, locked: bool) {
+        let index = self.active_layer.get();
+        let before = self.document.borrow().layers[index].clone();
+        let after = Layer {
+            visible,
+            locked,
+            ..before.clone()
+        };
+        if before != after {
+            self.selection.set(None);
+            self.execute(Command::UpdateLayer {
+                index,
+                before,
+                after,
+            });
+        }
+    }
+
+    pub fn recolor_selection(&self, color: Vec4) {
+        let selection = match self.selection.get() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let polygon = selection.polygon();
+        let before = match self.document.borrow().polygons.get(polygon) {
+            Some(p) => p.vertices.iter().map(|v| v.color).collect(),
+            None => return,
+        };
+        let vertex = match selection {
+            Selection::Vertex(_, vertex) => Some(vertex),
+            Selection::Polygon(_) => None,
+        };
+
+        self.execute(Command::Recolor {
+            polygon,
+            vertex,
+            before,
+            after: color,
+        });
+    }
+
+    // Only the history changes the document, so saving after each step keeps
+    // drags and previews out of storage
+    fn autosave(&self) {
+        if let Some(storage) = utils::local_storage() {
+            let json = format::to_json(&self.document.borrow());
+            let _ = storage.set_item(AUTOSAVE_KEY, &json);
+        }
+    }
+
+    // Re-uploads the GPU buffers from the document, with any drag or shape in
+    // progress applied
+    fn sync(&self) {
+        let document = self.document.borrow();
+        let pending = match (self.drag.borrow().as_ref(), self.draft.borrow().as_ref()) {
+            (Some((_, command)), _) => Some(command.clone()),
+            (None, Some(draft)) => draft.command(&self.shape_options.get()),
+            (None, None) => None,
+        };
+        let mut preview = None;
+        if let Some(command) = pending {
+            let mut edited = document.clone();
+            if command.apply(&mut edited).is_ok() {
+                preview = Some(edited);
+            }
+        }
+        let shown = preview.as_ref().unwrap_or(&document);
+
+        self.triangles.upload(&self.context, &shown.triangles());
+        self.current.upload(&self.context, &shown.current);
+
+        let mut overlay = vec![];
+        if let Some(selection) = self.selection.get() {
+            if let Some(polygon) = shown.polygons.get(selection.polygon()) {
+                for (i, v) in polygon.vertices.iter().enumerate() {
+                    let color = match selection {
+                        Selection::Vertex(_, selected) if selected == i => SELECTED_HANDLE_COLOR,
+                        _ => HANDLE_COLOR,
+                    };
+                    overlay.push(Vertex {
+                        position: v.position,
+                        color,
+                    });
+                }
+            }
+        }
+        self.overlay.upload(&self.context, &overlay);
+
+        // Undoing can remove the active layer
+        let last_layer = document.layers.len() - 1;
+        if self.active_layer.get() > last_layer {
+            self.active_layer.set(last_layer);
+        }
+        self.layer_panel
+            .update(&document.layers, self.active_layer.get());
+
+        let history = self.history.borrow();
+        self.undo_btn.set_disabled(!history.can_undo());
+        self.redo_btn.set_disabled(!history.can_redo());
+    }
+
+    pub fn draw(&self, v_position: u32, v_color: u32, u_view: &WebGlUniformLocation) {
+        let context = &self.context;
+        context.clear(Gl::COLOR_BUFFER_BIT);
+        context.uniform4fv_with_f32_array(Some(u_view), &self.view.get().uniform());
+
+        self.grid.bind(context, v_position, v_color);
+        context.draw_arrays(Gl::LINES, 0, self.grid.count());
+
+        self.triangles.bind(context, v_position, v_color);
+        context.draw_arrays(Gl::TRIANGLES, 0, self.triangles.count());
+
+        self.current.bind(context, v_position, v_color);
+        let current_vertices = self.current.count();
+        if current_vertices > 1 {
+            context.draw_arrays(Gl::LINE_LOOP, 0, current_vertices);
+        }
+        context.draw_arrays(Gl::POINTS, 0, current_vertices);
+
+        // Outline in a single color, then the handles on top of it
+        self.overlay.bind(context, v_position, v_color);
+        context.disable_vertex_attrib_array(v_color);
+        context.vertex_attrib4f(
+            v_color,
+            OUTLINE_COLOR.0,
+            OUTLINE_COLOR.1,
+            OUTLINE_COLOR.2,
+            OUTLINE_COLOR.3,
+        );
+        context.draw_arrays(Gl::LINE_LOOP, 0, self.overlay.count());
+        context.enable_vertex_attrib_array(v_color);
+        context.draw_arrays(Gl::POINTS, 0, self.overlay.count());
+
+        self.cursor.bind(context, v_position, v_color);
+        context.draw_arrays(Gl::POINTS, 0, self.cursor.count());
+    }
+}
+
+pub struct LayerPanel {
+    pub select: web_sys::HtmlSelectElement,
+    pub visible: web_sys::HtmlInputElement,
+    pub locked: web_sys::HtmlInputElement,
+}
+
+impl LayerPanel {
+    fn update(&self, layers: &[Layer], active: usize) {
+        self.select.set_length(0);
+        for (i, layer) in layers.iter().enumerate() {
+            if let Ok(option) =
+                web_sys::HtmlOptionElement::new_with_text_and_value(&layer.name, &i.to_string())
+            {
+                let _ = self.select.add_with_html_option_element(&option);
+            }
+        }
+        self.select.set_selected_index(active as i32);
+        self.visible.set_checked(layers[active].visible);
+        self.locked.set_checked(layers[active].locked);
+    }
+}
+
+struct Buffers {
+    position: WebGlBuffer,
+    color: WebGlBuffer,
+    count: Cell<i32>,
+}
+
+impl Buffers {
+    fn new(context: &Gl) -> Result<Buffers, JsValue> {
+        Ok(Buffers {
+            position: context
+                .create_buffer()
+                .ok_or("failed to create v_Position buffer")?,
+            color: context
+                .create_buffer()
+                .ok_or("failed to create v_Color buffer")?,
+            count: Cell::new(0),
+        })
+    }
+
+    fn upload(&self, context: &Gl, vertices: &[Vertex]) {
+        let positions: Vec<Vec2> = vertices.iter().map(|v| v.position).collect();
+        let colors: Vec<Vec4> = vertices.iter().map(|v| v.color).collect();
+
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec2::flatten(&positions),
+            Gl::DYNAMIC_DRAW,
+        );
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.color));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec4::flatten(&colors),
+            Gl::DYNAMIC_DRAW,
+        );
+        self.count.set(vertices.len() as i32);
+    }
+
+    fn bind(&self, context: &Gl, v_position: u32, v_color: u32) {
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position));
+        context.vertex_attrib_pointer_with_i32(v_position, 2, Gl::FLOAT, false, 0, 0);
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.color));
+        context.vertex_attrib_pointer_with_i32(v_color, 4, Gl::FLOAT, false, 0, 0);
+    }
+
+    fn count(&self) -> i32 {
+        self.count.get()
+    }
+}
@@ -0,0 +1,262 @@
+use super::document::{Document, Layer, Polygon, Vertex};
+use crate::color::{hex_to_rgba, rgba_to_hex};
+use crate::linear_algebra::Vec2;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Version 2:
+// {
+//   "version": 2,
+//   "layers": [{"name": "Layer 1", "visible": true, "locked": false}, ...],
+//   "polygons": [
+//     {"layer": 0, "vertices": [[x, y], ...], "color": "#rrggbb"},
+//     {"layer": 0, "vertices": [[x, y], ...], "colors": ["#rrggbbaa", ...]}
+//   ]
+// }
+// Positions are in world units, where the default view shows [-1, 1]. Layers
+// are listed bottom to top and polygons in drawing order within their layer.
+// A polygon has either a single `color` or one entry in `colors` per vertex;
+// colors carry an alpha byte unless they are opaque.
+//
+// Version 1 had no layers, so its polygons all go on a single layer, and
+// only opaque colors.
+pub const VERSION: u64 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct File {
+    version: u64,
+    layers: Vec<LayerRecord>,
+    polygons: Vec<PolygonRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayerRecord {
+    name: String,
+    visible: bool,
+    locked: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PolygonRecord {
+    layer: usize,
+    vertices: Vec<[f32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    colors: Option<Vec<String>>,
+}
+
+// Only finished polygons are saved
+pub fn to_json(document: &Document) -> String {
+    let layers = document
+        .layers
+        .iter()
+        .map(|layer| LayerRecord {
+            name: layer.name.clone(),
+            visible: layer.visible,
+            locked: layer.locked,
+        })
+        .collect();
+
+    let polygons = document
+        .polygons
+        .iter()
+        .map(|polygon| {
+            let colors: Vec<String> = polygon
+                .vertices
+                .iter()
+                .map(|v| rgba_to_hex(v.color))
+                .collect();
+            let uniform = colors.windows(2).all(|c| c[0] == c[1]);
+
+            PolygonRecord {
+                layer: polygon.layer,
+                vertices: polygon
+                    .vertices
+                    .iter()
+                    .map(|v| [v.position.0, v.position.1])
+                    .collect(),
+                color: if uniform {
+                    colors.first().cloned()
+                } else {
+                    None
+                },
+                colors: if uniform { None } else { Some(colors) },
+            }
+        })
+        .collect();
+
+    serde_json::to_string(&File {
+        version: VERSION,
+        layers,
+        polygons,
+    })
+    .unwrap()
+}
+
+pub fn from_json(json: &str) -> Result<Document, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("invalid JSON: {}", e))?;
+    let file: File =
+        serde_json::from_value(migrate(value)?).map_err(|e| format!("invalid drawing: {}", e))?;
+
+    if file.layers.is_empty() {
+        return Err(String::from("drawing has no layers"));
+    }
+    let mut document = Document::new();
+    document.layers = file
+        .layers
+        .into_iter()
+        .map(|record| Layer {
+            name: record.name,
+            visible: record.visible,
+            locked: record.locked,
+        })
+        .collect();
+
+    for (i, record) in file.polygons.into_iter().enumerate() {
+        if record.layer >= document.layers.len() {
+            return Err(format!(
+                "polygon {} is on missing layer {}",
+                i, record.layer
+            ));
+        }
+        let colors = match (record.color, record.colors) {
+            (Some(color), None) => vec![color; record.vertices.len()],
+            (None, Some(colors)) if colors.len() == record.vertices.len() => colors,
+            _ => return Err(format!("polygon {} needs one color or one per vertex", i)),
+        };
+
+        let vertices = record
+            .vertices
+            .iter()
+            .zip(colors.iter())
+            .map(|(position, color)| {
+                Ok(Vertex {
+                    position: Vec2(position[0], position[1]),
+                    color: hex_to_rgba(color).ok_or(format!("invalid color {}", color))?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        document.polygons.push(Polygon {
+            vertices,
+            layer: record.layer,
+        });
+    }
+
+    Ok(document)
+}
+
+fn version_1_to_2(mut value: Value) -> Value {
+    if let Some(polygons) = value.get_mut("polygons").and_then(Value::as_array_mut) {
+        for polygon in polygons.iter_mut().filter_map(Value::as_object_mut) {
+            polygon.insert(String::from("layer"), json!(0));
+        }
+    }
+    value["layers"] = json!([{"name": "Layer 1", "visible": true, "locked": false}]);
+    value["version"] = json!(2);
+    value
+}
+
+// Upgrades a saved file one version at a time until it matches `VERSION`
+pub fn migrate(value: Value) -> Result<Value, String> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("missing drawing version")?;
+
+    match version {
+        VERSION => Ok(value),
+        1 => migrate(version_1_to_2(value)),
+        v if v > VERSION => Err(format!(
+            "drawing version {} is newer than supported version {}",
+            v, VERSION
+        )),
+        v => Err(format!("unknown drawing version {}", v)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(colors: &[&str], layer: usize) -> Polygon {
+        let positions = [Vec2(0.0, 0.0), Vec2(1.5, -0.25), Vec2(0.1, 0.7)];
+        Polygon {
+            vertices: positions
+                .iter()
+                .zip(colors.iter().cycle())
+                .map(|(&position, color)| Vertex {
+                    position,
+                    color: hex_to_rgba(color).unwrap(),
+                })
+                .collect(),
+            layer,
+        }
+    }
+
+    #[test]
+    fn documents_round_trip() {
+        let mut document = Document::new();
+        document.layers.push(Layer {
+            name: String::from("Hidden \"layer\""),
+            visible: false,
+            locked: true,
+        });
+        document.polygons = vec![
+            polygon(&["#336699"], 1),
+            polygon(&["#ff0000", "#00ff0080", "#0000ff"], 0),
+        ];
+
+        let json = to_json(&document);
+        assert!(json.contains("\"color\":\"#336699\""));
+        assert_eq!(from_json(&json), Ok(document));
+    }
+
+    #[test]
+    fn drawing_in_progress_is_not_saved() {
+        let mut document = Document::new();
+        document.current = polygon(&["#000000"], 0).vertices;
+        assert_eq!(from_json(&to_json(&document)), Ok(Document::new()));
+    }
+
+    #[test]
+    fn version_1_goes_on_a_single_layer() {
+        let json = r##"{
+            "version": 1,
+            "polygons": [
+                {"vertices": [[0, 0], [1.5, -0.25], [0.1, 0.7]], "color": "#336699"}
+            ]
+        }"##;
+        let mut expected = Document::new();
+        expected.polygons = vec![polygon(&["#336699"], 0)];
+        assert_eq!(from_json(json), Ok(expected));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        for json in &[
+            r#"{"version": 3, "layers": [], "polygons": []}"#,
+            r#"{"version": 0, "polygons": []}"#,
+            r#"{"layers": [], "polygons": []}"#,
+        ] {
+            assert!(from_json(json).is_err(), "{} was accepted", json);
+        }
+    }
+
+    #[test]
+    fn invalid_drawings_are_rejected() {
+        let polygon = |fields: &str| {
+            format!(
+                r#"{{"version": 2, "layers": [{{"name": "", "visible": true, "locked": false}}],
+                    "polygons": [{{"vertices": [[0, 0], [1, 0], [0, 1]], {}}}]}}"#,
+                fields
+            )
+        };
+        assert!(from_json(&polygon(r##""layer": 0, "color": "#000000""##)).is_ok());
+        assert!(from_json(&polygon(r##""layer": 1, "color": "#000000""##)).is_err());
+        assert!(from_json(&polygon(r##""layer": 0, "colors": ["#000000"]"##)).is_err());
+        assert!(from_json(&polygon(r##""layer": 0, "color": "black""##)).is_err());
+        assert!(from_json(r#"{"version": 2, "layers": [], "polygons": []}"#).is_err());
+    }
+}
@@ -0,0 +1,551 @@
+use super::document::{Document, Layer, Polygon, Vertex};
+use crate::linear_algebra::{Vec2, Vec4};
+
+// Every change to a `Document` goes through a command, so that it can be
+// reverted and replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    AddVertex(Vertex),
+    EndPolygon {
+        layer: usize,
+    },
+    AddPolygons(Vec<Polygon>),
+    Replace {
+        before: Document,
+        after: Document,
+    },
+    MoveVertex {
+        polygon: usize,
+        vertex: usize,
+        delta: Vec2,
+    },
+    MovePolygon {
+        polygon: usize,
+        delta: Vec2,
+    },
+    InsertVertex {
+        polygon: usize,
+        index: usize,
+        vertex: Vertex,
+    },
+    DeleteVertex {
+        polygon: usize,
+        index: usize,
+        vertex: Vertex,
+    },
+    DeletePolygon {
+        index: usize,
+        polygon: Polygon,
+    },
+    // `vertex` is `None` to recolor the whole polygon
+    Recolor {
+        polygon: usize,
+        vertex: Option<usize>,
+        before: Vec<Vec4>,
+        after: Vec4,
+    },
+    // Exchanges two polygons' places in the drawing order
+    Swap(usize, usize),
+    AddLayer(Layer),
+    UpdateLayer {
+        index: usize,
+        before: Layer,
+        after: Layer,
+    },
+}
+
+fn polygon_mut(document: &mut Document, index: usize) -> Result<&mut Polygon, String> {
+    document
+        .polygons
+        .get_mut(index)
+        .ok_or(format!("no polygon {}", index))
+}
+
+fn vertex_mut(polygon: &mut Polygon, index: usize) -> Result<&mut Vertex, String> {
+    polygon
+        .vertices
+        .get_mut(index)
+        .ok_or(format!("no vertex {}", index))
+}
+
+// Applies `edit` to a copy of a finished polygon and keeps the result only if
+// it is still a simple polygon.
+fn edit_polygon<F>(document: &mut Document, index: usize, edit: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Polygon) -> Result<(), String>,
+{
+    let mut polygon = document
+        .polygons
+        .get(index)
+        .cloned()
+        .ok_or(format!("no polygon {}", index))?;
+    edit(&mut polygon)?;
+    polygon.triangles()?;
+    document.polygons[index] = polygon;
+    Ok(())
+}
+
+fn insert_vertex(polygon: &mut Polygon, index: usize, vertex: Vertex) -> Result<(), String> {
+    if index > polygon.vertices.len() {
+        return Err(format!("no vertex {}", index));
+    }
+    polygon.vertices.insert(index, vertex);
+    Ok(())
+}
+
+fn remove_vertex(polygon: &mut Polygon, index: usize) -> Result<(), String> {
+    if index >= polygon.vertices.len() {
+        return Err(format!("no vertex {}", index));
+    }
+    polygon.vertices.remove(index);
+    Ok(())
+}
+
+impl Command {
+    pub fn apply(&self, document: &mut Document) -> Result<(), String> {
+        match self {
+            Command::AddVertex(vertex) => document.current.push(*vertex),
+            Command::EndPolygon { layer } => {
+                let polygon = Polygon {
+                    vertices: document.current.clone(),
+                    layer: *layer,
+                };
+                polygon.triangles()?;
+                document.polygons.push(polygon);
+                document.current.clear();
+            }
+            Command::AddPolygons(polygons) => {
+                for polygon in polygons {
+                    polygon.triangles()?;
+                }
+                document.polygons.extend(polygons.iter().cloned());
+            }
+            Command::Replace { after, .. } => *document = after.clone(),
+            Command::MoveVertex {
+                polygon,
+                vertex,
+                delta,
+            } => edit_polygon(document, *polygon, |p| {
+                let v = vertex_mut(p, *vertex)?;
+                v.position = v.position + *delta;
+                Ok(())
+            })?,
+            Command::MovePolygon { polygon, delta } => edit_polygon(document, *polygon, |p| {
+                for v in &mut p.vertices {
+                    v.position = v.position + *delta;
+                }
+                Ok(())
+            })?,
+            Command::InsertVertex {
+                polygon,
+                index,
+                vertex,
+            } => edit_polygon(document, *polygon, |p| insert_vertex(p, *index, *vertex))?,
+            Command::DeleteVertex { polygon, index, .. } => {
+                edit_polygon(document, *polygon, |p| remove_vertex(p, *index))?
+            }
+            Command::DeletePolygon { index, .. } => {
+                if *index >= document.polygons.len() {
+                    return Err(format!("no polygon {}", index));
+                }
+                document.polygons.remove(*index);
+            }
+            Command::Recolor {
+                polygon,
+                vertex,
+                after,
+                ..
+            } => edit_polygon(document, *polygon, |p| {
+                match vertex {
+                    Some(i) => vertex_mut(p, *i)?.color = *after,
+                    None => {
+                        for v in &mut p.vertices {
+                            v.color = *after;
+                        }
+                    }
+                }
+                Ok(())
+            })?,
+            Command::Swap(a, b) => {
+                let len = document.polygons.len();
+                if *a >= len || *b >= len {
+                    return Err(format!("no polygons {} and {}", a, b));
+                }
+                document.polygons.swap(*a, *b);
+            }
+            Command::AddLayer(layer) => document.layers.push(layer.clone()),
+            Command::UpdateLayer { index, after, .. } => {
+                *document
+                    .layers
+                    .get_mut(*index)
+                    .ok_or(format!("no layer {}", index))? = after.clone()
+            }
+        }
+        Ok(())
+    }
+
+    // Fails without changing anything if the document is not in the state the
+    // command left it in
+    fn revert(&self, document: &mut Document) -> Result<(), String> {
+        match self {
+            Command::AddVertex(_) => {
+                document.current.pop();
+            }
+            Command::EndPolygon { .. } => {
+                if let Some(polygon) = document.polygons.pop() {
+                    document.current = polygon.vertices;
+                }
+            }
+            Command::AddPolygons(polygons) => {
+                let len = document
+                    .polygons
+                    .len()
+                    .checked_sub(polygons.len())
+                    .ok_or(format!("fewer than {} polygons", polygons.len()))?;
+                document.polygons.truncate(len);
+            }
+            Command::Replace { before, .. } => *document = before.clone(),
+            Command::MoveVertex {
+                polygon,
+                vertex,
+                delta,
+            } => {
+                let v = vertex_mut(polygon_mut(document, *polygon)?, *vertex)?;
+                v.position = v.position - *delta;
+            }
+            Command::MovePolygon { polygon, delta } => {
+                for v in &mut polygon_mut(document, *polygon)?.vertices {
+                    v.position = v.position - *delta;
+                }
+            }
+            Command::InsertVertex { polygon, index, .. } => {
+                remove_vertex(polygon_mut(document, *polygon)?, *index)?
+            }
+            Command::DeleteVertex {
+                polygon,
+                index,
+                vertex,
+            } => insert_vertex(polygon_mut(document, *polygon)?, *index, *vertex)?,
+            Command::DeletePolygon { index, polygon } => {
+                if *index > document.polygons.len() {
+                    return Err(format!("no polygon {}", index));
+                }
+                document.polygons.insert(*index, polygon.clone())
+            }
+            Command::Recolor {
+                polygon, before, ..
+            } => {
+                for (v, color) in polygon_mut(document, *polygon)?
+                    .vertices
+                    .iter_mut()
+                    .zip(before)
+                {
+                    v.color = *color;
+                }
+            }
+            Command::Swap(a, b) => {
+                let len = document.polygons.len();
+                if *a >= len || *b >= len {
+                    return Err(format!("no polygons {} and {}", a, b));
+                }
+                document.polygons.swap(*a, *b);
+            }
+            Command::AddLayer(_) => {
+                document.layers.pop();
+            }
+            Command::UpdateLayer { index, before, .. } => {
+                *document
+                    .layers
+                    .get_mut(*index)
+                    .ok_or(format!("no layer {}", index))? = before.clone()
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    // Applies `command` and records it. A command that fails leaves both the
+    // document and the history untouched.
+    pub fn execute(&mut self, document: &mut Document, command: Command) -> Result<(), String> {
+        command.apply(document)?;
+        self.undo_stack.push(command);
+        self.redo_stack.clear();
+        Ok(())
+    }
+
+    // A command that can't be reverted means the history no longer matches
+    // the document, so both stacks are discarded
+    pub fn undo(&mut self, document: &mut Document) -> Result<bool, String> {
+        match self.undo_stack.pop() {
+            Some(command) => match command.revert(document) {
+                Ok(()) => {
+                    self.redo_stack.push(command);
+                    Ok(true)
+                }
+                Err(e) => {
+                    self.undo_stack.clear();
+                    self.redo_stack.clear();
+                    Err(e)
+                }
+            },
+            None => Ok(false),
+        }
+    }
+
+    // A command that no longer applies, which would mean the document changed
+    // outside the history, discards the rest of the redo stack
+    pub fn redo(&mut self, document: &mut Document) -> Result<bool, String> {
+        match self.redo_stack.pop() {
+            Some(command) => match command.apply(document) {
+                Ok(()) => {
+                    self.undo_stack.push(command);
+                    Ok(true)
+                }
+                Err(e) => {
+                    self.redo_stack.clear();
+                    Err(e)
+                }
+            },
+            None => Ok(false),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Vec4 = Vec4(1.0, 0.0, 0.0, 1.0);
+    const BLUE: Vec4 = Vec4(0.0, 0.0, 1.0, 1.0);
+
+    fn vertex(x: f32, y: f32) -> Vertex {
+        Vertex {
+            position: Vec2(x, y),
+            color: RED,
+        }
+    }
+
+    fn square(x: f32, y: f32, layer: usize) -> Polygon {
+        Polygon {
+            vertices: vec![
+                vertex(x, y),
+                vertex(x + 1.0, y),
+                vertex(x + 1.0, y + 1.0),
+                vertex(x, y + 1.0),
+            ],
+            layer,
+        }
+    }
+
+    // Two squares on the first of two layers, and a triangle being drawn
+    fn document() -> Document {
+        let mut document = Document::new();
+        document.polygons = vec![square(0.0, 0.0, 0), square(2.0, 0.0, 0)];
+        document.current = vec![vertex(0.0, 2.0), vertex(1.0, 2.0), vertex(0.0, 3.0)];
+        document.layers.push(Layer::new("Layer 2"));
+        document
+    }
+
+    fn commands(document: &Document) -> Vec<Command> {
+        let mut replaced = document.clone();
+        replaced.polygons.clear();
+        vec![
+            Command::AddVertex(vertex(0.5, 2.5)),
+            Command::EndPolygon { layer: 1 },
+            Command::AddPolygons(vec![square(4.0, 0.0, 1), square(6.0, 0.0, 0)]),
+            Command::Replace {
+                before: document.clone(),
+                after: replaced,
+            },
+            Command::MoveVertex {
+                polygon: 1,
+                vertex: 2,
+                delta: Vec2(0.5, 0.25),
+            },
+            Command::MovePolygon {
+                polygon: 0,
+                delta: Vec2(-1.5, 2.0),
+            },
+            Command::InsertVertex {
+                polygon: 0,
+                index: 1,
+                vertex: vertex(0.5, -0.5),
+            },
+            Command::DeleteVertex {
+                polygon: 1,
+                index: 3,
+                vertex: document.polygons[1].vertices[3],
+            },
+            Command::DeletePolygon {
+                index: 0,
+                polygon: document.polygons[0].clone(),
+            },
+            Command::Recolor {
+                polygon: 1,
+                vertex: Some(2),
+                before: vec![RED; 4],
+                after: BLUE,
+            },
+            Command::Recolor {
+                polygon: 0,
+                vertex: None,
+                before: vec![RED; 4],
+                after: BLUE,
+            },
+            Command::Swap(0, 1),
+            Command::AddLayer(Layer::new("Layer 3")),
+            Command::UpdateLayer {
+                index: 1,
+                before: document.layers[1].clone(),
+                after: Layer {
+                    name: String::from("Hidden"),
+                    visible: false,
+                    locked: true,
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn every_command_reverts_to_the_original_document() {
+        let original = document();
+        for command in commands(&original) {
+            let mut document = original.clone();
+            let mut history = History::new();
+            history.execute(&mut document, command.clone()).unwrap();
+            assert_ne!(document, original, "{:?} changed nothing", command);
+            let applied = document.clone();
+
+            assert_eq!(history.undo(&mut document), Ok(true));
+            assert_eq!(document, original, "{:?} did not revert", command);
+            assert_eq!(history.redo(&mut document), Ok(true));
+            assert_eq!(document, applied, "{:?} did not redo", command);
+        }
+    }
+
+    #[test]
+    fn failed_commands_change_nothing() {
+        let original = document();
+        let mut document = original.clone();
+        let mut history = History::new();
+        // Moving a corner across the square makes it self-intersecting
+        let command = Command::MoveVertex {
+            polygon: 0,
+            vertex: 1,
+            delta: Vec2(-2.0, 1.0),
+        };
+        assert!(history.execute(&mut document, command).is_err());
+        assert!(history.execute(&mut document, Command::Swap(0, 5)).is_err());
+        assert_eq!(document, original);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn new_command_clears_redo() {
+        let mut document = document();
+        let mut history = History::new();
+        history
+            .execute(&mut document, Command::AddVertex(vertex(0.5, 2.5)))
+            .unwrap();
+        history.undo(&mut document).unwrap();
+        assert!(history.can_redo());
+
+        history.execute(&mut document, Command::Swap(0, 1)).unwrap();
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(&mut document), Ok(false));
+    }
+
+    #[test]
+    fn redo_that_no_longer_applies_is_dropped() {
+        let mut document = document();
+        let mut history = History::new();
+        history.execute(&mut document, Command::Swap(0, 1)).unwrap();
+        history
+            .execute(&mut document, Command::AddLayer(Layer::new("Layer 3")))
+            .unwrap();
+        history.undo(&mut document).unwrap();
+        history.undo(&mut document).unwrap();
+
+        document.polygons.clear();
+        assert!(history.redo(&mut document).is_err());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn redo_with_a_missing_vertex_is_an_error() {
+        let original = document();
+        for command in &[
+            Command::MoveVertex {
+                polygon: 0,
+                vertex: 4,
+                delta: Vec2(0.5, 0.5),
+            },
+            Command::InsertVertex {
+                polygon: 0,
+                index: 5,
+                vertex: vertex(0.5, -0.5),
+            },
+            Command::DeleteVertex {
+                polygon: 0,
+                index: 4,
+                vertex: vertex(0.0, 0.0),
+            },
+            Command::Recolor {
+                polygon: 0,
+                vertex: Some(4),
+                before: vec![RED],
+                after: BLUE,
+            },
+        ] {
+            let mut document = original.clone();
+            assert!(command.apply(&mut document).is_err(), "{:?}", command);
+            assert_eq!(document, original);
+        }
+    }
+
+    #[test]
+    fn undo_that_no_longer_reverts_is_dropped() {
+        let original = document();
+        for command in commands(&original) {
+            let mut document = original.clone();
+            let mut history = History::new();
+            history.execute(&mut document, command.clone()).unwrap();
+            history
+                .execute(&mut document, Command::AddVertex(vertex(0.0, 0.0)))
+                .unwrap();
+            history.undo(&mut document).unwrap();
+
+            // Whatever each command needs to revert is gone
+            document.polygons.clear();
+            document.layers.truncate(1);
+            let stale = document.clone();
+            match history.undo(&mut document) {
+                Ok(true) => {}
+                Ok(false) => panic!("{:?} was not undone", command),
+                Err(_) => {
+                    assert_eq!(document, stale, "{:?} changed the document", command);
+                    assert!(!history.can_undo() && !history.can_redo());
+                }
+            }
+        }
+    }
+}
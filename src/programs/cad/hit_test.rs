@@ -0,0 +1,257 @@
+use super::document::Document;
+use crate::linear_algebra::Vec2;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Hit {
+    Vertex {
+        polygon: usize,
+        vertex: usize,
+    },
+    // `point` is the closest point on the edge from `vertex` to the next one
+    Edge {
+        polygon: usize,
+        edge: usize,
+        point: Vec2,
+    },
+    Interior {
+        polygon: usize,
+    },
+}
+
+impl Hit {
+    pub fn polygon(&self) -> usize {
+        match *self {
+            Hit::Vertex { polygon, .. } => polygon,
+            Hit::Edge { polygon, .. } => polygon,
+            Hit::Interior { polygon } => polygon,
+        }
+    }
+}
+
+// Even-odd rule, so it also gives an answer for self-intersecting outlines
+pub fn point_in_polygon(p: Vec2, polygon: &[Vec2]) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + n - 1) % n]);
+        if (a.1 > p.1) != (b.1 > p.1) {
+            let x = a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0);
+            if p.0 < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+pub fn closest_point_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.dot(&ab);
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((p - a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+// Index of the vertex closest to `p` within `radius`
+pub fn nearest_vertex(p: Vec2, polygon: &[Vec2], radius: f32) -> Option<usize> {
+    polygon
+        .iter()
+        .map(|v| (*v - p).length())
+        .enumerate()
+        .filter(|(_, d)| *d <= radius)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(i, _)| i)
+}
+
+// Edge closest to `p` within `radius`, with the closest point on it
+pub fn nearest_edge(p: Vec2, polygon: &[Vec2], radius: f32) -> Option<(usize, Vec2)> {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let point = closest_point_on_segment(p, polygon[i], polygon[(i + 1) % n]);
+            (i, point, (point - p).length())
+        })
+        .filter(|(_, _, d)| *d <= radius)
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+        .map(|(i, point, _)| (i, point))
+}
+
+// Finds what is under `p`, checking polygons from the topmost down and
+// skipping hidden and locked layers. Within a polygon vertices take priority
+// over edges, and edges over the interior.
+pub fn hit_test(document: &Document, p: Vec2, radius: f32) -> Option<Hit> {
+    document.pickable().into_iter().find_map(|polygon| {
+        let positions = document.polygons[polygon].positions();
+        if let Some(vertex) = nearest_vertex(p, &positions, radius) {
+            Some(Hit::Vertex { polygon, vertex })
+        } else if let Some((edge, point)) = nearest_edge(p, &positions, radius) {
+            Some(Hit::Edge {
+                polygon,
+                edge,
+                point,
+            })
+        } else if point_in_polygon(p, &positions) {
+            Some(Hit::Interior { polygon })
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::document::{Layer, Polygon, Vertex};
+    use super::super::history::Command;
+    use super::*;
+    use crate::linear_algebra::Vec4;
+    use crate::triangulation;
+
+    fn polygon(positions: &[(f32, f32)], layer: usize) -> Polygon {
+        Polygon {
+            vertices: positions
+                .iter()
+                .map(|&(x, y)| Vertex {
+                    position: Vec2(x, y),
+                    color: Vec4(0.0, 0.0, 0.0, 1.0),
+                })
+                .collect(),
+            layer,
+        }
+    }
+
+    // A unit square at the origin with a smaller one overlapping its top
+    // right corner, drawn on top of it
+    fn document() -> Document {
+        let mut document = Document::new();
+        document.layers.push(Layer::new("Layer 2"));
+        document.polygons = vec![
+            polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)], 0),
+            polygon(&[(0.5, 0.5), (1.5, 0.5), (1.5, 1.5), (0.5, 1.5)], 1),
+        ];
+        document
+    }
+
+    #[test]
+    fn even_odd_inside_test() {
+        let square = document().polygons[0].positions();
+        assert!(point_in_polygon(Vec2(0.5, 0.5), &square));
+        assert!(!point_in_polygon(Vec2(1.5, 0.5), &square));
+        assert!(!point_in_polygon(Vec2(0.5, -0.1), &square));
+
+        // The middle of a bow tie's crossing belongs to neither half
+        let bow_tie = [
+            Vec2(0.0, 0.0),
+            Vec2(2.0, 2.0),
+            Vec2(2.0, 0.0),
+            Vec2(0.0, 2.0),
+        ];
+        assert!(point_in_polygon(Vec2(0.2, 1.0), &bow_tie));
+        assert!(!point_in_polygon(Vec2(1.0, 0.2), &bow_tie));
+    }
+
+    #[test]
+    fn closest_point_is_clamped_to_the_segment() {
+        let (a, b) = (Vec2(0.0, 0.0), Vec2(2.0, 0.0));
+        assert_eq!(
+            closest_point_on_segment(Vec2(1.0, 1.0), a, b),
+            Vec2(1.0, 0.0)
+        );
+        assert_eq!(closest_point_on_segment(Vec2(-1.0, 1.0), a, b), a);
+        assert_eq!(closest_point_on_segment(Vec2(3.0, -1.0), a, b), b);
+        assert_eq!(closest_point_on_segment(Vec2(3.0, -1.0), a, a), a);
+    }
+
+    #[test]
+    fn topmost_polygon_is_hit_first() {
+        let document = document();
+        assert_eq!(
+            hit_test(&document, Vec2(0.75, 0.75), 0.01),
+            Some(Hit::Interior { polygon: 1 })
+        );
+        assert_eq!(
+            hit_test(&document, Vec2(0.25, 0.25), 0.01),
+            Some(Hit::Interior { polygon: 0 })
+        );
+        assert_eq!(hit_test(&document, Vec2(2.0, 2.0), 0.01), None);
+    }
+
+    #[test]
+    fn vertices_take_priority_over_edges() {
+        let document = document();
+        assert_eq!(
+            hit_test(&document, Vec2(1.02, 0.01), 0.05),
+            Some(Hit::Vertex {
+                polygon: 0,
+                vertex: 1
+            })
+        );
+        assert_eq!(
+            hit_test(&document, Vec2(0.5, 0.51), 0.05),
+            Some(Hit::Vertex {
+                polygon: 1,
+                vertex: 0
+            })
+        );
+    }
+
+    #[test]
+    fn edges_take_priority_over_the_interior() {
+        let document = document();
+        let hit = hit_test(&document, Vec2(1.48, 1.0), 0.05);
+        assert_eq!(
+            hit,
+            Some(Hit::Edge {
+                polygon: 1,
+                edge: 1,
+                point: Vec2(1.5, 1.0)
+            })
+        );
+        assert_eq!(hit.map(|h| h.polygon()), Some(1));
+    }
+
+    #[test]
+    fn hidden_and_locked_layers_are_skipped() {
+        let mut document = document();
+        document.layers[1].visible = false;
+        assert_eq!(
+            hit_test(&document, Vec2(0.75, 0.75), 0.01),
+            Some(Hit::Interior { polygon: 0 })
+        );
+        document.layers[1].visible = true;
+        document.layers[1].locked = true;
+        assert_eq!(hit_test(&document, Vec2(1.25, 1.25), 0.01), None);
+    }
+
+    #[test]
+    fn vertex_inserted_after_the_edge_splits_it() {
+        let mut document = document();
+        // The closing edge runs from the last vertex back to the first
+        let hit = hit_test(&document, Vec2(-0.01, 0.25), 0.05);
+        let (edge, point) = match hit {
+            Some(Hit::Edge { edge, point, .. }) => (edge, point),
+            hit => panic!("expected an edge, got {:?}", hit),
+        };
+        assert_eq!((edge, point), (3, Vec2(0.0, 0.25)));
+
+        let before = triangulation::signed_area(&document.polygons[0].positions());
+        let vertex = Vertex {
+            position: point,
+            color: Vec4(0.0, 0.0, 0.0, 1.0),
+        };
+        Command::InsertVertex {
+            polygon: 0,
+            index: edge + 1,
+            vertex,
+        }
+        .apply(&mut document)
+        .unwrap();
+
+        let positions = document.polygons[0].positions();
+        assert_eq!(positions[4], point);
+        assert_eq!(triangulation::signed_area(&positions), before);
+    }
+}
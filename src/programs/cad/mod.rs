@@ -0,0 +1,590 @@
+mod document;
+mod editor;
+mod format;
+mod history;
+mod hit_test;
+mod shapes;
+mod snap;
+mod svg;
+mod view;
+
+use self::{
+    editor::{Editor, LayerPanel, Tool, AUTOSAVE_KEY},
+    history::Command,
+    view::View,
+};
+use crate::{
+    color,
+    linear_algebra::{Vec2, Vec4},
+    utils,
+};
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::WebGlRenderingContext as Gl;
+
+// How close, in pixels, the pointer has to be to pick a vertex or an edge
+const PICK_RADIUS: f32 = 6.0;
+// Zoom factor per pixel of wheel scrolling
+const WHEEL_ZOOM_RATE: f32 = 0.002;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 v_Position;
+attribute vec4 v_Color;
+
+// World to clip space: xy scales, zw offsets
+uniform vec4 u_View;
+
+varying vec4 f_Color;
+
+void main() {
+    gl_Position = vec4(v_Position.xy * u_View.xy + u_View.zw, 0.0, 1.0);
+    gl_PointSize = 5.0;
+    f_Color = v_Color;
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+varying vec4 f_Color;
+
+void main() {
+    gl_FragColor = f_Color;
+}
+"#;
+
+// Converts a mouse position to normalized device coordinates
+fn to_ndc(event: &web_sys::MouseEvent, canvas: &web_sys::HtmlCanvasElement) -> Vec2 {
+    Vec2(
+        -1.0 + (2.0 * event.offset_x() as f32) / canvas.width() as f32,
+        -1.0 + (2.0 * (canvas.height() as f32 - event.offset_y() as f32)) / canvas.height() as f32,
+    )
+}
+
+struct SnapInputs {
+    show_grid: web_sys::HtmlInputElement,
+    grid_spacing: web_sys::HtmlInputElement,
+    to_grid: web_sys::HtmlInputElement,
+    to_vertices: web_sys::HtmlInputElement,
+    to_angle: web_sys::HtmlInputElement,
+}
+
+impl SnapInputs {
+    fn new() -> Result<SnapInputs, JsValue> {
+        let input = |id: &str| -> Result<web_sys::HtmlInputElement, JsValue> {
+            let element = get_element(id).ok_or(format!("{} not found", id))?;
+            Ok(element.dyn_into::<web_sys::HtmlInputElement>()?)
+        };
+        Ok(SnapInputs {
+            show_grid: input("show-grid")?,
+            grid_spacing: input("grid-spacing")?,
+            to_grid: input("snap-grid")?,
+            to_vertices: input("snap-vertices")?,
+            to_angle: input("snap-angle")?,
+        })
+    }
+
+    fn elements(&self) -> [&web_sys::HtmlInputElement; 5] {
+        [
+            &self.show_grid,
+            &self.grid_spacing,
+            &self.to_grid,
+            &self.to_vertices,
+            &self.to_angle,
+        ]
+    }
+
+    fn settings(&self, radius: f32) -> snap::Settings {
+        snap::Settings {
+            show_grid: self.show_grid.checked(),
+            grid_spacing: self.grid_spacing.value().parse().unwrap_or(0.0),
+            to_grid: self.to_grid.checked(),
+            to_vertices: self.to_vertices.checked(),
+            to_angle: self.to_angle.checked(),
+            radius,
+        }
+    }
+}
+
+struct ColorInputs {
+    picker: web_sys::HtmlInputElement,
+    opacity: web_sys::HtmlInputElement,
+}
+
+impl ColorInputs {
+    fn new() -> Result<ColorInputs, JsValue> {
+        let picker = get_element("color-picker").ok_or("color picker not found")?;
+        let opacity = get_element("opacity").ok_or("opacity not found")?;
+        Ok(ColorInputs {
+            picker: picker.dyn_into::<web_sys::HtmlInputElement>()?,
+            opacity: opacity.dyn_into::<web_sys::HtmlInputElement>()?,
+        })
+    }
+
+    fn color(&self) -> Option<Vec4> {
+        let rgb = color::hex_to_rgba(&self.picker.value())?;
+        let alpha = self.opacity.value().parse::<f32>().unwrap_or(1.0);
+        Some(Vec4(rgb.0, rgb.1, rgb.2, alpha.clamp(0.0, 1.0)))
+    }
+}
+
+struct ShapeInputs {
+    segments: web_sys::HtmlInputElement,
+    sides: web_sys::HtmlInputElement,
+    stroke_width: web_sys::HtmlInputElement,
+    join: web_sys::HtmlSelectElement,
+}
+
+impl ShapeInputs {
+    fn new() -> Result<ShapeInputs, JsValue> {
+        let input = |id: &str| -> Result<web_sys::HtmlInputElement, JsValue> {
+            let element = get_element(id).ok_or(format!("{} not found", id))?;
+            Ok(element.dyn_into::<web_sys::HtmlInputElement>()?)
+        };
+        let join = get_element("join").ok_or("join not found")?;
+        Ok(ShapeInputs {
+            segments: input("segments")?,
+            sides: input("sides")?,
+            stroke_width: input("stroke-width")?,
+            join: join.dyn_into::<web_sys::HtmlSelectElement>()?,
+        })
+    }
+
+    fn elements(&self) -> [&web_sys::HtmlElement; 4] {
+        [&self.segments, &self.sides, &self.stroke_width, &self.join]
+    }
+
+    // Invalid entries fall back to the defaults and out of range counts are
+    // clamped
+    fn options(&self) -> shapes::Options {
+        let defaults = shapes::Options::default();
+        let count = |input: &web_sys::HtmlInputElement, default: usize, max: usize| {
+            input
+                .value()
+                .parse()
+                .unwrap_or(default)
+                .clamp(shapes::MIN_CORNERS, max)
+        };
+        shapes::Options {
+            segments: count(&self.segments, defaults.segments, shapes::MAX_SEGMENTS),
+            sides: count(&self.sides, defaults.sides, shapes::MAX_SIDES),
+            stroke_width: self
+                .stroke_width
+                .value()
+                .parse()
+                .unwrap_or(defaults.stroke_width),
+            join: shapes::Join::parse(&self.join.value()).unwrap_or(defaults.join),
+        }
+    }
+}
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let color_inputs = Rc::new(ColorInputs::new()?);
+
+    let end_polygon_btn = get_element("end-polygon").ok_or("end-polygon not found")?;
+    let end_polygon_btn = end_polygon_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let undo_btn = get_element("undo").ok_or("undo not found")?;
+    let undo_btn = undo_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let redo_btn = get_element("redo").ok_or("redo not found")?;
+    let redo_btn = redo_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let save_btn = get_element("save").ok_or("save not found")?;
+    let save_btn = save_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let load_input = get_element("load").ok_or("load not found")?;
+    let load_input = load_input.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let export_svg_btn = get_element("export-svg").ok_or("export-svg not found")?;
+    let export_svg_btn = export_svg_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let import_svg_input = get_element("import-svg").ok_or("import-svg not found")?;
+    let import_svg_input = import_svg_input.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let tool_select = get_element("tool").ok_or("tool not found")?;
+    let tool_select = tool_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
+    let coordinates_input = get_element("coordinates").ok_or("coordinates not found")?;
+    let coordinates_input = coordinates_input.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let add_vertex_btn = get_element("add-vertex").ok_or("add-vertex not found")?;
+    let add_vertex_btn = add_vertex_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let bring_forward_btn = get_element("bring-forward").ok_or("bring-forward not found")?;
+    let bring_forward_btn = bring_forward_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let send_backward_btn = get_element("send-backward").ok_or("send-backward not found")?;
+    let send_backward_btn = send_backward_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let add_layer_btn = get_element("add-layer").ok_or("add-layer not found")?;
+    let add_layer_btn = add_layer_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let layer_select = get_element("layer").ok_or("layer not found")?;
+    let layer_panel = LayerPanel {
+        select: layer_select.dyn_into::<web_sys::HtmlSelectElement>()?,
+        visible: get_element("layer-visible")
+            .ok_or("layer-visible not found")?
+            .dyn_into::<web_sys::HtmlInputElement>()?,
+        locked: get_element("layer-locked")
+            .ok_or("layer-locked not found")?
+            .dyn_into::<web_sys::HtmlInputElement>()?,
+    };
+    let (layer_select, layer_visible, layer_locked) = (
+        layer_panel.select.clone(),
+        layer_panel.visible.clone(),
+        layer_panel.locked.clone(),
+    );
+
+    let zoom_fit_btn = get_element("zoom-fit").ok_or("zoom-fit not found")?;
+    let zoom_fit_btn = zoom_fit_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let cursor_readout = get_element("cursor").ok_or("cursor not found")?;
+
+    let snap_inputs = Rc::new(SnapInputs::new()?);
+    let shape_inputs = Rc::new(ShapeInputs::new()?);
+
+    let status = get_element("status").ok_or("status not found")?;
+
+    let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+    let program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+    context.use_program(Some(&program));
+
+    let v_position = match context.get_attrib_location(&program, "v_Position") {
+        -1 => Err("unable to get location for v_Position"),
+        p => Ok(p as u32),
+    }?;
+    context.enable_vertex_attrib_array(v_position);
+
+    let v_color = match context.get_attrib_location(&program, "v_Color") {
+        -1 => Err("unable to get location for v_Color"),
+        p => Ok(p as u32),
+    }?;
+    context.enable_vertex_attrib_array(v_color);
+
+    let u_view = context
+        .get_uniform_location(&program, "u_View")
+        .ok_or("unable to get location for u_View")?;
+
+    context.clear_color(0.5, 0.5, 0.5, 1.0);
+    // Polygons are drawn back to front, so plain "over" blending is enough
+    context.enable(Gl::BLEND);
+    context.blend_func(Gl::SRC_ALPHA, Gl::ONE_MINUS_SRC_ALPHA);
+
+    let autosaved = utils::local_storage()
+        .and_then(|storage| storage.get_item(AUTOSAVE_KEY).ok().flatten())
+        .and_then(|json| format::from_json(&json).ok());
+
+    let pick_radius = 2.0 * PICK_RADIUS / canvas.height() as f32;
+    let aspect = canvas.width() as f32 / canvas.height() as f32;
+    let editor = Rc::new(Editor::new(
+        context,
+        autosaved.unwrap_or_default(),
+        View::new(aspect),
+        undo_btn.clone(),
+        redo_btn.clone(),
+        layer_panel,
+        status,
+    )?);
+    editor.set_snap(snap_inputs.settings(pick_radius));
+    editor.set_shape_options(shape_inputs.options());
+
+    for element in snap_inputs.elements().iter() {
+        let editor = editor.clone();
+        let snap_inputs = snap_inputs.clone();
+        utils::add_event_listener(element, "change", move |_event| {
+            editor.set_snap(snap_inputs.settings(pick_radius));
+        });
+    }
+    for element in shape_inputs.elements().iter() {
+        let editor = editor.clone();
+        let shape_inputs = shape_inputs.clone();
+        utils::add_event_listener(element, "change", move |_event| {
+            editor.set_shape_options(shape_inputs.options());
+        });
+    }
+    {
+        let editor = editor.clone();
+        let color_inputs = color_inputs.clone();
+        let coordinates_input_ref = coordinates_input.clone();
+        let add_vertex = move || {
+            if let Some(color) = color_inputs.color() {
+                if editor.add_vertex(&coordinates_input_ref.value(), color) {
+                    coordinates_input_ref.set_value("");
+                }
+            }
+        };
+        let add_vertex = Rc::new(add_vertex);
+        {
+            let add_vertex = add_vertex.clone();
+            utils::add_event_listener(&add_vertex_btn, "click", move |_event| add_vertex());
+        }
+        utils::add_event_listener(&coordinates_input, "keydown", move |event| {
+            let event = event.dyn_into::<web_sys::KeyboardEvent>().unwrap();
+            if event.key() == "Enter" {
+                add_vertex();
+            }
+        });
+    }
+    {
+        let editor = editor.clone();
+        let tool_select_ref = tool_select.clone();
+        utils::add_event_listener(&tool_select, "change", move |_event| {
+            if let Some(tool) = Tool::parse(&tool_select_ref.value()) {
+                editor.set_tool(tool);
+            }
+        });
+    }
+    for element in [&color_inputs.picker, &color_inputs.opacity].iter() {
+        let editor = editor.clone();
+        let color_inputs = color_inputs.clone();
+        utils::add_event_listener(element, "change", move |_event| {
+            if let Some(color) = color_inputs.color() {
+                editor.recolor_selection(color);
+            }
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&bring_forward_btn, "click", move |_event| {
+            editor.reorder_selection(true)
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&send_backward_btn, "click", move |_event| {
+            editor.reorder_selection(false)
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&add_layer_btn, "click", move |_event| editor.add_layer());
+    }
+    {
+        let editor = editor.clone();
+        let layer_select_ref = layer_select.clone();
+        utils::add_event_listener(&layer_select, "change", move |_event| {
+            if let Ok(index) = layer_select_ref.value().parse() {
+                editor.set_active_layer(index);
+            }
+        });
+    }
+    for element in [&layer_visible, &layer_locked].iter() {
+        let editor = editor.clone();
+        let (layer_visible, layer_locked) = (layer_visible.clone(), layer_locked.clone());
+        utils::add_event_listener(element, "change", move |_event| {
+            editor.update_active_layer(layer_visible.checked(), layer_locked.checked());
+        });
+    }
+
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&end_polygon_btn, "click", move |_event| {
+            editor.end_shape();
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&save_btn, "click", move |_event| {
+            let json = format::to_json(&editor.document.borrow());
+            if let Err(e) = utils::download("drawing.json", &json, "application/json") {
+                editor.status.set_text_content(Some(&utils::error_text(&e)));
+            }
+        });
+    }
+    {
+        let editor = editor.clone();
+        let load_input_ref = load_input.clone();
+        utils::add_event_listener(&load_input, "change", move |_event| {
+            if let Some(file) = load_input_ref.files().and_then(|files| files.get(0)) {
+                let editor = editor.clone();
+                utils::read_file(&file, move |text| {
+                    let drawing = text
+                        .map_err(|e| utils::error_text(&e))
+                        .and_then(|text| format::from_json(&text));
+                    match drawing {
+                        Ok(after) => {
+                            let before = editor.document.borrow().clone();
+                            editor.execute(Command::Replace { before, after });
+                        }
+                        Err(e) => editor.status.set_text_content(Some(&e)),
+                    }
+                });
+            }
+            load_input_ref.set_value("");
+        });
+    }
+    {
+        let editor = editor.clone();
+        let (width, height) = (canvas.width(), canvas.height());
+        utils::add_event_listener(&export_svg_btn, "click", move |_event| {
+            let svg = svg::export(&editor.document.borrow(), width, height);
+            if let Err(e) = utils::download("drawing.svg", &svg, "image/svg+xml") {
+                editor.status.set_text_content(Some(&utils::error_text(&e)));
+            }
+        });
+    }
+    {
+        let editor = editor.clone();
+        let import_svg_input_ref = import_svg_input.clone();
+        let (width, height) = (canvas.width(), canvas.height());
+        utils::add_event_listener(&import_svg_input, "change", move |_event| {
+            if let Some(file) = import_svg_input_ref.files().and_then(|files| files.get(0)) {
+                let editor = editor.clone();
+                utils::read_file(&file, move |text| {
+                    let polygons = text
+                        .map_err(|e| utils::error_text(&e))
+                        .and_then(|text| svg::import(&text, &editor.view(), width, height));
+                    match polygons {
+                        Ok(polygons) => editor.import(polygons),
+                        Err(e) => editor.status.set_text_content(Some(&e)),
+                    }
+                });
+            }
+            import_svg_input_ref.set_value("");
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&undo_btn, "click", move |_event| editor.undo());
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&redo_btn, "click", move |_event| editor.redo());
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&zoom_fit_btn, "click", move |_event| editor.zoom_to_fit());
+    }
+    // Dragging with the space bar held down pans, as does middle dragging
+    let space_held = Rc::new(Cell::new(false));
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("document not found")?;
+    {
+        let space_held = space_held.clone();
+        utils::add_event_listener(&document, "keyup", move |event| {
+            let event = event.dyn_into::<web_sys::KeyboardEvent>().unwrap();
+            if event.key() == " " {
+                space_held.set(false);
+            }
+        });
+    }
+    {
+        let editor = editor.clone();
+        let space_held = space_held.clone();
+        utils::add_event_listener(&document, "keydown", move |event| {
+            let event = event.dyn_into::<web_sys::KeyboardEvent>().unwrap();
+            let editing_text = match event.target() {
+                Some(t) => {
+                    t.dyn_ref::<web_sys::HtmlInputElement>().is_some()
+                        || t.dyn_ref::<web_sys::HtmlSelectElement>().is_some()
+                        || t.dyn_ref::<web_sys::HtmlTextAreaElement>().is_some()
+                }
+                None => false,
+            };
+            // Form fields keep their own keys, including their text undo
+            if editing_text && event.key() != "Escape" {
+                return;
+            }
+            if !(event.ctrl_key() || event.meta_key()) {
+                match event.key().as_str() {
+                    "Delete" | "Backspace" => editor.delete_selection(),
+                    " " => space_held.set(true),
+                    "Escape" => editor.cancel(),
+                    _ => return,
+                }
+                event.prevent_default();
+                return;
+            }
+            match event.key().as_str() {
+                "z" | "Z" if event.shift_key() => editor.redo(),
+                "z" | "Z" => editor.undo(),
+                "y" | "Y" => editor.redo(),
+                _ => return,
+            }
+            event.prevent_default();
+        });
+    }
+    let canvas = Rc::new(canvas);
+    {
+        let canvas_ref = canvas.clone();
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "mousedown", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let ndc = to_ndc(&event, &canvas_ref);
+
+            match event.button() {
+                0 if space_held.get() => editor.begin_pan(ndc),
+                0 => {
+                    if let Some(color) = color_inputs.color() {
+                        editor.mouse_down(ndc, color);
+                    }
+                }
+                1 => {
+                    // Keep the browser from starting to autoscroll
+                    event.prevent_default();
+                    editor.begin_pan(ndc);
+                }
+                _ => {}
+            }
+        });
+    }
+    {
+        let canvas_ref = canvas.clone();
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "wheel", move |event| {
+            let event = event.dyn_into::<web_sys::WheelEvent>().unwrap();
+            event.prevent_default();
+
+            // Line and page scrolling are converted to roughly equivalent pixels
+            let pixels = match event.delta_mode() {
+                web_sys::WheelEvent::DOM_DELTA_LINE => event.delta_y() * 40.0,
+                web_sys::WheelEvent::DOM_DELTA_PAGE => event.delta_y() * 800.0,
+                _ => event.delta_y(),
+            };
+            let factor = (-pixels as f32 * WHEEL_ZOOM_RATE).exp();
+            editor.zoom_at(to_ndc(&event, &canvas_ref), factor);
+        });
+    }
+    {
+        let canvas_ref = canvas.clone();
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "mousemove", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let p = editor.mouse_move(to_ndc(&event, &canvas_ref));
+            cursor_readout.set_text_content(Some(&format!("{:.3}, {:.3}", p.0, p.1)));
+        });
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "mouseup", move |_event| editor.mouse_up());
+    }
+    {
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "mouseleave", move |_event| editor.mouse_leave());
+    }
+    {
+        let canvas_ref = canvas.clone();
+        let editor = editor.clone();
+        utils::add_event_listener(&canvas, "dblclick", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            editor.double_click(to_ndc(&event, &canvas_ref));
+        });
+    }
+
+    utils::render_loop(move || editor.draw(v_position, v_color, &u_view));
+    Ok(())
+}
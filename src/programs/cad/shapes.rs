@@ -0,0 +1,358 @@
+use crate::{linear_algebra::Vec2, triangulation};
+use std::f32::consts::PI;
+
+// Joins longer than this many half widths are beveled instead, as in SVG
+pub const MITER_LIMIT: f32 = 4.0;
+
+// Bounds of the segment and side inputs. Fewer than three corners enclose
+// nothing, and far more only slow down triangulation.
+pub const MIN_CORNERS: usize = 3;
+pub const MAX_SEGMENTS: usize = 256;
+pub const MAX_SIDES: usize = 64;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rectangle,
+    Circle,
+    Ellipse,
+    RegularPolygon,
+    Line,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Join {
+    Miter,
+    Bevel,
+}
+
+impl Join {
+    pub fn parse(join: &str) -> Option<Join> {
+        match join {
+            "miter" => Some(Join::Miter),
+            "bevel" => Some(Join::Bevel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Options {
+    pub segments: usize,
+    pub sides: usize,
+    pub stroke_width: f32,
+    pub join: Join,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            segments: 32,
+            sides: 6,
+            stroke_width: 0.02,
+            join: Join::Miter,
+        }
+    }
+}
+
+// Axis-aligned rectangle with opposite corners `a` and `b`
+pub fn rectangle(a: Vec2, b: Vec2) -> Vec<Vec2> {
+    vec![a, Vec2(b.0, a.1), b, Vec2(a.0, b.1)]
+}
+
+pub fn ellipse(center: Vec2, radii: Vec2, segments: usize) -> Vec<Vec2> {
+    (0..segments)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f32 / segments as f32;
+            center + Vec2(radii.0 * angle.cos(), radii.1 * angle.sin())
+        })
+        .collect()
+}
+
+// Regular polygon centered on `center` with one corner at `corner`
+pub fn regular_polygon(center: Vec2, corner: Vec2, sides: usize) -> Vec<Vec2> {
+    let d = corner - center;
+    let radius = d.length();
+    let start = d.1.atan2(d.0);
+    (0..sides)
+        .map(|i| {
+            let angle = start + 2.0 * PI * i as f32 / sides as f32;
+            center + Vec2(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+fn normal(a: Vec2, b: Vec2) -> Vec2 {
+    let d = b - a;
+    let d = d * (1.0 / d.length());
+    Vec2(-d.1, d.0)
+}
+
+fn dedup(points: &[Vec2]) -> Vec<Vec2> {
+    let mut result: Vec<Vec2> = vec![];
+    for &p in points {
+        match result.last() {
+            Some(&last) if (p - last).length() <= f32::EPSILON => {}
+            _ => result.push(p),
+        }
+    }
+    result
+}
+
+// The offset points on either side of interior point `p` between segments
+// with normals `n1` and `n2`. The inner side of the turn always meets at the
+// miter point; the outer side is mitered or beveled.
+fn join(p: Vec2, n1: Vec2, n2: Vec2, h: f32, join: Join) -> Option<(Vec<Vec2>, Vec<Vec2>)> {
+    let sum = n1 + n2;
+    if sum.length() <= f32::EPSILON {
+        // The line doubles back on itself
+        return None;
+    }
+    if n1.cross(&n2).abs() <= f32::EPSILON {
+        return Some((vec![p + n1 * h], vec![p - n1 * h]));
+    }
+    let m = sum * (1.0 / sum.length());
+    let scale = 1.0 / m.dot(&n1);
+    let miter = m * (h * scale);
+
+    let outer = if join == Join::Miter && scale <= MITER_LIMIT {
+        vec![miter]
+    } else {
+        vec![n1 * h, n2 * h]
+    };
+
+    let (left, right) = if n1.cross(&n2) > 0.0 {
+        // Turning left, so the left side is on the inside
+        (vec![miter], outer.iter().map(|&o| o * -1.0).collect())
+    } else {
+        (outer, vec![miter * -1.0])
+    };
+    Some((
+        left.into_iter().map(|o| p + o).collect(),
+        right.into_iter().map(|o| p + o).collect(),
+    ))
+}
+
+// Outline of a polyline stroked `width` wide with butt ends: the left offset
+// forwards, then the right offset backwards. `None` if the outline would
+// cross itself.
+pub fn stroke_outline(points: &[Vec2], width: f32, join_style: Join) -> Option<Vec<Vec2>> {
+    let points = dedup(points);
+    let n = points.len();
+    if n < 2 || width <= 0.0 {
+        return None;
+    }
+    let h = width / 2.0;
+
+    let first = normal(points[0], points[1]);
+    let last = normal(points[n - 2], points[n - 1]);
+    let mut left = vec![points[0] + first * h];
+    let mut right = vec![points[0] - first * h];
+    for i in 1..n - 1 {
+        let n1 = normal(points[i - 1], points[i]);
+        let n2 = normal(points[i], points[i + 1]);
+        let (l, r) = join(points[i], n1, n2, h, join_style)?;
+        left.extend(l);
+        right.extend(r);
+    }
+    left.push(points[n - 1] + last * h);
+    right.push(points[n - 1] - last * h);
+
+    left.extend(right.into_iter().rev());
+    if triangulation::self_intersection(&left).is_some() {
+        None
+    } else {
+        Some(left)
+    }
+}
+
+// A stroke as separate simple pieces: one quad per segment and one polygon
+// filling the outside of each join. Used when the outline crosses itself.
+pub fn stroke_pieces(points: &[Vec2], width: f32, join_style: Join) -> Vec<Vec<Vec2>> {
+    let points = dedup(points);
+    if points.len() < 2 || width <= 0.0 {
+        return vec![];
+    }
+    let h = width / 2.0;
+
+    let mut pieces: Vec<Vec<Vec2>> = points
+        .windows(2)
+        .map(|s| {
+            let offset = normal(s[0], s[1]) * h;
+            vec![s[0] + offset, s[0] - offset, s[1] - offset, s[1] + offset]
+        })
+        .collect();
+
+    for s in points.windows(3) {
+        let (n1, n2) = (normal(s[0], s[1]), normal(s[1], s[2]));
+        let turn = n1.cross(&n2);
+        if turn.abs() <= f32::EPSILON {
+            continue;
+        }
+        // The outside of the turn is on the right when turning left
+        let side = if turn > 0.0 { -h } else { h };
+        let (a, b) = (s[1] + n1 * side, s[1] + n2 * side);
+
+        let sum = n1 + n2;
+        let scale = if sum.length() > f32::EPSILON {
+            1.0 / (sum * (1.0 / sum.length())).dot(&n1)
+        } else {
+            f32::INFINITY
+        };
+        if join_style == Join::Miter && scale <= MITER_LIMIT {
+            let miter = s[1] + sum * (1.0 / sum.length()) * (side * scale);
+            pieces.push(vec![s[1], a, miter, b]);
+        } else {
+            pieces.push(vec![s[1], a, b]);
+        }
+    }
+
+    pieces
+}
+
+// Polygons for a shape from its control points: the drag start and end, or
+// every clicked point of a line
+pub fn build(shape: Shape, points: &[Vec2], options: &Options) -> Vec<Vec<Vec2>> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return vec![],
+    };
+    let d = last - first;
+
+    match shape {
+        Shape::Rectangle => vec![rectangle(first, last)],
+        Shape::Circle => vec![ellipse(
+            first,
+            Vec2(d.length(), d.length()),
+            options.segments.max(3),
+        )],
+        Shape::Ellipse => vec![ellipse(
+            (first + last) * 0.5,
+            Vec2(d.0.abs() / 2.0, d.1.abs() / 2.0),
+            options.segments.max(3),
+        )],
+        Shape::RegularPolygon => vec![regular_polygon(first, last, options.sides.max(3))],
+        Shape::Line => match stroke_outline(points, options.stroke_width, options.join) {
+            Some(outline) => vec![outline],
+            None => stroke_pieces(points, options.stroke_width, options.join),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triangulation::signed_area;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn rectangle_from_either_corner() {
+        let a = rectangle(Vec2(0.0, 0.0), Vec2(2.0, 1.5));
+        assert_eq!(a.len(), 4);
+        assert_eq!(signed_area(&a), 3.0);
+        // Dragging the other way flips the winding but not the area
+        let b = rectangle(Vec2(2.0, 0.0), Vec2(0.0, 1.5));
+        assert_eq!(signed_area(&b).abs(), 3.0);
+    }
+
+    #[test]
+    fn ellipse_points_lie_on_the_ellipse() {
+        let center = Vec2(1.0, -1.0);
+        let points = ellipse(center, Vec2(2.0, 0.5), 24);
+        assert_eq!(points.len(), 24);
+        for p in &points {
+            let d = *p - center;
+            assert_near((d.0 / 2.0).powi(2) + (d.1 / 0.5).powi(2), 1.0);
+        }
+    }
+
+    #[test]
+    fn regular_polygon_starts_at_the_corner() {
+        let (center, corner) = (Vec2(0.0, 0.0), Vec2(0.0, 2.0));
+        let hexagon = regular_polygon(center, corner, 6);
+        assert_eq!(hexagon.len(), 6);
+        assert!((hexagon[0] - corner).length() < 1e-5);
+        // n/2 r^2 sin(2pi/n)
+        assert_near(signed_area(&hexagon), 3.0 * 4.0 * (PI / 3.0).sin());
+    }
+
+    #[test]
+    fn build_uses_at_least_three_corners() {
+        let options = Options {
+            segments: 1,
+            sides: 0,
+            ..Options::default()
+        };
+        let drag = [Vec2(0.0, 0.0), Vec2(1.0, 1.0)];
+        assert_eq!(build(Shape::Circle, &drag, &options)[0].len(), 3);
+        assert_eq!(build(Shape::Ellipse, &drag, &options)[0].len(), 3);
+        assert_eq!(build(Shape::RegularPolygon, &drag, &options)[0].len(), 3);
+        assert_eq!(build(Shape::Rectangle, &drag, &options)[0].len(), 4);
+        assert!(build(Shape::Rectangle, &[], &options).is_empty());
+
+        let options = Options::default();
+        assert_eq!(build(Shape::Circle, &drag, &options)[0].len(), 32);
+        assert_eq!(build(Shape::RegularPolygon, &drag, &options)[0].len(), 6);
+    }
+
+    #[test]
+    fn straight_stroke_is_a_rectangle() {
+        let outline = stroke_outline(&[Vec2(0.0, 0.0), Vec2(2.0, 0.0)], 0.5, Join::Miter).unwrap();
+        assert_eq!(
+            outline,
+            vec![
+                Vec2(0.0, 0.25),
+                Vec2(2.0, 0.25),
+                Vec2(2.0, -0.25),
+                Vec2(0.0, -0.25),
+            ]
+        );
+    }
+
+    #[test]
+    fn mitered_corner_closes_the_outline() {
+        let corner = [Vec2(0.0, 0.0), Vec2(2.0, 0.0), Vec2(2.0, 2.0)];
+        let miter = stroke_outline(&corner, 0.5, Join::Miter).unwrap();
+        // Both offsets get one point at the corner
+        assert_eq!(miter.len(), 6);
+        assert!(triangulation::self_intersection(&miter).is_none());
+        // The two 2 x 0.5 arms, plus the square where they overlap outside
+        assert_near(signed_area(&miter).abs(), 2.0 * 2.0 * 0.5);
+
+        let bevel = stroke_outline(&corner, 0.5, Join::Bevel).unwrap();
+        assert_eq!(bevel.len(), 7);
+        assert_near(signed_area(&bevel).abs(), 2.0 - 0.25 * 0.25 / 2.0);
+    }
+
+    #[test]
+    fn crossing_strokes_fall_back_to_pieces() {
+        let zigzag = [
+            Vec2(0.0, 0.0),
+            Vec2(1.0, 0.0),
+            Vec2(1.0, 1.0),
+            Vec2(0.5, -1.0),
+        ];
+        assert!(stroke_outline(&zigzag, 0.1, Join::Miter).is_none());
+        let pieces = build(
+            Shape::Line,
+            &zigzag,
+            &Options {
+                stroke_width: 0.1,
+                ..Options::default()
+            },
+        );
+        // Three segments and two joins
+        assert_eq!(pieces.len(), 5);
+        assert!(pieces.iter().all(|p| triangulation::triangulate(p).is_ok()));
+    }
+
+    #[test]
+    fn degenerate_strokes_are_empty() {
+        let p = Vec2(1.0, 1.0);
+        assert!(stroke_outline(&[p, p], 0.1, Join::Miter).is_none());
+        assert!(stroke_outline(&[p, Vec2(2.0, 1.0)], 0.0, Join::Miter).is_none());
+        assert!(stroke_pieces(&[p, p, p], 0.1, Join::Bevel).is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use super::hit_test;
+use crate::linear_algebra::Vec2;
+
+pub const ANGLE_STEP_DEGREES: f32 = 15.0;
+const MAX_GRID_LINES: usize = 400;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub show_grid: bool,
+    pub grid_spacing: f32,
+    pub to_grid: bool,
+    pub to_vertices: bool,
+    pub to_angle: bool,
+    // How close a vertex has to be to be snapped to, or picked by the select
+    // tool
+    pub radius: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            show_grid: true,
+            grid_spacing: 0.1,
+            to_grid: false,
+            to_vertices: false,
+            to_angle: false,
+            radius: 0.025,
+        }
+    }
+}
+
+impl Settings {
+    fn grid(&self) -> Option<f32> {
+        if self.to_grid && self.grid_spacing > 0.0 {
+            Some(self.grid_spacing)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn to_grid(p: Vec2, spacing: f32) -> Vec2 {
+    Vec2(
+        (p.0 / spacing).round() * spacing,
+        (p.1 / spacing).round() * spacing,
+    )
+}
+
+// Rotates `p` about `anchor` to the nearest multiple of the angle step,
+// keeping its distance
+pub fn to_angle(p: Vec2, anchor: Vec2) -> Vec2 {
+    let d = p - anchor;
+    let length = d.length();
+    if length == 0.0 {
+        return p;
+    }
+    let step = ANGLE_STEP_DEGREES.to_radians();
+    let angle = (d.1.atan2(d.0) / step).round() * step;
+    anchor + Vec2(angle.cos(), angle.sin()) * length
+}
+
+// Snapping rules, in order of priority:
+// 1. the nearest of `vertices` within the radius, the first one on a tie
+// 2. the angle step around `anchor`, with the distance rounded to the grid
+//    spacing when grid snapping is on as well
+// 3. the nearest grid intersection
+pub fn snap(p: Vec2, settings: &Settings, vertices: &[Vec2], anchor: Option<Vec2>) -> Vec2 {
+    if settings.to_vertices {
+        if let Some(i) = hit_test::nearest_vertex(p, vertices, settings.radius) {
+            return vertices[i];
+        }
+    }
+
+    if let (true, Some(anchor)) = (settings.to_angle, anchor) {
+        let snapped = to_angle(p, anchor);
+        return match settings.grid() {
+            Some(spacing) => {
+                let d = snapped - anchor;
+                let length = d.length();
+                if length == 0.0 {
+                    snapped
+                } else {
+                    anchor + d * ((length / spacing).round() * spacing / length)
+                }
+            }
+            None => snapped,
+        };
+    }
+
+    match settings.grid() {
+        Some(spacing) => to_grid(p, spacing),
+        None => p,
+    }
+}
+
+// Grid lines covering the box from `min` to `max`, as a LINES stream. Nothing
+// is drawn when the lines would be too dense to be useful.
+pub fn grid_lines(spacing: f32, min: Vec2, max: Vec2) -> Vec<Vec2> {
+    if spacing <= 0.0 {
+        return vec![];
+    }
+
+    let columns = ((min.0 / spacing).ceil() as i64)..=((max.0 / spacing).floor() as i64);
+    let rows = ((min.1 / spacing).ceil() as i64)..=((max.1 / spacing).floor() as i64);
+    if columns.clone().count() + rows.clone().count() > MAX_GRID_LINES {
+        return vec![];
+    }
+
+    let mut lines = vec![];
+    for i in columns {
+        let x = i as f32 * spacing;
+        lines.extend_from_slice(&[Vec2(x, min.1), Vec2(x, max.1)]);
+    }
+    for i in rows {
+        let y = i as f32 * spacing;
+        lines.extend_from_slice(&[Vec2(min.0, y), Vec2(max.0, y)]);
+    }
+    lines
+}
+
+// Parses typed coordinates: "x, y" is absolute and "@dx, dy" is relative to
+// `last`, the previous vertex
+pub fn parse_point(input: &str, last: Option<Vec2>) -> Result<Vec2, String> {
+    let input = input.trim();
+    let (relative, coordinates) = match input.strip_prefix('@') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+
+    let numbers = coordinates
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<f32>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or(format!("invalid coordinate \"{}\"", s))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if numbers.len() != 2 {
+        return Err(String::from(
+            "expected two coordinates, \"x, y\" or \"@dx, dy\"",
+        ));
+    }
+
+    let p = Vec2(numbers[0], numbers[1]);
+    if relative {
+        let last = last.ok_or("no previous vertex for relative coordinates")?;
+        Ok(last + p)
+    } else {
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    fn settings(to_grid: bool, to_vertices: bool, to_angle: bool) -> Settings {
+        Settings {
+            grid_spacing: 0.5,
+            to_grid,
+            to_vertices,
+            to_angle,
+            radius: 0.1,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn snaps_to_the_nearest_grid_intersection() {
+        let grid = settings(true, false, false);
+        assert_close(snap(Vec2(0.7, -0.3), &grid, &[], None), Vec2(0.5, -0.5));
+        assert_close(snap(Vec2(0.8, 1.1), &grid, &[], None), Vec2(1.0, 1.0));
+
+        let off = settings(false, false, false);
+        assert_eq!(snap(Vec2(0.7, -0.3), &off, &[], None), Vec2(0.7, -0.3));
+        let no_spacing = Settings {
+            grid_spacing: 0.0,
+            ..grid
+        };
+        assert_eq!(
+            snap(Vec2(0.7, -0.3), &no_spacing, &[], None),
+            Vec2(0.7, -0.3)
+        );
+    }
+
+    #[test]
+    fn vertices_take_priority_over_the_grid() {
+        let vertices = [Vec2(0.33, 0.33), Vec2(0.38, 0.33)];
+        let all = settings(true, true, true);
+        assert_eq!(snap(Vec2(0.36, 0.3), &all, &vertices, None), vertices[1]);
+        assert_eq!(
+            snap(Vec2(0.36, 0.3), &all, &vertices, Some(Vec2(0.0, 0.0))),
+            vertices[1]
+        );
+        // Out of the radius, the grid wins again
+        assert_close(snap(Vec2(0.6, 0.6), &all, &vertices, None), Vec2(0.5, 0.5));
+    }
+
+    #[test]
+    fn angle_snapping_keeps_the_distance() {
+        let anchor = Vec2(1.0, 1.0);
+        let p = to_angle(anchor + Vec2(2.0, 0.1), anchor);
+        assert_close(p, anchor + Vec2(Vec2(2.0, 0.1).length(), 0.0));
+        assert_eq!(to_angle(anchor, anchor), anchor);
+
+        let angle = settings(false, false, true);
+        let p = snap(Vec2(1.0, 1.3), &angle, &[], Some(Vec2(0.0, 0.0)));
+        let expected = 45f32.to_radians();
+        assert!((p.1.atan2(p.0) - expected).abs() < 1e-5);
+        assert!((p.length() - Vec2(1.0, 1.3).length()).abs() < 1e-5);
+
+        // Without an anchor there is nothing to measure the angle from
+        assert_eq!(snap(Vec2(1.0, 1.3), &angle, &[], None), Vec2(1.0, 1.3));
+    }
+
+    #[test]
+    fn angle_and_grid_round_the_distance() {
+        let both = settings(true, false, true);
+        let p = snap(Vec2(0.0, 1.3), &both, &[], Some(Vec2(0.0, 0.0)));
+        assert_close(p, Vec2(0.0, 1.5));
+    }
+
+    #[test]
+    fn grid_lines_cover_the_box() {
+        let lines = grid_lines(0.5, Vec2(-0.7, 0.0), Vec2(0.7, 1.0));
+        // Columns at -0.5, 0 and 0.5, rows at 0, 0.5 and 1
+        assert_eq!(lines.len(), 2 * 6);
+        assert_eq!(lines[0], Vec2(-0.5, 0.0));
+        assert_eq!(lines[1], Vec2(-0.5, 1.0));
+        assert_eq!(lines[6], Vec2(-0.7, 0.0));
+        assert_eq!(lines[7], Vec2(0.7, 0.0));
+    }
+
+    #[test]
+    fn dense_or_empty_grids_are_not_drawn() {
+        assert!(grid_lines(0.0, Vec2(-1.0, -1.0), Vec2(1.0, 1.0)).is_empty());
+        assert!(grid_lines(0.001, Vec2(-1.0, -1.0), Vec2(1.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn parses_absolute_and_relative_points() {
+        assert_eq!(parse_point("1, 2", None), Ok(Vec2(1.0, 2.0)));
+        assert_eq!(parse_point(" -1.5 2e-1 ", None), Ok(Vec2(-1.5, 0.2)));
+        assert_eq!(
+            parse_point("@0.5,-1", Some(Vec2(1.0, 1.0))),
+            Ok(Vec2(1.5, 0.0))
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_points() {
+        for input in &["1,", "", "1, 2, 3", "x, 2", "inf, 0", "@1, 1"] {
+            assert!(
+                parse_point(input, None).is_err(),
+                "{:?} was accepted",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,572 @@
+use super::document::{Document, Polygon, Vertex};
+use super::view::{self, View};
+use crate::{
+    color::{hex_to_rgba, rgba_to_hex},
+    linear_algebra::{Vec2, Vec4},
+};
+
+const DEFAULT_FILL: Vec4 = Vec4(0.0, 0.0, 0.0, 1.0);
+
+fn to_pixels(world: Vec2, view: &View, width: f32, height: f32) -> Vec2 {
+    let p = view.world_to_ndc(world);
+    Vec2((p.0 + 1.0) / 2.0 * width, (1.0 - p.1) / 2.0 * height)
+}
+
+fn from_pixels(p: Vec2, view: &View, width: f32, height: f32) -> Vec2 {
+    view.ndc_to_world(Vec2(-1.0 + 2.0 * p.0 / width, 1.0 - 2.0 * p.1 / height))
+}
+
+// The view of a `width` by `height` canvas that fits every visible polygon
+fn framing(document: &Document, width: u32, height: u32) -> View {
+    let points: Vec<Vec2> = document
+        .draw_order()
+        .into_iter()
+        .flat_map(|i| document.polygons[i].positions())
+        .collect();
+    let view = View::new(width as f32 / height as f32);
+    match view::bounding_box(&points) {
+        Some((min, max)) => view.fit(min, max),
+        None => view,
+    }
+}
+
+// Writes every visible polygon as a `<polygon>` in the pixels of a `width` by
+// `height` canvas zoomed to fit the drawing, back to front. SVG has no
+// per-vertex colors, so each polygon is filled with the color of its first
+// vertex.
+pub fn export(document: &Document, width: u32, height: u32) -> String {
+    let (w, h) = (width as f32, height as f32);
+    let view = framing(document, width, height);
+    let mut svg = format!(
+        concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" ",
+            "width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n"
+        ),
+        width, height, width, height
+    );
+
+    for polygon in document
+        .draw_order()
+        .into_iter()
+        .map(|i| &document.polygons[i])
+    {
+        let points: Vec<String> = polygon
+            .vertices
+            .iter()
+            .map(|v| {
+                let p = to_pixels(v.position, &view, w, h);
+                format!("{},{}", p.0, p.1)
+            })
+            .collect();
+        let fill = polygon.vertices.first().map_or(DEFAULT_FILL, |v| v.color);
+
+        let opacity = if fill.3 < 1.0 {
+            format!(" fill-opacity=\"{}\"", fill.3)
+        } else {
+            String::new()
+        };
+
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\"{} />\n",
+            points.join(" "),
+            rgba_to_hex(Vec4(fill.0, fill.1, fill.2, 1.0)),
+            opacity
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+struct Element<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Element<'a> {
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn number(&self, name: &str) -> Result<f32, String> {
+        let value = self.attribute(name).unwrap_or("0");
+        value
+            .trim_end_matches("px")
+            .parse()
+            .map_err(|_| format!("invalid {} \"{}\" on <{}>", name, value, self.name))
+    }
+
+    // A presentation attribute, overridden by the same property in `style`
+    fn property(&self, name: &str) -> Option<&'a str> {
+        let from_style = self.attribute("style").and_then(|style| {
+            style.split(';').find_map(|declaration| {
+                let mut parts = declaration.splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) if key.trim() == name => Some(value.trim()),
+                    _ => None,
+                }
+            })
+        });
+        from_style.or_else(|| self.attribute(name))
+    }
+
+    // `fill` with `fill-opacity` and `opacity` applied as alpha
+    fn fill(&self) -> Option<Vec4> {
+        let color = parse_color(self.property("fill")?)?;
+        let alpha = ["fill-opacity", "opacity"]
+            .iter()
+            .filter_map(|name| self.property(name)?.parse::<f32>().ok())
+            .fold(color.3, |alpha, opacity| alpha * opacity.clamp(0.0, 1.0));
+        Some(Vec4(color.0, color.1, color.2, alpha))
+    }
+}
+
+fn parse_color(color: &str) -> Option<Vec4> {
+    if color.len() == 4 && color.starts_with('#') {
+        let expanded: String = color[1..].chars().flat_map(|c| vec![c, c]).collect();
+        hex_to_rgba(&format!("#{}", expanded))
+    } else {
+        hex_to_rgba(color)
+    }
+}
+
+// A minimal scanner for start and empty-element tags. Comments, text and
+// closing tags are skipped; nesting and transforms are ignored.
+fn elements(svg: &str) -> Result<Vec<Element<'_>>, String> {
+    let mut elements = vec![];
+    let mut rest = svg;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if rest.starts_with("!--") {
+            let end = rest.find("-->").ok_or("unterminated comment")?;
+            rest = &rest[end + 3..];
+            continue;
+        }
+
+        let end = rest.find('>').ok_or("unterminated tag")?;
+        let tag = rest[..end].trim_end_matches('/').trim();
+        rest = &rest[end + 1..];
+        if tag.starts_with('/') || tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut attributes = vec![];
+        let mut attrs = tag[name_end..].trim_start();
+        while let Some(eq) = attrs.find('=') {
+            let key = attrs[..eq].trim();
+            let value = attrs[eq + 1..].trim_start();
+            let quote = value.chars().next().ok_or("missing attribute value")?;
+            if quote != '"' && quote != '\'' {
+                return Err(format!("unquoted value for attribute {}", key));
+            }
+            let close = value[1..]
+                .find(quote)
+                .ok_or(format!("unterminated value for attribute {}", key))?;
+            attributes.push((key, &value[1..=close]));
+            attrs = value[close + 2..].trim_start();
+        }
+
+        elements.push(Element {
+            name: &tag[..name_end],
+            attributes,
+        });
+    }
+
+    Ok(elements)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+// Splits point lists and path data such as "M10-5L20,20z" into commands and
+// numbers.
+fn tokenize(data: &str) -> Result<Vec<Token>, String> {
+    fn flush(number: &mut String, tokens: &mut Vec<Token>) -> Result<(), String> {
+        if !number.is_empty() {
+            let value = number
+                .parse()
+                .map_err(|_| format!("invalid number \"{}\"", number))?;
+            tokens.push(Token::Number(value));
+            number.clear();
+        }
+        Ok(())
+    }
+
+    let mut tokens = vec![];
+    let mut number = String::new();
+
+    for c in data.chars() {
+        match c {
+            // A second point starts the next number, as in "M.5.5"
+            '.' if number.contains(&['.', 'e', 'E'][..]) => {
+                flush(&mut number, &mut tokens)?;
+                number.push(c);
+            }
+            '0'..='9' | '.' => number.push(c),
+            '-' | '+' if !number.ends_with('e') && !number.ends_with('E') => {
+                flush(&mut number, &mut tokens)?;
+                number.push(c);
+            }
+            '-' | '+' | 'e' | 'E' if !number.is_empty() => number.push(c),
+            c if c.is_ascii_alphabetic() => {
+                flush(&mut number, &mut tokens)?;
+                tokens.push(Token::Command(c));
+            }
+            c if c.is_whitespace() || c == ',' => flush(&mut number, &mut tokens)?,
+            c => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+    flush(&mut number, &mut tokens)?;
+
+    Ok(tokens)
+}
+
+fn points(data: &str) -> Result<Vec<Vec2>, String> {
+    let numbers = tokenize(data)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Number(n) => Ok(n),
+            Token::Command(c) => Err(format!("unexpected '{}' in points", c)),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if numbers.len() % 2 != 0 {
+        return Err(String::from("points has an odd number of coordinates"));
+    }
+    Ok(numbers.chunks(2).map(|c| Vec2(c[0], c[1])).collect())
+}
+
+// Subpaths of a path made of M, L and Z commands, absolute or relative
+fn path(data: &str) -> Result<Vec<Vec<Vec2>>, String> {
+    let mut subpaths: Vec<Vec<Vec2>> = vec![];
+    let mut current = Vec2(0.0, 0.0);
+    let mut command = None;
+    let mut tokens = tokenize(data)?.into_iter();
+
+    while let Some(token) = tokens.next() {
+        let number = match token {
+            Token::Command(c) => {
+                if c == 'Z' || c == 'z' {
+                    if let Some(start) = subpaths.last().and_then(|s| s.first()) {
+                        current = *start;
+                    }
+                    command = None;
+                    continue;
+                }
+                command = Some(c);
+                match tokens.next() {
+                    Some(Token::Number(n)) => n,
+                    _ => return Err(format!("missing coordinates after '{}'", c)),
+                }
+            }
+            Token::Number(n) => n,
+        };
+        let y = match tokens.next() {
+            Some(Token::Number(n)) => n,
+            _ => return Err(String::from("path coordinates must come in pairs")),
+        };
+
+        let p = match command {
+            Some('M') | Some('L') => Vec2(number, y),
+            Some('m') | Some('l') => current + Vec2(number, y),
+            Some(c) => return Err(format!("unsupported path command '{}'", c)),
+            None => return Err(String::from("path data must start with a command")),
+        };
+        match command {
+            Some('M') | Some('m') => {
+                subpaths.push(vec![p]);
+                // Further pairs after a moveto are implicit linetos
+                command = if command == Some('M') {
+                    Some('L')
+                } else {
+                    Some('l')
+                };
+            }
+            _ => match subpaths.last_mut() {
+                Some(subpath) => subpath.push(p),
+                None => return Err(String::from("path data must start with a moveto")),
+            },
+        }
+        current = p;
+    }
+
+    Ok(subpaths)
+}
+
+// Reads `<polygon>`, `<polyline>`, `<rect>` and `<path>` elements into
+// polygons, mapping the SVG's viewBox (or its size, or else the canvas size)
+// onto the canvas as `view` shows it. Open shapes are closed. The polygons are
+// returned on layer 0, and `Editor::import` moves them onto the active layer.
+pub fn import(svg: &str, view: &View, width: u32, height: u32) -> Result<Vec<Polygon>, String> {
+    let elements = elements(svg)?;
+
+    let root = elements.iter().find(|e| e.name == "svg");
+    let (origin, size) = match root.and_then(|r| r.attribute("viewBox")) {
+        Some(view_box) => {
+            let v = points(view_box)?;
+            if v.len() != 2 {
+                return Err(format!("invalid viewBox \"{}\"", view_box));
+            }
+            (v[0], v[1])
+        }
+        None => {
+            let w = root
+                .and_then(|r| r.number("width").ok())
+                .filter(|w| *w > 0.0);
+            let h = root
+                .and_then(|r| r.number("height").ok())
+                .filter(|h| *h > 0.0);
+            (
+                Vec2(0.0, 0.0),
+                Vec2(w.unwrap_or(width as f32), h.unwrap_or(height as f32)),
+            )
+        }
+    };
+
+    let mut polygons = vec![];
+    for element in &elements {
+        let shapes = match element.name {
+            "polygon" | "polyline" => vec![points(element.attribute("points").unwrap_or(""))?],
+            "rect" => {
+                let (x, y) = (element.number("x")?, element.number("y")?);
+                let (w, h) = (element.number("width")?, element.number("height")?);
+                vec![vec![
+                    Vec2(x, y),
+                    Vec2(x + w, y),
+                    Vec2(x + w, y + h),
+                    Vec2(x, y + h),
+                ]]
+            }
+            "path" => path(element.attribute("d").unwrap_or(""))?,
+            _ => continue,
+        };
+
+        let color = element.fill().unwrap_or(DEFAULT_FILL);
+        for shape in shapes {
+            let vertices = shape
+                .into_iter()
+                .map(|p| Vertex {
+                    position: from_pixels(p - origin, view, size.0, size.1),
+                    color,
+                })
+                .collect();
+            polygons.push(Polygon { vertices, layer: 0 });
+        }
+    }
+
+    Ok(polygons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::document::Layer;
+    use super::*;
+
+    fn assert_close(a: &[Vec2], b: &[Vec2]) {
+        assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+        for (p, q) in a.iter().zip(b) {
+            assert!((*p - *q).length() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn tokenizes_compact_numbers() {
+        use Token::{Command, Number};
+        assert_eq!(
+            tokenize("M.5.5L1.5.5").unwrap(),
+            vec![
+                Command('M'),
+                Number(0.5),
+                Number(0.5),
+                Command('L'),
+                Number(1.5),
+                Number(0.5),
+            ]
+        );
+        assert_eq!(
+            tokenize("M10-5L20,20z").unwrap(),
+            vec![
+                Command('M'),
+                Number(10.0),
+                Number(-5.0),
+                Command('L'),
+                Number(20.0),
+                Number(20.0),
+                Command('z'),
+            ]
+        );
+        assert_eq!(
+            tokenize("1e-2-3E+1 2e1.5").unwrap(),
+            vec![Number(0.01), Number(-30.0), Number(20.0), Number(0.5)]
+        );
+        assert!(tokenize("1;2").is_err());
+    }
+
+    #[test]
+    fn parses_point_lists() {
+        assert_close(
+            &points(" 0,0 10,0\n10 -10 ").unwrap(),
+            &[Vec2(0.0, 0.0), Vec2(10.0, 0.0), Vec2(10.0, -10.0)],
+        );
+        assert!(points("0,0 10").is_err());
+        assert!(points("0,0 L10,10").is_err());
+    }
+
+    #[test]
+    fn parses_absolute_and_relative_paths() {
+        let subpaths = path("M0 0 10 0 L10 10z m5 5 l1 0 0 1 Z").unwrap();
+        assert_eq!(subpaths.len(), 2);
+        assert_close(
+            &subpaths[0],
+            &[Vec2(0.0, 0.0), Vec2(10.0, 0.0), Vec2(10.0, 10.0)],
+        );
+        // After closing, relative moves start from the subpath's first point
+        assert_close(
+            &subpaths[1],
+            &[Vec2(5.0, 5.0), Vec2(6.0, 5.0), Vec2(6.0, 6.0)],
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_paths() {
+        for data in &["L1 1", "M0 0 Q1 1 2 2", "M0", "M0 0 L1", "0 0"] {
+            assert!(path(data).is_err(), "{:?} was accepted", data);
+        }
+    }
+
+    #[test]
+    fn imports_shapes_through_the_view_box() {
+        let svg = r##"<?xml version="1.0"?>
+            <!-- <rect width="1" height="1" /> -->
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="-10 -10 20 20">
+                <rect x="-10" y="-10" width="10" height="10" fill="#f00"/>
+                <g><polyline points="0,0 10,0 0,10" style="fill: #00ff00; opacity: 0.5"/></g>
+                <path d="M0 0h1" fill="#0000ff" />
+                <circle r="5" />
+            </svg>"##;
+        let view = View::new(1.0);
+        let result = import(svg, &view, 100, 100);
+        assert!(result.is_err(), "h is not a supported path command");
+
+        let polygons = import(&svg.replace("M0 0h1", "M0 0l5 5 -5 5"), &view, 100, 100).unwrap();
+        assert_eq!(polygons.len(), 3);
+        let positions: Vec<Vec<Vec2>> = polygons.iter().map(Polygon::positions).collect();
+        assert_close(
+            &positions[0],
+            &[
+                Vec2(-1.0, 1.0),
+                Vec2(0.0, 1.0),
+                Vec2(0.0, 0.0),
+                Vec2(-1.0, 0.0),
+            ],
+        );
+        assert_close(
+            &positions[1],
+            &[Vec2(0.0, 0.0), Vec2(1.0, 0.0), Vec2(0.0, -1.0)],
+        );
+        assert_eq!(polygons[0].vertices[0].color, Vec4(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(polygons[1].vertices[0].color, Vec4(0.0, 1.0, 0.0, 0.5));
+        assert_eq!(polygons[2].vertices[0].color, Vec4(0.0, 0.0, 1.0, 1.0));
+        assert!(polygons.iter().all(|p| p.layer == 0));
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let vertex = |x, y, color| Vertex {
+            position: Vec2(x, y),
+            color,
+        };
+        let red = Vec4(1.0, 0.0, 0.0, 1.0);
+        let see_through = Vec4(0.0, 0.2, 1.0, 0.25);
+
+        let mut document = Document::new();
+        document.layers.push(Layer::new("Layer 2"));
+        document.polygons = vec![
+            Polygon {
+                vertices: vec![
+                    vertex(0.1, 0.1, see_through),
+                    vertex(0.9, 0.3, see_through),
+                    vertex(0.2, 0.8, see_through),
+                ],
+                layer: 1,
+            },
+            Polygon {
+                vertices: vec![
+                    vertex(-0.5, -0.5, red),
+                    vertex(0.5, -0.5, red),
+                    vertex(0.0, 0.75, red),
+                ],
+                layer: 0,
+            },
+        ];
+
+        let svg = export(&document, 300, 200);
+        let imported = import(&svg, &framing(&document, 300, 200), 300, 200).unwrap();
+        // Exported back to front, so the bottom layer's polygon comes first
+        assert_eq!(imported.len(), 2);
+        for (original, copy) in [&document.polygons[1], &document.polygons[0]]
+            .iter()
+            .zip(&imported)
+        {
+            assert_close(&original.positions(), &copy.positions());
+            for (a, b) in original.vertices.iter().zip(&copy.vertices) {
+                assert_eq!(a.color, b.color);
+            }
+        }
+    }
+
+    #[test]
+    fn export_fits_the_drawing_without_distorting_it() {
+        let black = Vec4(0.0, 0.0, 0.0, 1.0);
+        let square = |x: f32, y: f32, side: f32| Polygon {
+            vertices: [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+                .iter()
+                .map(|&(dx, dy)| Vertex {
+                    position: Vec2(x + dx * side, y + dy * side),
+                    color: black,
+                })
+                .collect(),
+            layer: 0,
+        };
+        let mut document = Document::new();
+        document.polygons = vec![square(-7.0, 2.0, 3.0), square(5.0, -4.0, 1.5)];
+
+        let (width, height) = (400, 200);
+        let svg = export(&document, width, height);
+        let view = framing(&document, width, height);
+        let shapes: Vec<Vec<Vec2>> = elements(&svg)
+            .unwrap()
+            .iter()
+            .filter(|e| e.name == "polygon")
+            .map(|e| points(e.attribute("points").unwrap()).unwrap())
+            .collect();
+        assert_eq!(shapes.len(), 2);
+        for (original, pixels) in document.polygons.iter().zip(&shapes) {
+            for p in pixels {
+                assert!(p.0 >= 0.0 && p.0 <= 400.0 && p.1 >= 0.0 && p.1 <= 200.0);
+            }
+            // Squares stay square on a wide canvas
+            let (side_x, side_y) = (pixels[1].0 - pixels[0].0, pixels[0].1 - pixels[3].1);
+            assert!((side_x - side_y).abs() < 1e-3, "{} != {}", side_x, side_y);
+
+            let world: Vec<Vec2> = pixels
+                .iter()
+                .map(|&p| from_pixels(p, &view, width as f32, height as f32))
+                .collect();
+            assert_close(&world, &original.positions());
+        }
+
+        let imported = import(&svg, &view, width, height).unwrap();
+        for (original, copy) in document.polygons.iter().zip(&imported) {
+            assert_close(&original.positions(), &copy.positions());
+        }
+    }
+}
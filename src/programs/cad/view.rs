@@ -0,0 +1,216 @@
+use crate::linear_algebra::Vec2;
+
+pub const MIN_ZOOM: f32 = 0.01;
+pub const MAX_ZOOM: f32 = 1000.0;
+// Space left around the drawing by zoom to fit, as a fraction of its size
+const FIT_MARGIN: f32 = 0.1;
+
+// Maps world coordinates, which the document is stored in, to normalized
+// device coordinates. The default view shows world [-1, 1] vertically with
+// `center` in the middle of the canvas; `aspect` is width over height.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct View {
+    pub center: Vec2,
+    pub zoom: f32,
+    pub aspect: f32,
+}
+
+impl View {
+    pub fn new(aspect: f32) -> View {
+        View {
+            center: Vec2(0.0, 0.0),
+            zoom: 1.0,
+            aspect,
+        }
+    }
+
+    fn scale(&self) -> Vec2 {
+        Vec2(self.zoom / self.aspect, self.zoom)
+    }
+
+    pub fn world_to_ndc(&self, world: Vec2) -> Vec2 {
+        let scale = self.scale();
+        let d = world - self.center;
+        Vec2(d.0 * scale.0, d.1 * scale.1)
+    }
+
+    pub fn ndc_to_world(&self, ndc: Vec2) -> Vec2 {
+        let scale = self.scale();
+        self.center + Vec2(ndc.0 / scale.0, ndc.1 / scale.1)
+    }
+
+    // A length on screen in NDC y units as a length in world units
+    pub fn world_length(&self, ndc_length: f32) -> f32 {
+        ndc_length / self.zoom
+    }
+
+    // Scale and offset for the vertex shader, `ndc = world * scale + offset`
+    pub fn uniform(&self) -> [f32; 4] {
+        let scale = self.scale();
+        let offset = self.world_to_ndc(Vec2(0.0, 0.0));
+        [scale.0, scale.1, offset.0, offset.1]
+    }
+
+    // World corners of the visible area, bottom left and top right
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        (
+            self.ndc_to_world(Vec2(-1.0, -1.0)),
+            self.ndc_to_world(Vec2(1.0, 1.0)),
+        )
+    }
+
+    // Zooms by `factor` keeping the world point under `ndc` where it is
+    pub fn zoom_about(&self, ndc: Vec2, factor: f32) -> View {
+        let anchor = self.ndc_to_world(ndc);
+        let mut view = View {
+            zoom: (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM),
+            ..*self
+        };
+        view.center = view.center + (anchor - view.ndc_to_world(ndc));
+        view
+    }
+
+    // Moves the drawing along with a drag of `ndc_delta` on screen
+    pub fn pan(&self, ndc_delta: Vec2) -> View {
+        let scale = self.scale();
+        View {
+            center: self.center - Vec2(ndc_delta.0 / scale.0, ndc_delta.1 / scale.1),
+            ..*self
+        }
+    }
+
+    // Centers the box from `min` to `max` and zooms so it fills the canvas.
+    // An empty box only moves the center.
+    pub fn fit(&self, min: Vec2, max: Vec2) -> View {
+        let size = (max - min) * (1.0 + FIT_MARGIN);
+        let zoom = if size.0 > 0.0 || size.1 > 0.0 {
+            let by_width = if size.0 > 0.0 {
+                2.0 * self.aspect / size.0
+            } else {
+                f32::INFINITY
+            };
+            let by_height = if size.1 > 0.0 {
+                2.0 / size.1
+            } else {
+                f32::INFINITY
+            };
+            by_width.min(by_height).clamp(MIN_ZOOM, MAX_ZOOM)
+        } else {
+            self.zoom
+        };
+
+        View {
+            center: (min + max) * 0.5,
+            zoom,
+            aspect: self.aspect,
+        }
+    }
+}
+
+// Smallest box containing every point, if there are any
+pub fn bounding_box(points: &[Vec2]) -> Option<(Vec2, Vec2)> {
+    let first = *points.first()?;
+    Some(points.iter().fold((first, first), |(min, max), p| {
+        (
+            Vec2(min.0.min(p.0), min.1.min(p.1)),
+            Vec2(max.0.max(p.0), max.1.max(p.1)),
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn view() -> View {
+        View {
+            center: Vec2(0.5, -2.0),
+            zoom: 2.5,
+            aspect: 1.5,
+        }
+    }
+
+    #[test]
+    fn ndc_to_world_inverts_world_to_ndc() {
+        let view = view();
+        let world = Vec2(3.0, 1.25);
+        assert_close(view.ndc_to_world(view.world_to_ndc(world)), world);
+        assert_close(view.world_to_ndc(view.center), Vec2(0.0, 0.0));
+
+        let [sx, sy, ox, oy] = view.uniform();
+        let ndc = view.world_to_ndc(world);
+        assert_close(Vec2(world.0 * sx + ox, world.1 * sy + oy), ndc);
+    }
+
+    #[test]
+    fn default_view_shows_unit_height() {
+        let (min, max) = View::new(2.0).bounds();
+        assert_close(min, Vec2(-2.0, -1.0));
+        assert_close(max, Vec2(2.0, 1.0));
+    }
+
+    #[test]
+    fn zoom_keeps_the_anchor_fixed() {
+        let view = view();
+        let ndc = Vec2(0.3, -0.8);
+        let anchor = view.ndc_to_world(ndc);
+        for &factor in &[2.0, 0.5, 1.1] {
+            let zoomed = view.zoom_about(ndc, factor);
+            assert_eq!(zoomed.zoom, view.zoom * factor);
+            assert_close(zoomed.world_to_ndc(anchor), ndc);
+        }
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let view = view();
+        assert_eq!(view.zoom_about(Vec2(0.0, 0.0), 1e6).zoom, MAX_ZOOM);
+        assert_eq!(view.zoom_about(Vec2(0.0, 0.0), 1e-6).zoom, MIN_ZOOM);
+
+        let point = Vec2(1.0, 1.0);
+        assert_eq!(view.fit(point, point + Vec2(1e-6, 0.0)).zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn pan_moves_the_drawing_with_the_drag() {
+        let view = view();
+        let world = Vec2(-1.0, 4.0);
+        let delta = Vec2(0.25, -0.5);
+        let panned = view.pan(delta);
+        assert_close(panned.world_to_ndc(world), view.world_to_ndc(world) + delta);
+        assert_close(panned.pan(delta * -1.0).center, view.center);
+    }
+
+    #[test]
+    fn fit_fills_the_canvas_with_the_box() {
+        let view = view();
+        let (min, max) = (Vec2(-1.0, 2.0), Vec2(5.0, 3.0));
+        let fitted = view.fit(min, max);
+        assert_close(fitted.world_to_ndc((min + max) * 0.5), Vec2(0.0, 0.0));
+
+        // The wide box touches the sides, less the margin
+        let edge = 1.0 / (1.0 + FIT_MARGIN);
+        assert_close(fitted.world_to_ndc(max), Vec2(edge, edge / 6.0 * 1.5));
+        assert_close(fitted.world_to_ndc(min) * -1.0, fitted.world_to_ndc(max));
+    }
+
+    #[test]
+    fn fitting_a_point_only_moves_the_center() {
+        let view = view();
+        let p = Vec2(7.0, 7.0);
+        assert_eq!(view.fit(p, p), View { center: p, ..view });
+    }
+
+    #[test]
+    fn bounding_box_of_points() {
+        assert_eq!(bounding_box(&[]), None);
+        assert_eq!(
+            bounding_box(&[Vec2(1.0, -1.0), Vec2(-2.0, 3.0), Vec2(0.0, 0.0)]),
+            Some((Vec2(-2.0, -1.0), Vec2(1.0, 3.0)))
+        );
+    }
+}
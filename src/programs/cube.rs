@@ -0,0 +1,190 @@
+use crate::{
+    geometry,
+    linear_algebra::{Mat4, Vec3},
+    utils,
+};
+use std::f32::consts::PI;
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::WebGlRenderingContext as Gl;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 v_position;
+attribute vec3 v_color;
+
+uniform mat4 u_model_view_projection;
+
+varying vec4 f_color;
+
+void main() {
+    gl_Position = u_model_view_projection * v_position;
+    f_color = vec4(v_color, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+varying vec4 f_color;
+
+void main() {
+    gl_FragColor = f_color;
+}
+"#;
+
+// One color per face, in the order of `geometry::CUBE_FACES`
+const FACE_COLORS: [Vec3; 6] = [
+    Vec3(1.0, 0.0, 0.0), // red
+    Vec3(0.0, 1.0, 1.0), // cyan
+    Vec3(0.0, 1.0, 0.0), // green
+    Vec3(1.0, 0.0, 1.0), // magenta
+    Vec3(0.0, 0.0, 1.0), // blue
+    Vec3(1.0, 1.0, 0.0), // yellow
+];
+
+// Radians per second about the chosen axis
+const SPEED: f32 = PI / 3.0;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let x_btn = get_element("rotate-x").ok_or("rotate-x not found")?;
+    let x_btn = x_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+    let y_btn = get_element("rotate-y").ok_or("rotate-y not found")?;
+    let y_btn = y_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+    let z_btn = get_element("rotate-z").ok_or("rotate-z not found")?;
+    let z_btn = z_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let interpolate = get_element("interpolate").ok_or("interpolate not found")?;
+    let interpolate = interpolate.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+    let program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+    context.use_program(Some(&program));
+
+    let cube = geometry::cube();
+
+    let v_position_buffer = context
+        .create_buffer()
+        .ok_or("failed to create v_position buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&v_position_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec3::flatten(&cube.positions),
+        Gl::STATIC_DRAW,
+    );
+    let v_position = match context.get_attrib_location(&program, "v_position") {
+        -1 => Err("unable to get location for v_position"),
+        p => Ok(p as u32),
+    }?;
+    context.vertex_attrib_pointer_with_i32(v_position, 3, Gl::FLOAT, false, 0, 0);
+    context.enable_vertex_attrib_array(v_position);
+
+    // Flat colors repeat the face's color on its four vertices. Interpolated
+    // colors come from the corner, making the cube an RGB color cube, so the
+    // faces meeting at a corner agree on its color.
+    let face_colors: Vec<Vec3> = FACE_COLORS
+        .iter()
+        .flat_map(|&color| vec![color; 4])
+        .collect();
+    let vertex_colors: Vec<Vec3> = cube
+        .positions
+        .iter()
+        .map(|&p| p + Vec3(0.5, 0.5, 0.5))
+        .collect();
+
+    let face_color_buffer = context
+        .create_buffer()
+        .ok_or("failed to create face color buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&face_color_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec3::flatten(&face_colors),
+        Gl::STATIC_DRAW,
+    );
+    let vertex_color_buffer = context
+        .create_buffer()
+        .ok_or("failed to create vertex color buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&vertex_color_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec3::flatten(&vertex_colors),
+        Gl::STATIC_DRAW,
+    );
+    let v_color = match context.get_attrib_location(&program, "v_color") {
+        -1 => Err("unable to get location for v_color"),
+        p => Ok(p as u32),
+    }?;
+    context.enable_vertex_attrib_array(v_color);
+
+    let index_buffer = context
+        .create_buffer()
+        .ok_or("failed to create index buffer")?;
+    context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&index_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ELEMENT_ARRAY_BUFFER,
+        &js_sys::Uint16Array::from(&cube.indices[..]),
+        Gl::STATIC_DRAW,
+    );
+
+    let u_model_view_projection = context
+        .get_uniform_location(&program, "u_model_view_projection")
+        .ok_or("unable to get location for u_model_view_projection")?;
+
+    // 0, 1 and 2 for x, y and z
+    let axis = Rc::new(Cell::new(0));
+    for (i, btn) in [x_btn, y_btn, z_btn].iter().enumerate() {
+        let axis = axis.clone();
+        utils::add_event_listener(btn, "click", move |_event| {
+            axis.set(i);
+        });
+    }
+
+    // Orthographic, flipping z so that larger z is nearer the viewer
+    let projection = Mat4::orthographic(-1.0, 1.0, -1.0, 1.0, -1.0, 1.0);
+    let mut theta = [0.0f32; 3];
+    let mut last = js_sys::Date::now();
+
+    context.enable(Gl::DEPTH_TEST);
+    context.enable(Gl::CULL_FACE);
+    context.cull_face(Gl::BACK);
+    context.clear_color(1.0, 1.0, 1.0, 1.0);
+    utils::render_loop(move || {
+        let now = js_sys::Date::now();
+        theta[axis.get()] += SPEED * ((now - last) / 1000.0) as f32;
+        last = now;
+
+        let colors = if interpolate.checked() {
+            &vertex_color_buffer
+        } else {
+            &face_color_buffer
+        };
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(colors));
+        context.vertex_attrib_pointer_with_i32(v_color, 3, Gl::FLOAT, false, 0, 0);
+
+        let model =
+            Mat4::rotation_x(theta[0]) * Mat4::rotation_y(theta[1]) * Mat4::rotation_z(theta[2]);
+        context.uniform_matrix4fv_with_f32_array(
+            Some(&u_model_view_projection),
+            false,
+            &(projection * model).0,
+        );
+
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        context.draw_elements_with_i32(
+            Gl::TRIANGLES,
+            cube.indices.len() as i32,
+            Gl::UNSIGNED_SHORT,
+            0,
+        );
+    });
+
+    Ok(())
+}
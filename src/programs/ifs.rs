@@ -0,0 +1,197 @@
+use crate::{
+    ifs::{self, Ifs, PRESETS},
+    linear_algebra::Vec2,
+    utils,
+};
+use rand_pcg::Pcg32;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::WebGlRenderingContext as Gl;
+
+const NUM_POINTS: usize = 1_000_000;
+// Iterates discarded before the orbit settles onto the attractor
+const SKIP_POINTS: usize = 20;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 v_position;
+
+varying vec2 f_texcoord;
+
+void main() {
+    gl_Position = v_position;
+    f_texcoord = (v_position.xy + 1.0) / 2.0;
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D density;
+
+varying vec2 f_texcoord;
+
+void main() {
+    gl_FragColor = texture2D(density, f_texcoord);
+}
+"#;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+pub fn run(context: Gl, seed: Option<u32>) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let preset_select = get_element("preset").ok_or("preset not found")?;
+    let preset_select = preset_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
+    let maps_input = get_element("maps").ok_or("maps not found")?;
+    let maps_input = maps_input.dyn_into::<web_sys::HtmlTextAreaElement>()?;
+
+    let apply_btn = get_element("apply").ok_or("apply not found")?;
+    let apply_btn = apply_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let export_btn = get_element("export").ok_or("export not found")?;
+    let export_btn = export_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let status = get_element("status").ok_or("status not found")?;
+
+    let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+    let program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+    context.use_program(Some(&program));
+
+    let quad = [
+        Vec2(-1.0, -1.0),
+        Vec2(1.0, -1.0),
+        Vec2(-1.0, 1.0),
+        Vec2(1.0, 1.0),
+    ];
+
+    let v_position_buffer = context
+        .create_buffer()
+        .ok_or("failed to create v_position buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&v_position_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec2::flatten(&quad),
+        Gl::STATIC_DRAW,
+    );
+    let v_position = match context.get_attrib_location(&program, "v_position") {
+        -1 => Err("unable to get location for v_position"),
+        p => Ok(p as u32),
+    }?;
+    context.vertex_attrib_pointer_with_i32(v_position, 2, Gl::FLOAT, false, 0, 0);
+    context.enable_vertex_attrib_array(v_position);
+
+    let texture = context
+        .create_texture()
+        .ok_or("failed to create density texture")?;
+    context.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+    context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MIN_FILTER, Gl::NEAREST as i32);
+    context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MAG_FILTER, Gl::NEAREST as i32);
+    context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE as i32);
+    context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE as i32);
+
+    let density_loc = context
+        .get_uniform_location(&program, "density")
+        .ok_or("failed to get location for density")?;
+    context.uniform1i(Some(&density_loc), 0);
+
+    for preset in PRESETS.iter() {
+        preset_select.add_with_html_option_element_and_opt_i32(
+            &web_sys::HtmlOptionElement::new_with_text_and_value(preset, preset)?,
+            None,
+        )?;
+    }
+
+    let renderer = Rc::new(Renderer {
+        context,
+        width: canvas.width() as i32,
+        height: canvas.height() as i32,
+        rng: RefCell::new(ifs::seeded_rng(seed)),
+    });
+    let maps_input = Rc::new(maps_input);
+
+    let load_preset = {
+        let renderer = renderer.clone();
+        let maps_input = maps_input.clone();
+        let preset_select = preset_select.clone();
+        move || {
+            if let Some(ifs) = Ifs::preset(&preset_select.value()) {
+                maps_input.set_value(&ifs.to_json());
+                renderer.render(&ifs);
+            }
+        }
+    };
+    load_preset();
+    utils::add_event_listener(&preset_select, "change", move |_event| load_preset());
+
+    {
+        let maps_input = maps_input.clone();
+        let status = status.clone();
+        utils::add_event_listener(&export_btn, "click", move |_event| {
+            match utils::download("ifs.json", &maps_input.value(), "application/json") {
+                Ok(()) => status.set_text_content(None),
+                Err(e) => status.set_text_content(Some(&utils::error_text(&e))),
+            }
+        });
+    }
+    {
+        let maps_input = maps_input.clone();
+        utils::add_event_listener(&apply_btn, "click", move |_event| {
+            match Ifs::from_json(&maps_input.value()) {
+                Ok(ifs) => {
+                    status.set_text_content(None);
+                    renderer.render(&ifs);
+                }
+                Err(e) => status.set_text_content(Some(&e)),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+struct Renderer {
+    context: Gl,
+    width: i32,
+    height: i32,
+    rng: RefCell<Pcg32>,
+}
+
+impl Renderer {
+    fn render(&self, ifs: &Ifs) {
+        let points = ifs.chaos_game(
+            &mut *self.rng.borrow_mut(),
+            Vec2(0.0, 0.0),
+            NUM_POINTS + SKIP_POINTS,
+        );
+        let density = ifs::density(
+            &points[SKIP_POINTS..],
+            self.width as usize,
+            self.height as usize,
+        );
+
+        self.context
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                Gl::TEXTURE_2D,
+                0,
+                Gl::RGBA as i32,
+                self.width,
+                self.height,
+                0,
+                Gl::RGBA,
+                Gl::UNSIGNED_BYTE,
+                Some(&ifs::tone_map(&density)),
+            )
+            .unwrap();
+
+        self.context.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+use super::rule::Rule;
+use std::fmt;
+
+// The CPU counterpart of the simulation, for checking rules and seeding the
+// GPU state. Cells are stored row by row from the top, and the edges wrap
+// around like the textures do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Grid {
+        Grid {
+            width,
+            height,
+            cells: vec![false; width * height],
+        }
+    }
+
+    fn index(&self, x: isize, y: isize) -> usize {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        y * self.width + x
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let i = self.index(x as isize, y as isize);
+        self.cells[i] = alive;
+    }
+
+    // Live cells white and dead ones black, for `Texture::update`
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.cells
+            .iter()
+            .flat_map(|&alive| {
+                let value = if alive { 255 } else { 0 };
+                vec![value, value, value, 255]
+            })
+            .collect()
+    }
+}
+
+// A reference stepper, which the tests check LIFE_SRC's lookup tables against
+#[cfg(test)]
+impl Grid {
+    // Plaintext patterns, one row per line with `O` or `*` for live cells
+    // and `.` for dead ones. Short rows are padded with dead cells.
+    pub fn parse(pattern: &str) -> Result<Grid, String> {
+        let rows: Vec<&str> = pattern
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty() && !row.starts_with('!'))
+            .collect();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut grid = Grid::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    'O' | '*' => grid.set(x, y, true),
+                    '.' => {}
+                    _ => return Err(format!("unexpected {:?} in row {}", c, y + 1)),
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn neighbours(&self, x: usize, y: usize) -> usize {
+        let (x, y) = (x as isize, y as isize);
+        let mut count = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) != (0, 0) && self.get(x + dx, y + dy) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    pub fn step(&self, rule: &Rule) -> Grid {
+        let mut next = Grid::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alive = rule.next(self.get(x as isize, y as isize), self.neighbours(x, y));
+                next.set(x, y, alive);
+            }
+        }
+        next
+    }
+
+    // Copies the live cells of `pattern` in with its top left corner at
+    // (x, y)
+    pub fn place(&mut self, pattern: &Grid, x: usize, y: usize) {
+        for py in 0..pattern.height {
+            for px in 0..pattern.width {
+                if pattern.get(px as isize, py as isize) {
+                    self.set(x + px, y + py, true);
+                }
+            }
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&alive| alive).count()
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(self.width.max(1)) {
+            let row: String = row
+                .iter()
+                .map(|&alive| if alive { 'O' } else { '.' })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rule::CONWAY;
+    use super::*;
+
+    fn pattern(pattern: &str, width: usize, height: usize, x: usize, y: usize) -> Grid {
+        let mut grid = Grid::new(width, height);
+        grid.place(&Grid::parse(pattern).unwrap(), x, y);
+        grid
+    }
+
+    #[test]
+    fn parses_plaintext_patterns() {
+        let grid = Grid::parse("!Name: glider\n.O\n..O\nOOO").unwrap();
+        assert_eq!((grid.width, grid.height), (3, 3));
+        assert_eq!(grid.population(), 5);
+        assert_eq!(grid.to_string(), ".O.\n..O\nOOO\n");
+        assert!(Grid::parse("O.x").is_err());
+    }
+
+    #[test]
+    fn counts_neighbours_across_the_edges() {
+        let grid = pattern("O", 4, 4, 0, 0);
+        assert_eq!(grid.neighbours(3, 3), 1);
+        assert_eq!(grid.neighbours(1, 1), 1);
+        assert_eq!(grid.neighbours(2, 2), 0);
+    }
+
+    #[test]
+    fn block_is_fixed() {
+        let block = pattern("OO\nOO", 6, 6, 2, 2);
+        assert_eq!(block.step(&CONWAY), block);
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let blinker = pattern("OOO", 5, 5, 1, 2);
+        let next = blinker.step(&CONWAY);
+        assert_ne!(next, blinker);
+        assert_eq!(next, pattern("O\nO\nO", 5, 5, 2, 1));
+        assert_eq!(next.step(&CONWAY), blinker);
+    }
+
+    #[test]
+    fn glider_moves_diagonally_and_wraps() {
+        let glider = ".O\n..O\nOOO";
+        let mut grid = pattern(glider, 8, 8, 6, 6);
+        for _ in 0..4 {
+            grid = grid.step(&CONWAY);
+        }
+        assert_eq!(grid, pattern(glider, 8, 8, 7, 7));
+        assert_eq!(grid.population(), 5);
+    }
+}
@@ -0,0 +1,782 @@
+pub mod grid;
+pub mod rule;
+
+use self::grid::Grid;
+use self::rule::{Rule, CONWAY};
+use crate::{
+    linear_algebra::Vec2,
+    render_target::RenderTarget,
+    texture::{Filter, Options, Wrap},
+    utils,
+};
+use rand::Rng;
+use std::collections::HashMap;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+// A single triangle covering the whole viewport, from a_position of
+// (-1, -1), (3, -1) and (-1, 3)
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec2 a_position;
+
+varying vec2 f_uv;
+
+void main() {
+    f_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+"#;
+
+// One generation of a B/S rule. Cells are alive when red is set.
+const LIFE_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_state;
+uniform vec2 u_texel;
+uniform float u_birth[9];
+uniform float u_survival[9];
+
+varying vec2 f_uv;
+
+float cell(float x, float y) {
+    return step(0.5, texture2D(u_state, f_uv + vec2(x, y) * u_texel).r);
+}
+
+void main() {
+    float neighbours = cell(-1.0, -1.0) + cell(0.0, -1.0) + cell(1.0, -1.0)
+        + cell(-1.0, 0.0) + cell(1.0, 0.0)
+        + cell(-1.0, 1.0) + cell(0.0, 1.0) + cell(1.0, 1.0);
+    bool alive = cell(0.0, 0.0) > 0.5;
+
+    // Fragment shaders may only index uniform arrays with loop counters
+    float next = 0.0;
+    for (int i = 0; i < 9; i++) {
+        if (float(i) == neighbours) {
+            next = alive ? u_survival[i] : u_birth[i];
+        }
+    }
+    gl_FragColor = vec4(vec3(next), 1.0);
+}
+"#;
+
+// Shared by the passes that read Gray-Scott concentrations. A byte is too
+// coarse for the reaction, so u is kept in red and green and v in blue and
+// alpha, each as a high and a low byte.
+const CONCENTRATIONS_SRC: &str = r#"
+float decode(vec2 bytes) {
+    return (bytes.x * 255.0 + bytes.y) / 255.0;
+}
+
+vec2 encode(float x) {
+    x = clamp(x, 0.0, 1.0) * 255.0;
+    float high = floor(x);
+    return vec2(high / 255.0, x - high);
+}
+"#;
+
+// One time step of Gray-Scott reaction-diffusion
+const GRAY_SCOTT_SRC: &str = r#"
+uniform sampler2D u_state;
+uniform vec2 u_texel;
+uniform float u_feed;
+uniform float u_kill;
+
+varying vec2 f_uv;
+
+const float DIFFUSION_U = 1.0;
+const float DIFFUSION_V = 0.5;
+
+vec2 concentrations(float x, float y) {
+    vec4 texel = texture2D(u_state, f_uv + vec2(x, y) * u_texel);
+    return vec2(decode(texel.rg), decode(texel.ba));
+}
+
+void main() {
+    vec2 c = concentrations(0.0, 0.0);
+    vec2 laplacian = -c
+        + 0.2 * (concentrations(-1.0, 0.0) + concentrations(1.0, 0.0)
+            + concentrations(0.0, -1.0) + concentrations(0.0, 1.0))
+        + 0.05 * (concentrations(-1.0, -1.0) + concentrations(1.0, -1.0)
+            + concentrations(-1.0, 1.0) + concentrations(1.0, 1.0));
+    float reaction = c.x * c.y * c.y;
+    float u = c.x + DIFFUSION_U * laplacian.x - reaction + u_feed * (1.0 - c.x);
+    float v = c.y + DIFFUSION_V * laplacian.y + reaction - (u_feed + u_kill) * c.y;
+    gl_FragColor = vec4(encode(u), encode(v));
+}
+"#;
+
+// Sets the cells within `u_radius` of the segment from `u_from` to `u_to`,
+// all in cells, to `u_value`
+const PAINT_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_state;
+uniform vec2 u_size;
+uniform vec2 u_from;
+uniform vec2 u_to;
+uniform float u_radius;
+uniform vec4 u_value;
+
+varying vec2 f_uv;
+
+void main() {
+    vec2 p = f_uv * u_size;
+    vec2 d = u_to - u_from;
+    float t = clamp(dot(p - u_from, d) / max(dot(d, d), 0.0001), 0.0, 1.0);
+    if (length(p - u_from - t * d) <= u_radius) {
+        gl_FragColor = u_value;
+    } else {
+        gl_FragColor = texture2D(u_state, f_uv);
+    }
+}
+"#;
+
+const DISPLAY_SRC: &str = r#"
+uniform sampler2D u_state;
+uniform bool u_gray_scott;
+
+varying vec2 f_uv;
+
+void main() {
+    vec4 texel = texture2D(u_state, f_uv);
+    if (u_gray_scott) {
+        float v = decode(texel.ba);
+        vec3 color = mix(vec3(0.05, 0.05, 0.15), vec3(0.2, 0.5, 0.9), smoothstep(0.0, 0.2, v));
+        color = mix(color, vec3(1.0, 0.95, 0.8), smoothstep(0.2, 0.4, v));
+        gl_FragColor = vec4(color, 1.0);
+    } else {
+        gl_FragColor = vec4(mix(vec3(0.08), vec3(0.35, 0.95, 0.55), texel.r), 1.0);
+    }
+}
+"#;
+
+// Gray-Scott needs more than the 10 bits mediump may have
+const HIGH_PRECISION_SRC: &str = r#"
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+"#;
+
+// Cells per side. A power of two, so that the state textures can repeat and
+// the edges wrap around.
+const SIZE: u32 = 256;
+const STATE_OPTIONS: Options = Options {
+    wrap_s: Wrap::Repeat,
+    wrap_t: Wrap::Repeat,
+    min_filter: Filter::Nearest,
+    mag_filter: Filter::Nearest,
+};
+
+// Chance of each cell being alive in a random soup
+const DENSITY: f64 = 0.25;
+// Gray-Scott time steps per frame, since a step changes very little
+const GRAY_SCOTT_STEPS: usize = 16;
+// Patches of v a random Gray-Scott start is seeded with
+const GRAY_SCOTT_SEEDS: usize = 24;
+// Catching up after the page was hidden is capped at this many generations
+const MAX_STEPS_PER_FRAME: f64 = 8.0;
+// Brush radii in cells. Life strokes run between cell centres, so less than
+// half a cell paints only the cells they pass through.
+const LIFE_BRUSH: f32 = 0.45;
+const GRAY_SCOTT_BRUSH: f32 = 4.0;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+fn get_input(id: &str) -> Result<web_sys::HtmlInputElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(JsValue::from)
+}
+
+fn get_select(id: &str) -> Result<web_sys::HtmlSelectElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .map_err(JsValue::from)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    Life,
+    GrayScott,
+}
+
+impl Mode {
+    fn parse(mode: &str) -> Option<Mode> {
+        match mode {
+            "life" => Some(Mode::Life),
+            "gray-scott" => Some(Mode::GrayScott),
+            _ => None,
+        }
+    }
+}
+
+// A Gray-Scott concentration as the high and low bytes the shaders decode
+fn encode(x: f32) -> [u8; 2] {
+    let x = x.clamp(0.0, 1.0) * 255.0;
+    let high = x.floor();
+    [high as u8, ((x - high) * 255.0).round() as u8]
+}
+
+fn gray_scott_rgba(cells: &[(f32, f32)]) -> Vec<u8> {
+    cells
+        .iter()
+        .flat_map(|&(u, v)| {
+            let ([u0, u1], [v0, v1]) = (encode(u), encode(v));
+            vec![u0, u1, v0, v1]
+        })
+        .collect()
+}
+
+// The centre of the cell `p` is in, which is where the paint shader samples
+// it
+fn cell_centre(p: Vec2) -> Vec2 {
+    Vec2(p.0.floor() + 0.5, p.1.floor() + 0.5)
+}
+
+// A rule's birth or survival counts as the 0/1 table LIFE_SRC looks up by
+// neighbour count
+fn to_floats(counts: &[bool; 9]) -> [f32; 9] {
+    let mut values = [0.0; 9];
+    for (value, &on) in values.iter_mut().zip(counts) {
+        *value = on as u8 as f32;
+    }
+    values
+}
+
+// A full-screen fragment shader and its uniforms
+struct Pass {
+    program: WebGlProgram,
+    a_position: u32,
+    uniforms: HashMap<&'static str, WebGlUniformLocation>,
+}
+
+impl Pass {
+    fn new(context: &Gl, fragment_src: &str, uniforms: &[&'static str]) -> Result<Pass, JsValue> {
+        let vertex_shader = utils::compile_shader(context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+        let fragment_shader = utils::compile_shader(context, Gl::FRAGMENT_SHADER, fragment_src)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let a_position = match context.get_attrib_location(&program, "a_position") {
+            -1 => Err("unable to get location for a_position"),
+            p => Ok(p as u32),
+        }?;
+        let uniforms = uniforms
+            .iter()
+            .map(|&name| {
+                context
+                    .get_uniform_location(&program, name)
+                    .map(|location| (name, location))
+                    .ok_or(format!("unable to get location for {}", name))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Pass {
+            program,
+            a_position,
+            uniforms,
+        })
+    }
+
+    fn uniform(&self, name: &str) -> Option<&WebGlUniformLocation> {
+        self.uniforms.get(name)
+    }
+
+    // Draws the pass with `state` as u_state into whatever is bound
+    fn draw<F>(&self, context: &Gl, triangle: &WebGlBuffer, state: &RenderTarget, uniforms: F)
+    where
+        F: FnOnce(&Pass),
+    {
+        context.use_program(Some(&self.program));
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(triangle));
+        context.vertex_attrib_pointer_with_i32(self.a_position, 2, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(self.a_position);
+        state.color.bind(context, 0);
+        context.uniform1i(self.uniform("u_state"), 0);
+        uniforms(self);
+        context.draw_arrays(Gl::TRIANGLES, 0, 3);
+        context.disable_vertex_attrib_array(self.a_position);
+    }
+}
+
+// Draws `pass` over the current state into the other target, which then
+// becomes the current state
+fn advance<F>(
+    context: &Gl,
+    triangle: &WebGlBuffer,
+    states: &mut [RenderTarget; 2],
+    pass: &Pass,
+    uniforms: F,
+) where
+    F: FnOnce(&Pass),
+{
+    states[1].bind(context);
+    pass.draw(context, triangle, &states[0], uniforms);
+    states.swap(0, 1);
+}
+
+struct Simulation {
+    context: Gl,
+    triangle: WebGlBuffer,
+    life: Pass,
+    gray_scott: Pass,
+    paint: Pass,
+    display: Pass,
+    // The current state is `states[0]`
+    states: [RenderTarget; 2],
+    mode: Mode,
+    rule: Rule,
+    feed: f32,
+    kill: f32,
+    generation: u64,
+}
+
+impl Simulation {
+    fn new(context: &Gl, mode: Mode) -> Result<Simulation, JsValue> {
+        let triangle = context
+            .create_buffer()
+            .ok_or("failed to create a_position buffer")?;
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&triangle));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0][..]),
+            Gl::STATIC_DRAW,
+        );
+
+        let state = || RenderTarget::new(context, SIZE, SIZE, STATE_OPTIONS, false);
+        let mut simulation = Simulation {
+            context: context.clone(),
+            triangle,
+            life: Pass::new(
+                context,
+                LIFE_SRC,
+                &["u_state", "u_texel", "u_birth", "u_survival"],
+            )?,
+            gray_scott: Pass::new(
+                context,
+                &format!(
+                    "{}{}{}",
+                    HIGH_PRECISION_SRC, CONCENTRATIONS_SRC, GRAY_SCOTT_SRC
+                ),
+                &["u_state", "u_texel", "u_feed", "u_kill"],
+            )?,
+            paint: Pass::new(
+                context,
+                PAINT_SRC,
+                &["u_state", "u_size", "u_from", "u_to", "u_radius", "u_value"],
+            )?,
+            display: Pass::new(
+                context,
+                &format!(
+                    "{}{}{}",
+                    HIGH_PRECISION_SRC, CONCENTRATIONS_SRC, DISPLAY_SRC
+                ),
+                &["u_state", "u_gray_scott"],
+            )?,
+            states: [state()?, state()?],
+            mode,
+            rule: CONWAY,
+            feed: 0.055,
+            kill: 0.062,
+            generation: 0,
+        };
+        simulation.randomize()?;
+        Ok(simulation)
+    }
+
+    fn set_mode(&mut self, mode: Mode) -> Result<(), JsValue> {
+        self.mode = mode;
+        self.randomize()
+    }
+
+    fn upload(&mut self, pixels: &[u8]) -> Result<(), JsValue> {
+        self.generation = 0;
+        self.states[0].color.update(&self.context, pixels)
+    }
+
+    // Kills every cell, or fills the Gray-Scott state with u alone
+    fn clear(&mut self) -> Result<(), JsValue> {
+        let size = SIZE as usize;
+        let pixels = match self.mode {
+            Mode::Life => Grid::new(size, size).to_rgba(),
+            Mode::GrayScott => gray_scott_rgba(&vec![(1.0, 0.0); size * size]),
+        };
+        self.upload(&pixels)
+    }
+
+    // A random soup for Life, or scattered patches of v for Gray-Scott
+    fn randomize(&mut self) -> Result<(), JsValue> {
+        let size = SIZE as usize;
+        let mut rng = rand::thread_rng();
+        let pixels = match self.mode {
+            Mode::Life => {
+                let mut grid = Grid::new(size, size);
+                for y in 0..size {
+                    for x in 0..size {
+                        grid.set(x, y, rng.gen_bool(DENSITY));
+                    }
+                }
+                grid.to_rgba()
+            }
+            Mode::GrayScott => {
+                let mut cells = vec![(1.0, 0.0); size * size];
+                for _ in 0..GRAY_SCOTT_SEEDS {
+                    let (x, y) = (rng.gen_range(0, size), rng.gen_range(0, size));
+                    let side = rng.gen_range(4, 12);
+                    for dy in 0..side {
+                        for dx in 0..side {
+                            cells[(y + dy) % size * size + (x + dx) % size] = (0.5, 0.25);
+                        }
+                    }
+                }
+                gray_scott_rgba(&cells)
+            }
+        };
+        self.upload(&pixels)
+    }
+
+    fn step(&mut self) {
+        let texel = 1.0 / SIZE as f32;
+        let context = &self.context;
+        match self.mode {
+            Mode::Life => {
+                let rule = self.rule;
+                advance(
+                    context,
+                    &self.triangle,
+                    &mut self.states,
+                    &self.life,
+                    |pass| {
+                        context.uniform2f(pass.uniform("u_texel"), texel, texel);
+                        context.uniform1fv_with_f32_array(
+                            pass.uniform("u_birth"),
+                            &to_floats(&rule.birth),
+                        );
+                        context.uniform1fv_with_f32_array(
+                            pass.uniform("u_survival"),
+                            &to_floats(&rule.survival),
+                        );
+                    },
+                );
+            }
+            Mode::GrayScott => {
+                let (feed, kill) = (self.feed, self.kill);
+                advance(
+                    context,
+                    &self.triangle,
+                    &mut self.states,
+                    &self.gray_scott,
+                    |pass| {
+                        context.uniform2f(pass.uniform("u_texel"), texel, texel);
+                        context.uniform1f(pass.uniform("u_feed"), feed);
+                        context.uniform1f(pass.uniform("u_kill"), kill);
+                    },
+                );
+            }
+        }
+        self.generation += 1;
+    }
+
+    // Paints from `from` to `to`, in cells with y up. Erasing kills Life
+    // cells and removes v.
+    fn paint(&mut self, from: Vec2, to: Vec2, erase: bool) {
+        let (from, to) = match self.mode {
+            Mode::Life => (cell_centre(from), cell_centre(to)),
+            Mode::GrayScott => (from, to),
+        };
+        let (radius, pixel) = match (self.mode, erase) {
+            (Mode::Life, false) => (LIFE_BRUSH, vec![255, 255, 255, 255]),
+            (Mode::Life, true) => (LIFE_BRUSH, vec![0, 0, 0, 255]),
+            (Mode::GrayScott, false) => (GRAY_SCOTT_BRUSH, gray_scott_rgba(&[(0.5, 0.25)])),
+            (Mode::GrayScott, true) => (GRAY_SCOTT_BRUSH, gray_scott_rgba(&[(1.0, 0.0)])),
+        };
+        let value: Vec<f32> = pixel.iter().map(|&c| c as f32 / 255.0).collect();
+        let context = &self.context;
+        advance(
+            context,
+            &self.triangle,
+            &mut self.states,
+            &self.paint,
+            |pass| {
+                context.uniform2f(pass.uniform("u_size"), SIZE as f32, SIZE as f32);
+                context.uniform2f(pass.uniform("u_from"), from.0, from.1);
+                context.uniform2f(pass.uniform("u_to"), to.0, to.1);
+                context.uniform1f(pass.uniform("u_radius"), radius);
+                context.uniform4f(
+                    pass.uniform("u_value"),
+                    value[0],
+                    value[1],
+                    value[2],
+                    value[3],
+                );
+            },
+        );
+    }
+
+    fn draw(&self, width: u32, height: u32) {
+        let context = &self.context;
+        RenderTarget::unbind(context, width, height);
+        self.display
+            .draw(context, &self.triangle, &self.states[0], |pass| {
+                context.uniform1i(
+                    pass.uniform("u_gray_scott"),
+                    (self.mode == Mode::GrayScott) as i32,
+                );
+            });
+    }
+}
+
+// Canvas pixels to cells, with y up like the state
+fn to_cell(event: &web_sys::MouseEvent, canvas: &web_sys::HtmlCanvasElement) -> Vec2 {
+    Vec2(
+        event.offset_x() as f32 / canvas.width() as f32 * SIZE as f32,
+        (1.0 - event.offset_y() as f32 / canvas.height() as f32) * SIZE as f32,
+    )
+}
+
+// Positive and no more than 1, which covers every interesting feed and kill
+// rate
+fn parse_rate(value: &str) -> Option<f32> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|rate| *rate > 0.0 && *rate <= 1.0)
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+    let (width, height) = (canvas.width(), canvas.height());
+
+    let mode_select = get_select("mode")?;
+    let rule_input = get_input("rule")?;
+    let rule_error = get_element("rule-error").ok_or("rule-error not found")?;
+    let feed_input = get_input("feed")?;
+    let kill_input = get_input("kill")?;
+    let speed_input = get_input("speed")?;
+    let pause_btn = get_element("pause").ok_or("pause not found")?;
+    let step_btn = get_element("step").ok_or("step not found")?;
+    let clear_btn = get_element("clear").ok_or("clear not found")?;
+    let randomize_btn = get_element("randomize").ok_or("randomize not found")?;
+    let generation = get_element("generation").ok_or("generation not found")?;
+    let status = get_element("status").ok_or("status not found")?;
+
+    let mode = Mode::parse(&mode_select.value()).unwrap_or(Mode::Life);
+    let simulation = Rc::new(RefCell::new(Simulation::new(&context, mode)?));
+    let paused = Rc::new(Cell::new(false));
+    // Steps asked for with the step button, taken by the next frame
+    let requested = Rc::new(Cell::new(0));
+    // The last cell painted while the mouse is down
+    let painting = Rc::new(Cell::new(None));
+
+    {
+        let simulation = simulation.clone();
+        let mode_select_ref = mode_select.clone();
+        let status = status.clone();
+        utils::add_event_listener(&mode_select, "change", move |_event| {
+            if let Some(mode) = Mode::parse(&mode_select_ref.value()) {
+                utils::report(&status, simulation.borrow_mut().set_mode(mode));
+            }
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let rule_input_ref = rule_input.clone();
+        utils::add_event_listener(&rule_input, "change", move |_event| {
+            match Rule::parse(&rule_input_ref.value()) {
+                Ok(rule) => {
+                    simulation.borrow_mut().rule = rule;
+                    rule_input_ref.set_value(&rule.to_string());
+                    rule_error.set_text_content(None);
+                }
+                Err(error) => rule_error.set_text_content(Some(&error)),
+            }
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let feed_input_ref = feed_input.clone();
+        utils::add_event_listener(&feed_input, "input", move |_event| {
+            if let Some(feed) = parse_rate(&feed_input_ref.value()) {
+                simulation.borrow_mut().feed = feed;
+            }
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let kill_input_ref = kill_input.clone();
+        utils::add_event_listener(&kill_input, "input", move |_event| {
+            if let Some(kill) = parse_rate(&kill_input_ref.value()) {
+                simulation.borrow_mut().kill = kill;
+            }
+        });
+    }
+    {
+        let paused = paused.clone();
+        let pause_btn_ref = pause_btn.clone();
+        utils::add_event_listener(&pause_btn, "click", move |_event| {
+            paused.set(!paused.get());
+            pause_btn_ref.set_text_content(Some(if paused.get() { "Resume" } else { "Pause" }));
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let requested = requested.clone();
+        utils::add_event_listener(&step_btn, "click", move |_event| {
+            let steps = match simulation.borrow().mode {
+                Mode::Life => 1,
+                Mode::GrayScott => GRAY_SCOTT_STEPS,
+            };
+            requested.set(requested.get() + steps);
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let status = status.clone();
+        utils::add_event_listener(&clear_btn, "click", move |_event| {
+            utils::report(&status, simulation.borrow_mut().clear());
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        utils::add_event_listener(&randomize_btn, "click", move |_event| {
+            utils::report(&status, simulation.borrow_mut().randomize());
+        });
+    }
+
+    let canvas = Rc::new(canvas);
+    {
+        let simulation = simulation.clone();
+        let painting = painting.clone();
+        let canvas_ref = canvas.clone();
+        utils::add_event_listener(&canvas, "mousedown", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let p = to_cell(&event, &canvas_ref);
+            simulation.borrow_mut().paint(p, p, event.shift_key());
+            painting.set(Some(p));
+        });
+    }
+    {
+        let simulation = simulation.clone();
+        let painting = painting.clone();
+        let canvas_ref = canvas.clone();
+        utils::add_event_listener(&canvas, "mousemove", move |event| {
+            if let Some(last) = painting.get() {
+                let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+                let p = to_cell(&event, &canvas_ref);
+                simulation.borrow_mut().paint(last, p, event.shift_key());
+                painting.set(Some(p));
+            }
+        });
+    }
+    for event in &["mouseup", "mouseleave"] {
+        let painting = painting.clone();
+        utils::add_event_listener(&canvas, event, move |_event| {
+            painting.set(None);
+        });
+    }
+
+    // Life generations owed by the time passed, so the speed doesn't depend
+    // on the frame rate
+    let mut due = 0.0;
+    let mut last = js_sys::Date::now();
+    utils::render_loop(move || {
+        let now = js_sys::Date::now();
+        let elapsed = (now - last) / 1000.0;
+        last = now;
+
+        let mut simulation = simulation.borrow_mut();
+        let mut steps = requested.replace(0);
+        if !paused.get() {
+            match simulation.mode {
+                Mode::Life => {
+                    due += elapsed * speed_input.value().parse::<f64>().unwrap_or(1.0);
+                    let whole = due.floor().min(MAX_STEPS_PER_FRAME);
+                    due = (due - whole).min(1.0);
+                    steps += whole as usize;
+                }
+                Mode::GrayScott => steps += GRAY_SCOTT_STEPS,
+            }
+        }
+        for _ in 0..steps {
+            simulation.step();
+        }
+
+        simulation.draw(width, height);
+        generation.set_text_content(Some(&format!("Generation {}", simulation.generation)));
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What LIFE_SRC computes for one cell from the uploaded tables
+    fn lookup(rule: &Rule, alive: bool, neighbours: usize) -> bool {
+        let table = if alive {
+            to_floats(&rule.survival)
+        } else {
+            to_floats(&rule.birth)
+        };
+        table[neighbours] == 1.0
+    }
+
+    #[test]
+    fn lookup_tables_hold_the_rule() {
+        assert_eq!(
+            to_floats(&CONWAY.birth),
+            [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            to_floats(&CONWAY.survival),
+            [0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn lookup_tables_step_like_the_grid() {
+        let mut grid = Grid::new(12, 12);
+        grid.place(&Grid::parse(".O\n..O\nOOO").unwrap(), 1, 1);
+        grid.place(&Grid::parse("OOO\nO..\n.O.O").unwrap(), 6, 6);
+        for rule in &[
+            CONWAY,
+            Rule::parse("B36/S23").unwrap(),
+            Rule::parse("B2/S").unwrap(),
+        ] {
+            let mut next = Grid::new(grid.width, grid.height);
+            for y in 0..grid.height {
+                for x in 0..grid.width {
+                    let alive = grid.get(x as isize, y as isize);
+                    next.set(x, y, lookup(rule, alive, grid.neighbours(x, y)));
+                }
+            }
+            assert_eq!(next, grid.step(rule), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn life_brush_paints_only_the_cell_under_the_pointer() {
+        for &p in &[Vec2(3.0, 7.0), Vec2(3.99, 7.99), Vec2(3.5, 7.01)] {
+            let centre = cell_centre(p);
+            assert_eq!(centre, Vec2(3.5, 7.5));
+            for &(dx, dy) in &[(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+                let neighbour = Vec2(centre.0 + dx, centre.1 + dy);
+                assert!((neighbour - centre).length() > LIFE_BRUSH);
+            }
+        }
+    }
+}
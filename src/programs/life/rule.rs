@@ -0,0 +1,107 @@
+use std::fmt;
+
+// An outer totalistic rule for cells with eight neighbours. `birth[n]` is
+// whether a dead cell with n live neighbours comes alive, `survival[n]`
+// whether a live one stays alive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+pub const CONWAY: Rule = Rule {
+    birth: [false, false, false, true, false, false, false, false, false],
+    survival: [false, false, true, true, false, false, false, false, false],
+};
+
+impl Rule {
+    // Parses B/S notation such as "B3/S23" or "B36/S23". Either part may
+    // come first or be left empty, as in "B2/S", and letters may be lower
+    // case.
+    pub fn parse(rule: &str) -> Result<Rule, String> {
+        let mut birth = None;
+        let mut survival = None;
+        for part in rule.trim().split('/').map(str::trim) {
+            let mut chars = part.chars();
+            let counts = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut birth,
+                Some('S') => &mut survival,
+                _ => return Err(format!("expected B or S at the start of {:?}", part)),
+            };
+            if counts.is_some() {
+                return Err(format!("{:?} is given twice in {:?}", &part[..1], rule));
+            }
+
+            let mut set = [false; 9];
+            for c in chars {
+                let n = c
+                    .to_digit(10)
+                    .filter(|&n| n <= 8)
+                    .ok_or(format!("{:?} is not a neighbour count from 0 to 8", c))?;
+                set[n as usize] = true;
+            }
+            *counts = Some(set);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => Err(format!("{:?} needs both a B and an S part", rule)),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn next(&self, alive: bool, neighbours: usize) -> bool {
+        if alive {
+            self.survival[neighbours]
+        } else {
+            self.birth[neighbours]
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = |set: &[bool; 9]| -> String {
+            (0..9)
+                .filter(|&n| set[n])
+                .map(|n| std::char::from_digit(n as u32, 10).unwrap())
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_b_s_notation() {
+        assert_eq!(Rule::parse("B3/S23"), Ok(CONWAY));
+        assert_eq!(Rule::parse("S23/B3"), Ok(CONWAY));
+
+        let high_life = Rule::parse("b36/s23").unwrap();
+        assert!(high_life.birth[3] && high_life.birth[6]);
+        assert_eq!(high_life.survival, CONWAY.survival);
+
+        let seeds = Rule::parse("B2/S").unwrap();
+        assert!(seeds.birth[2]);
+        assert_eq!(seeds.survival, [false; 9]);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for rule in &["B9/S", "B3/B3", "", "B3", "X3/S23", "B3/S2a"] {
+            assert!(Rule::parse(rule).is_err(), "{:?} was accepted", rule);
+        }
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        for rule in &["B3/S23", "B36/S23", "B2/S", "B/S012345678"] {
+            let parsed = Rule::parse(rule).unwrap();
+            assert_eq!(parsed.to_string(), *rule);
+            assert_eq!(Rule::parse(&parsed.to_string()), Ok(parsed));
+        }
+    }
+}
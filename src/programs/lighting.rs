@@ -0,0 +1,456 @@
+use crate::{
+    color,
+    geometry::{self, Mesh},
+    linear_algebra::{Mat3, Mat4, Vec3, Vec4},
+    utils,
+};
+use std::f32::consts::PI;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+// Shared by both programs. Everything is in eye space, where the viewer is at
+// the origin. A light with w = 0 is directional and `xyz` points towards it.
+const LIGHTING_SRC: &str = r#"
+uniform vec4 u_light_position;
+uniform vec3 u_light_color;
+uniform vec3 u_ambient;
+uniform vec3 u_diffuse;
+uniform vec3 u_specular;
+uniform float u_shininess;
+uniform bool u_blinn;
+
+vec3 shade(vec3 position, vec3 normal) {
+    vec3 n = normalize(normal);
+    vec3 l = normalize(u_light_position.w == 0.0
+        ? u_light_position.xyz
+        : u_light_position.xyz - position);
+    vec3 v = normalize(-position);
+
+    float diffuse = max(dot(l, n), 0.0);
+    float specular = 0.0;
+    if (diffuse > 0.0) {
+        float alignment = u_blinn
+            ? dot(n, normalize(l + v))
+            : dot(reflect(-l, n), v);
+        specular = pow(max(alignment, 0.0), u_shininess);
+    }
+
+    return u_light_color * (u_ambient + diffuse * u_diffuse + specular * u_specular);
+}
+"#;
+
+const TRANSFORM_SRC: &str = r#"
+attribute vec4 a_position;
+attribute vec3 a_normal;
+
+uniform mat4 u_model_view;
+uniform mat4 u_projection;
+uniform mat3 u_normal_matrix;
+"#;
+
+// Gouraud shading lights the vertices and interpolates the color
+const GOURAUD_VERTEX_SHADER_SRC: &str = r#"
+varying vec3 f_color;
+
+void main() {
+    vec4 position = u_model_view * a_position;
+    f_color = shade(position.xyz, u_normal_matrix * a_normal);
+    gl_Position = u_projection * position;
+}
+"#;
+
+const GOURAUD_FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+varying vec3 f_color;
+
+void main() {
+    gl_FragColor = vec4(f_color, 1.0);
+}
+"#;
+
+// Phong shading interpolates the normal and lights each fragment
+const PHONG_VERTEX_SHADER_SRC: &str = r#"
+varying vec3 f_position;
+varying vec3 f_normal;
+
+void main() {
+    vec4 position = u_model_view * a_position;
+    f_position = position.xyz;
+    f_normal = u_normal_matrix * a_normal;
+    gl_Position = u_projection * position;
+}
+"#;
+
+const PHONG_FRAGMENT_SHADER_SRC: &str = r#"
+varying vec3 f_position;
+varying vec3 f_normal;
+
+void main() {
+    gl_FragColor = vec4(shade(f_position, f_normal), 1.0);
+}
+"#;
+
+const CAMERA_DISTANCE: f32 = 4.0;
+// Radians per second the model turns about the y axis
+const SPIN_SPEED: f32 = PI / 5.0;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+fn get_input(id: &str) -> Result<web_sys::HtmlInputElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(JsValue::from)
+}
+
+fn get_select(id: &str) -> Result<web_sys::HtmlSelectElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .map_err(JsValue::from)
+}
+
+// Color inputs give "#rrggbb"
+fn hex_to_rgb(hex: &str) -> Option<Vec3> {
+    color::hex_to_rgba(hex).map(|c| Vec3(c.0, c.1, c.2))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Shading {
+    Gouraud,
+    Phong,
+    BlinnPhong,
+}
+
+impl Shading {
+    fn parse(shading: &str) -> Option<Shading> {
+        match shading {
+            "gouraud" => Some(Shading::Gouraud),
+            "phong" => Some(Shading::Phong),
+            "blinn-phong" => Some(Shading::BlinnPhong),
+            _ => None,
+        }
+    }
+}
+
+fn build_mesh(name: &str) -> Mesh {
+    match name {
+        "icosphere" => geometry::icosphere(1.0, 3),
+        "cube" => geometry::cuboid(Vec3(1.4, 1.4, 1.4)),
+        "cylinder" => geometry::cylinder(0.8, 1.6, 48),
+        "cone" => geometry::cone(0.8, 1.6, 48),
+        "torus" => geometry::torus(0.9, 0.35, 64, 32),
+        "plane" => geometry::plane(2.0, 2.0, 16, 16),
+        _ => geometry::uv_sphere(1.0, 48, 24),
+    }
+}
+
+struct Material {
+    ambient: Vec3,
+    diffuse: Vec3,
+    specular: Vec3,
+    shininess: f32,
+}
+
+struct Light {
+    // World space, w = 0 for a directional light
+    position: Vec4,
+    color: Vec3,
+}
+
+struct Shader {
+    program: WebGlProgram,
+    a_position: u32,
+    a_normal: u32,
+    u_model_view: WebGlUniformLocation,
+    u_projection: WebGlUniformLocation,
+    u_normal_matrix: WebGlUniformLocation,
+    u_light_position: WebGlUniformLocation,
+    u_light_color: WebGlUniformLocation,
+    u_ambient: WebGlUniformLocation,
+    u_diffuse: WebGlUniformLocation,
+    u_specular: WebGlUniformLocation,
+    u_shininess: WebGlUniformLocation,
+    u_blinn: WebGlUniformLocation,
+}
+
+impl Shader {
+    fn new(context: &Gl, vertex_src: &str, fragment_src: &str) -> Result<Shader, JsValue> {
+        let vertex_shader = utils::compile_shader(context, Gl::VERTEX_SHADER, vertex_src)?;
+        let fragment_shader = utils::compile_shader(context, Gl::FRAGMENT_SHADER, fragment_src)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let attribute = |name: &str| match context.get_attrib_location(&program, name) {
+            -1 => Err(format!("unable to get location for {}", name)),
+            p => Ok(p as u32),
+        };
+        let uniform = |name: &str| {
+            context
+                .get_uniform_location(&program, name)
+                .ok_or(format!("unable to get location for {}", name))
+        };
+
+        Ok(Shader {
+            a_position: attribute("a_position")?,
+            a_normal: attribute("a_normal")?,
+            u_model_view: uniform("u_model_view")?,
+            u_projection: uniform("u_projection")?,
+            u_normal_matrix: uniform("u_normal_matrix")?,
+            u_light_position: uniform("u_light_position")?,
+            u_light_color: uniform("u_light_color")?,
+            u_ambient: uniform("u_ambient")?,
+            u_diffuse: uniform("u_diffuse")?,
+            u_specular: uniform("u_specular")?,
+            u_shininess: uniform("u_shininess")?,
+            u_blinn: uniform("u_blinn")?,
+            program,
+        })
+    }
+}
+
+// The mesh being shown, in GPU buffers
+struct Model {
+    position_buffer: WebGlBuffer,
+    normal_buffer: WebGlBuffer,
+    index_buffer: WebGlBuffer,
+    count: i32,
+}
+
+impl Model {
+    fn new(context: &Gl) -> Result<Model, JsValue> {
+        Ok(Model {
+            position_buffer: context
+                .create_buffer()
+                .ok_or("failed to create a_position buffer")?,
+            normal_buffer: context
+                .create_buffer()
+                .ok_or("failed to create a_normal buffer")?,
+            index_buffer: context
+                .create_buffer()
+                .ok_or("failed to create index buffer")?,
+            count: 0,
+        })
+    }
+
+    fn upload(&mut self, context: &Gl, mesh: &Mesh) {
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position_buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec3::flatten(&mesh.positions),
+            Gl::STATIC_DRAW,
+        );
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.normal_buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec3::flatten(&mesh.normals),
+            Gl::STATIC_DRAW,
+        );
+        context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&self.index_buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ELEMENT_ARRAY_BUFFER,
+            &js_sys::Uint16Array::from(&mesh.indices[..]),
+            Gl::STATIC_DRAW,
+        );
+        self.count = mesh.indices.len() as i32;
+    }
+
+    fn draw(&self, context: &Gl, shader: &Shader) {
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position_buffer));
+        context.vertex_attrib_pointer_with_i32(shader.a_position, 3, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(shader.a_position);
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.normal_buffer));
+        context.vertex_attrib_pointer_with_i32(shader.a_normal, 3, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(shader.a_normal);
+
+        context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&self.index_buffer));
+        context.draw_elements_with_i32(Gl::TRIANGLES, self.count, Gl::UNSIGNED_SHORT, 0);
+
+        context.disable_vertex_attrib_array(shader.a_position);
+        context.disable_vertex_attrib_array(shader.a_normal);
+    }
+}
+
+// Reads the material and light editor
+struct Editor {
+    shading: web_sys::HtmlSelectElement,
+    light_type: web_sys::HtmlSelectElement,
+    light_x: web_sys::HtmlInputElement,
+    light_y: web_sys::HtmlInputElement,
+    light_z: web_sys::HtmlInputElement,
+    light_color: web_sys::HtmlInputElement,
+    ambient: web_sys::HtmlInputElement,
+    diffuse: web_sys::HtmlInputElement,
+    specular: web_sys::HtmlInputElement,
+    shininess: web_sys::HtmlInputElement,
+    spin: web_sys::HtmlInputElement,
+}
+
+impl Editor {
+    fn new() -> Result<Editor, JsValue> {
+        Ok(Editor {
+            shading: get_select("shading")?,
+            light_type: get_select("light-type")?,
+            light_x: get_input("light-x")?,
+            light_y: get_input("light-y")?,
+            light_z: get_input("light-z")?,
+            light_color: get_input("light-color")?,
+            ambient: get_input("ambient")?,
+            diffuse: get_input("diffuse")?,
+            specular: get_input("specular")?,
+            shininess: get_input("shininess")?,
+            spin: get_input("spin")?,
+        })
+    }
+
+    fn shading(&self) -> Shading {
+        Shading::parse(&self.shading.value()).unwrap_or(Shading::Phong)
+    }
+
+    fn light(&self) -> Light {
+        let coordinate = |input: &web_sys::HtmlInputElement| input.value().parse().unwrap_or(0.0);
+        let w = if self.light_type.value() == "directional" {
+            0.0
+        } else {
+            1.0
+        };
+        Light {
+            position: Vec4(
+                coordinate(&self.light_x),
+                coordinate(&self.light_y),
+                coordinate(&self.light_z),
+                w,
+            ),
+            color: hex_to_rgb(&self.light_color.value()).unwrap_or(Vec3(1.0, 1.0, 1.0)),
+        }
+    }
+
+    fn material(&self) -> Material {
+        let color = |input: &web_sys::HtmlInputElement, default| {
+            hex_to_rgb(&input.value()).unwrap_or(default)
+        };
+        Material {
+            ambient: color(&self.ambient, Vec3(0.1, 0.1, 0.1)),
+            diffuse: color(&self.diffuse, Vec3(0.8, 0.2, 0.2)),
+            specular: color(&self.specular, Vec3(1.0, 1.0, 1.0)),
+            shininess: self
+                .shininess
+                .value()
+                .parse::<f32>()
+                .ok()
+                .filter(|s| s.is_finite() && *s >= 1.0)
+                .unwrap_or(32.0),
+        }
+    }
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let mesh_select = get_select("mesh")?;
+    let editor = Editor::new()?;
+
+    let gouraud = Shader::new(
+        &context,
+        &format!(
+            "{}{}{}",
+            TRANSFORM_SRC, LIGHTING_SRC, GOURAUD_VERTEX_SHADER_SRC
+        ),
+        GOURAUD_FRAGMENT_SHADER_SRC,
+    )?;
+    let phong = Shader::new(
+        &context,
+        &format!("{}{}", TRANSFORM_SRC, PHONG_VERTEX_SHADER_SRC),
+        &format!(
+            "precision mediump float;\n{}{}",
+            LIGHTING_SRC, PHONG_FRAGMENT_SHADER_SRC
+        ),
+    )?;
+
+    let mut model = Model::new(&context)?;
+    model.upload(&context, &build_mesh(&mesh_select.value()));
+    let model = Rc::new(RefCell::new(model));
+
+    let context = Rc::new(context);
+    {
+        let model = model.clone();
+        let context = context.clone();
+        let mesh_select_ref = mesh_select.clone();
+        utils::add_event_listener(&mesh_select, "change", move |_event| {
+            model
+                .borrow_mut()
+                .upload(&context, &build_mesh(&mesh_select_ref.value()));
+        });
+    }
+
+    let projection = Mat4::perspective(
+        PI / 4.0,
+        canvas.width() as f32 / canvas.height() as f32,
+        0.1,
+        100.0,
+    );
+    let view = Mat4::translation(Vec3(0.0, 0.0, -CAMERA_DISTANCE));
+    let mut angle = 0.0f32;
+    let mut last = js_sys::Date::now();
+
+    context.enable(Gl::DEPTH_TEST);
+    context.enable(Gl::CULL_FACE);
+    context.clear_color(0.1, 0.1, 0.1, 1.0);
+    utils::render_loop(move || {
+        let now = js_sys::Date::now();
+        if editor.spin.checked() {
+            angle += SPIN_SPEED * ((now - last) / 1000.0) as f32;
+        }
+        last = now;
+
+        let shading = editor.shading();
+        let shader = match shading {
+            Shading::Gouraud => &gouraud,
+            Shading::Phong | Shading::BlinnPhong => &phong,
+        };
+        let light = editor.light();
+        let material = editor.material();
+
+        let model_view = view * Mat4::rotation_y(angle) * Mat4::rotation_x(0.4);
+        // Lights are placed in world space and don't turn with the model
+        let light_position = view * light.position;
+
+        context.use_program(Some(&shader.program));
+        context.uniform_matrix4fv_with_f32_array(Some(&shader.u_model_view), false, &model_view.0);
+        context.uniform_matrix4fv_with_f32_array(Some(&shader.u_projection), false, &projection.0);
+        context.uniform_matrix3fv_with_f32_array(
+            Some(&shader.u_normal_matrix),
+            false,
+            &Mat3::normal_matrix(&model_view).0,
+        );
+        context.uniform4f(
+            Some(&shader.u_light_position),
+            light_position.0,
+            light_position.1,
+            light_position.2,
+            light_position.3,
+        );
+        let uniform3 = |location, v: Vec3| context.uniform3f(Some(location), v.0, v.1, v.2);
+        uniform3(&shader.u_light_color, light.color);
+        uniform3(&shader.u_ambient, material.ambient);
+        uniform3(&shader.u_diffuse, material.diffuse);
+        uniform3(&shader.u_specular, material.specular);
+        context.uniform1f(Some(&shader.u_shininess), material.shininess);
+        context.uniform1i(
+            Some(&shader.u_blinn),
+            (shading == Shading::BlinnPhong) as i32,
+        );
+
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        model.borrow().draw(&context, shader);
+    });
+
+    Ok(())
+}
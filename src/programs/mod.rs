@@ -1,6 +1,7 @@
 pub mod square;
 pub mod triangle;
 
+pub mod ifs;
 pub mod sierpinski;
 pub mod sierpinski_3d;
 pub mod sierpinski_3d_points;
@@ -8,7 +9,13 @@ pub mod sierpinski_points;
 pub mod twist;
 
 pub mod cad;
+pub mod cube;
+pub mod life;
+pub mod lighting;
+pub mod picking;
 pub mod pixels;
+pub mod render_to_texture;
 pub mod rotating_square;
 pub mod rotating_square_controls;
+pub mod textured_cube;
 pub mod triangles;
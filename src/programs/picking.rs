@@ -0,0 +1,419 @@
+use crate::{
+    geometry::{self, Mesh},
+    linear_algebra::{Mat3, Mat4, Vec3},
+    picking::Picker,
+    utils,
+};
+use std::collections::BTreeSet;
+use std::f32::consts::PI;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 a_position;
+attribute vec3 a_normal;
+
+uniform mat4 u_model_view;
+uniform mat4 u_projection;
+uniform mat3 u_normal_matrix;
+
+varying vec3 f_normal;
+
+void main() {
+    f_normal = u_normal_matrix * a_normal;
+    gl_Position = u_projection * u_model_view * a_position;
+}
+"#;
+
+// Diffuse light from a fixed direction in eye space
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform vec3 u_color;
+
+varying vec3 f_normal;
+
+void main() {
+    vec3 light = normalize(vec3(0.4, 0.8, 0.6));
+    float diffuse = max(dot(normalize(f_normal), light), 0.0);
+    gl_FragColor = vec4(u_color * (0.25 + 0.75 * diffuse), 1.0);
+}
+"#;
+
+// Objects per side of the grid
+const GRID: usize = 7;
+const SPACING: f32 = 0.8;
+const CAMERA_DISTANCE: f32 = 10.0;
+// Radians per second the grid turns about the y axis
+const SPIN_SPEED: f32 = PI / 10.0;
+// A drag shorter than this many pixels is a click
+const CLICK_DISTANCE: i32 = 3;
+
+const PALETTE: [Vec3; 6] = [
+    Vec3(0.85, 0.3, 0.3),
+    Vec3(0.3, 0.7, 0.35),
+    Vec3(0.3, 0.45, 0.85),
+    Vec3(0.75, 0.4, 0.8),
+    Vec3(0.3, 0.75, 0.8),
+    Vec3(0.9, 0.55, 0.25),
+];
+const SELECTED_COLOR: Vec3 = Vec3(1.0, 0.95, 0.3);
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+fn get_input(id: &str) -> Result<web_sys::HtmlInputElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(JsValue::from)
+}
+
+fn build_meshes() -> Vec<Mesh> {
+    vec![
+        geometry::cuboid(Vec3(0.5, 0.5, 0.5)),
+        geometry::icosphere(0.3, 2),
+        geometry::torus(0.25, 0.1, 24, 12),
+        geometry::cone(0.3, 0.6, 24),
+        geometry::cylinder(0.25, 0.6, 24),
+    ]
+}
+
+struct Shader {
+    program: WebGlProgram,
+    a_position: u32,
+    a_normal: u32,
+    u_model_view: WebGlUniformLocation,
+    u_projection: WebGlUniformLocation,
+    u_normal_matrix: WebGlUniformLocation,
+    u_color: WebGlUniformLocation,
+}
+
+impl Shader {
+    fn new(context: &Gl) -> Result<Shader, JsValue> {
+        let vertex_shader = utils::compile_shader(context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+        let fragment_shader =
+            utils::compile_shader(context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let attribute = |name: &str| match context.get_attrib_location(&program, name) {
+            -1 => Err(format!("unable to get location for {}", name)),
+            p => Ok(p as u32),
+        };
+        let uniform = |name: &str| {
+            context
+                .get_uniform_location(&program, name)
+                .ok_or(format!("unable to get location for {}", name))
+        };
+
+        Ok(Shader {
+            a_position: attribute("a_position")?,
+            a_normal: attribute("a_normal")?,
+            u_model_view: uniform("u_model_view")?,
+            u_projection: uniform("u_projection")?,
+            u_normal_matrix: uniform("u_normal_matrix")?,
+            u_color: uniform("u_color")?,
+            program,
+        })
+    }
+}
+
+// A mesh in GPU buffers
+struct Model {
+    position_buffer: WebGlBuffer,
+    normal_buffer: WebGlBuffer,
+    index_buffer: WebGlBuffer,
+    count: i32,
+}
+
+impl Model {
+    fn new(context: &Gl, mesh: &Mesh) -> Result<Model, JsValue> {
+        let buffer = |data: &js_sys::Object, target| -> Result<WebGlBuffer, JsValue> {
+            let buffer = context.create_buffer().ok_or("failed to create buffer")?;
+            context.bind_buffer(target, Some(&buffer));
+            context.buffer_data_with_array_buffer_view(target, data, Gl::STATIC_DRAW);
+            Ok(buffer)
+        };
+        Ok(Model {
+            position_buffer: buffer(&Vec3::flatten(&mesh.positions), Gl::ARRAY_BUFFER)?,
+            normal_buffer: buffer(&Vec3::flatten(&mesh.normals), Gl::ARRAY_BUFFER)?,
+            index_buffer: buffer(
+                &js_sys::Uint16Array::from(&mesh.indices[..]),
+                Gl::ELEMENT_ARRAY_BUFFER,
+            )?,
+            count: mesh.indices.len() as i32,
+        })
+    }
+
+    // The picking pass only needs positions, so `a_normal` is optional
+    fn draw(&self, context: &Gl, a_position: u32, a_normal: Option<u32>) {
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position_buffer));
+        context.vertex_attrib_pointer_with_i32(a_position, 3, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(a_position);
+        if let Some(a_normal) = a_normal {
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.normal_buffer));
+            context.vertex_attrib_pointer_with_i32(a_normal, 3, Gl::FLOAT, false, 0, 0);
+            context.enable_vertex_attrib_array(a_normal);
+        }
+
+        context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&self.index_buffer));
+        context.draw_elements_with_i32(Gl::TRIANGLES, self.count, Gl::UNSIGNED_SHORT, 0);
+
+        context.disable_vertex_attrib_array(a_position);
+        if let Some(a_normal) = a_normal {
+            context.disable_vertex_attrib_array(a_normal);
+        }
+    }
+}
+
+struct Object {
+    // Index into the models
+    model: usize,
+    position: Vec3,
+    color: Vec3,
+}
+
+// A grid in the xz plane, with the object at index i having id i
+fn layout(models: usize) -> Vec<Object> {
+    let offset = (GRID - 1) as f32 * SPACING / 2.0;
+    (0..GRID * GRID)
+        .map(|i| Object {
+            model: i % models,
+            position: Vec3(
+                (i % GRID) as f32 * SPACING - offset,
+                0.0,
+                (i / GRID) as f32 * SPACING - offset,
+            ),
+            color: PALETTE[i % PALETTE.len()],
+        })
+        .collect()
+}
+
+type Point = (i32, i32);
+
+// A finished click or drag, picked in the next frame when the objects'
+// transforms are known. `additive` is whether shift was held.
+#[derive(Copy, Clone, Debug)]
+enum Request {
+    Click { point: Point, additive: bool },
+    Rect { a: Point, b: Point, additive: bool },
+}
+
+#[derive(Default)]
+struct Selection {
+    selected: BTreeSet<u32>,
+    // Where the mouse went down and where it is now, in canvas pixels
+    drag: Option<(Point, Point)>,
+    pending: Option<Request>,
+}
+
+impl Selection {
+    fn mouse_down(&mut self, p: Point) {
+        self.drag = Some((p, p));
+    }
+
+    fn mouse_move(&mut self, p: Point) {
+        if let Some((_, current)) = &mut self.drag {
+            *current = p;
+        }
+    }
+
+    fn mouse_up(&mut self, additive: bool) {
+        if let Some((a, b)) = self.drag.take() {
+            let click = (a.0 - b.0).abs() <= CLICK_DISTANCE && (a.1 - b.1).abs() <= CLICK_DISTANCE;
+            self.pending = Some(if click {
+                Request::Click { point: a, additive }
+            } else {
+                Request::Rect { a, b, additive }
+            });
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.drag = None;
+    }
+
+    // The rectangle being dragged out, once it is too big to be a click
+    fn marquee(&self) -> Option<(Point, Point)> {
+        self.drag.filter(|(a, b)| {
+            (a.0 - b.0).abs() > CLICK_DISTANCE || (a.1 - b.1).abs() > CLICK_DISTANCE
+        })
+    }
+
+    // Clicking selects the object under the cursor, or with shift toggles
+    // it. Dragging selects every object showing in the rectangle, or with
+    // shift adds them.
+    fn apply(&mut self, request: Request, ids: Vec<u32>) {
+        match request {
+            Request::Click { additive: true, .. } => {
+                if let Some(&id) = ids.first() {
+                    if !self.selected.remove(&id) {
+                        self.selected.insert(id);
+                    }
+                }
+            }
+            Request::Rect { additive: true, .. } => self.selected.extend(ids),
+            Request::Click { .. } | Request::Rect { .. } => {
+                self.selected = ids.into_iter().collect()
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        if self.selected.is_empty() {
+            return String::from("Nothing selected");
+        }
+        let ids: Vec<String> = self.selected.iter().map(|id| id.to_string()).collect();
+        format!("Selected {}: {}", ids.len(), ids.join(", "))
+    }
+}
+
+fn update_marquee(marquee: &web_sys::Element, rect: Option<(Point, Point)>) -> Result<(), JsValue> {
+    match rect {
+        Some((a, b)) => marquee.set_attribute(
+            "style",
+            &format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; \
+                 border: 1px dashed white; pointer-events: none;",
+                a.0.min(b.0),
+                a.1.min(b.1),
+                (a.0 - b.0).abs(),
+                (a.1 - b.1).abs()
+            ),
+        ),
+        None => marquee.set_attribute("style", "display: none;"),
+    }
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+    let (width, height) = (canvas.width(), canvas.height());
+
+    let spin = get_input("spin")?;
+    let marquee = get_element("marquee").ok_or("marquee not found")?;
+    let status = get_element("selection").ok_or("selection not found")?;
+
+    let shader = Shader::new(&context)?;
+    let picker = Picker::new(&context, width, height)?;
+    let models = build_meshes()
+        .iter()
+        .map(|mesh| Model::new(&context, mesh))
+        .collect::<Result<Vec<_>, _>>()?;
+    let objects = layout(models.len());
+
+    let selection = Rc::new(RefCell::new(Selection::default()));
+    status.set_text_content(Some(&selection.borrow().describe()));
+    {
+        let selection = selection.clone();
+        utils::add_event_listener(&canvas, "mousedown", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            selection
+                .borrow_mut()
+                .mouse_down((event.offset_x(), event.offset_y()));
+        });
+    }
+    {
+        let selection = selection.clone();
+        let marquee = marquee.clone();
+        utils::add_event_listener(&canvas, "mousemove", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let mut selection = selection.borrow_mut();
+            selection.mouse_move((event.offset_x(), event.offset_y()));
+            update_marquee(&marquee, selection.marquee()).unwrap();
+        });
+    }
+    {
+        let selection = selection.clone();
+        let marquee = marquee.clone();
+        utils::add_event_listener(&canvas, "mouseup", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            selection.borrow_mut().mouse_up(event.shift_key());
+            update_marquee(&marquee, None).unwrap();
+        });
+    }
+    {
+        let selection = selection.clone();
+        utils::add_event_listener(&canvas, "mouseleave", move |_event| {
+            selection.borrow_mut().cancel();
+            update_marquee(&marquee, None).unwrap();
+        });
+    }
+
+    let projection = Mat4::perspective(PI / 4.0, width as f32 / height as f32, 0.1, 100.0);
+    let view = Mat4::translation(Vec3(0.0, 0.0, -CAMERA_DISTANCE)) * Mat4::rotation_x(0.6);
+    let mut angle = 0.0f32;
+    let mut last = js_sys::Date::now();
+
+    context.enable(Gl::CULL_FACE);
+    utils::render_loop(move || {
+        let now = js_sys::Date::now();
+        if spin.checked() {
+            angle += SPIN_SPEED * ((now - last) / 1000.0) as f32;
+        }
+        last = now;
+
+        let model_views: Vec<Mat4> = objects
+            .iter()
+            .map(|object| view * Mat4::rotation_y(angle) * Mat4::translation(object.position))
+            .collect();
+
+        // Picked with the transforms the objects are about to be drawn with,
+        // so the result matches what is on screen
+        let request = selection.borrow_mut().pending.take();
+        if let Some(request) = request {
+            picker.begin(&context);
+            for (i, (object, model_view)) in objects.iter().zip(&model_views).enumerate() {
+                picker
+                    .object(&context, i as u32, &(projection * *model_view))
+                    .unwrap();
+                models[object.model].draw(&context, picker.a_position, None);
+            }
+            let ids = match request {
+                Request::Click { point, .. } => {
+                    picker.pick(&context, point).unwrap().into_iter().collect()
+                }
+                Request::Rect { a, b, .. } => picker.pick_rect(&context, a, b).unwrap(),
+            };
+            picker.end(&context, width, height);
+
+            let mut selection = selection.borrow_mut();
+            selection.apply(request, ids);
+            status.set_text_content(Some(&selection.describe()));
+        }
+
+        context.use_program(Some(&shader.program));
+        context.uniform_matrix4fv_with_f32_array(Some(&shader.u_projection), false, &projection.0);
+        context.enable(Gl::DEPTH_TEST);
+        context.clear_color(0.1, 0.1, 0.1, 1.0);
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+
+        let selection = selection.borrow();
+        for (i, (object, model_view)) in objects.iter().zip(&model_views).enumerate() {
+            let color = if selection.selected.contains(&(i as u32)) {
+                SELECTED_COLOR
+            } else {
+                object.color
+            };
+            context.uniform_matrix4fv_with_f32_array(
+                Some(&shader.u_model_view),
+                false,
+                &model_view.0,
+            );
+            context.uniform_matrix3fv_with_f32_array(
+                Some(&shader.u_normal_matrix),
+                false,
+                &Mat3::normal_matrix(model_view).0,
+            );
+            context.uniform3f(Some(&shader.u_color), color.0, color.1, color.2);
+            models[object.model].draw(&context, shader.a_position, Some(shader.a_normal));
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::linear_algebra::{Vec2, Vec4};
+use std::f32::consts::PI;
+
+// Triangles used to round off each sample of a stroke
+pub const CAP_SEGMENTS: usize = 16;
+
+// A filled circle as a TRIANGLES stream
+pub fn disc(center: Vec2, radius: f32, segments: usize) -> Vec<Vec2> {
+    let point = |i: usize| {
+        let angle = 2.0 * PI * i as f32 / segments as f32;
+        center + Vec2(angle.cos(), angle.sin()) * radius
+    };
+    (0..segments)
+        .flat_map(|i| vec![center, point(i), point(i + 1)])
+        .collect()
+}
+
+// A `width` wide rectangle from `a` to `b` as two triangles
+pub fn segment(a: Vec2, b: Vec2, width: f32) -> Vec<Vec2> {
+    let d = b - a;
+    let length = d.length();
+    if length == 0.0 {
+        return vec![];
+    }
+    let offset = Vec2(-d.1, d.0) * (width / 2.0 / length);
+    vec![
+        a + offset,
+        a - offset,
+        b - offset,
+        a + offset,
+        b - offset,
+        b + offset,
+    ]
+}
+
+// One continuous brush stroke, in canvas pixels
+pub struct Stroke {
+    pub color: Vec4,
+    pub width: f32,
+    pub points: Vec<Vec2>,
+}
+
+impl Stroke {
+    pub fn new(color: Vec4, width: f32) -> Stroke {
+        Stroke {
+            color,
+            width,
+            points: vec![],
+        }
+    }
+
+    // Adds a sample and returns the triangles that connect it to the previous
+    // one: a thick segment and a round cap, so fast strokes have no gaps
+    pub fn extend(&mut self, p: Vec2) -> Vec<Vec2> {
+        let mut triangles = match self.points.last() {
+            Some(&last) => segment(last, p, self.width),
+            None => vec![],
+        };
+        triangles.extend(disc(p, self.width / 2.0, CAP_SEGMENTS));
+        self.points.push(p);
+        triangles
+    }
+}
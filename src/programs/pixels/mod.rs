@@ -0,0 +1,317 @@
+mod brush;
+mod raster;
+
+use self::brush::Stroke;
+use self::raster::{Blitter, Raster};
+use crate::color;
+use crate::linear_algebra::{Vec2, Vec4};
+use crate::utils;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlUniformLocation};
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec2 v_position;
+
+// Raster size in pixels
+uniform vec2 u_resolution;
+
+void main() {
+    vec2 clip = v_position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform vec4 u_color;
+
+void main() {
+    gl_FragColor = u_color;
+}
+"#;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Tool {
+    Brush,
+    Eraser,
+}
+
+impl Tool {
+    fn parse(tool: &str) -> Option<Tool> {
+        match tool {
+            "brush" => Some(Tool::Brush),
+            "eraser" => Some(Tool::Eraser),
+            _ => None,
+        }
+    }
+}
+
+// Draws stroke triangles, given in raster pixels, into the raster
+struct Brush {
+    program: WebGlProgram,
+    buffer: WebGlBuffer,
+    v_position: u32,
+    u_resolution: WebGlUniformLocation,
+    u_color: WebGlUniformLocation,
+}
+
+impl Brush {
+    fn new(context: &Gl) -> Result<Brush, JsValue> {
+        let vertex_shader = utils::compile_shader(context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+        let fragment_shader =
+            utils::compile_shader(context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let buffer = context
+            .create_buffer()
+            .ok_or("failed to create v_position buffer")?;
+        let v_position = match context.get_attrib_location(&program, "v_position") {
+            -1 => Err("unable to get location for v_position"),
+            p => Ok(p as u32),
+        }?;
+        let u_resolution = context
+            .get_uniform_location(&program, "u_resolution")
+            .ok_or("unable to get location for u_resolution")?;
+        let u_color = context
+            .get_uniform_location(&program, "u_color")
+            .ok_or("unable to get location for u_color")?;
+
+        Ok(Brush {
+            program,
+            buffer,
+            v_position,
+            u_resolution,
+            u_color,
+        })
+    }
+
+    // Blending is off, so the eraser's transparent color replaces whatever
+    // is underneath instead of leaving it as it is
+    fn paint(&self, context: &Gl, raster: &Raster, triangles: &[Vec2], color: Vec4) {
+        if triangles.is_empty() {
+            return;
+        }
+        raster.bind(context);
+        context.use_program(Some(&self.program));
+        context.uniform2f(
+            Some(&self.u_resolution),
+            raster.width() as f32,
+            raster.height() as f32,
+        );
+        context.uniform4f(Some(&self.u_color), color.0, color.1, color.2, color.3);
+
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec2::flatten(triangles),
+            Gl::STREAM_DRAW,
+        );
+        context.vertex_attrib_pointer_with_i32(self.v_position, 2, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(self.v_position);
+        context.draw_arrays(Gl::TRIANGLES, 0, triangles.len() as i32);
+        context.disable_vertex_attrib_array(self.v_position);
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+    }
+}
+
+// Strokes are painted into the raster as they are drawn, so only the one in
+// progress is kept
+struct Painting {
+    raster: Raster,
+    brush: Brush,
+    blitter: Blitter,
+    stroke: Option<Stroke>,
+}
+
+impl Painting {
+    fn begin(&mut self, context: &Gl, p: Vec2, color: Vec4, width: f32) {
+        self.stroke = Some(Stroke::new(color, width));
+        self.extend(context, p);
+    }
+
+    fn extend(&mut self, context: &Gl, p: Vec2) {
+        if let Some(stroke) = self.stroke.as_mut() {
+            let triangles = stroke.extend(p);
+            self.brush
+                .paint(context, &self.raster, &triangles, stroke.color);
+        }
+    }
+
+    fn end(&mut self) {
+        self.stroke = None;
+    }
+
+    fn clear(&mut self, context: &Gl) {
+        self.raster.clear(context);
+        self.stroke = None;
+    }
+
+    // Grows the raster to cover a canvas of the given size. It never shrinks,
+    // so what is painted outside a smaller window is still there when the
+    // window grows again; only the part on the canvas is drawn.
+    fn resize(&mut self, context: &Gl, width: u32, height: u32) -> Result<(), JsValue> {
+        let width = width.max(self.raster.width());
+        let height = height.max(self.raster.height());
+        if width == self.raster.width() && height == self.raster.height() {
+            return Ok(());
+        }
+        let resized = self.raster.resized(context, &self.blitter, width, height)?;
+        std::mem::replace(&mut self.raster, resized).delete(context);
+        Ok(())
+    }
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let tool_select = get_element("tool").ok_or("tool not found")?;
+    let tool_select = tool_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
+    let color_picker = get_element("color").ok_or("color not found")?;
+    let color_picker = color_picker.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let brush_size = get_element("brush-size").ok_or("brush-size not found")?;
+    let brush_size = brush_size.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let clear_btn = get_element("clear").ok_or("clear not found")?;
+    let clear_btn = clear_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let save_btn = get_element("save-png").ok_or("save-png not found")?;
+    let save_btn = save_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let status = get_element("status").ok_or("status not found")?;
+
+    // The canvas is sized by the page, so its drawing buffer follows its
+    // size on screen
+    canvas.set_width(canvas.client_width().max(1) as u32);
+    canvas.set_height(canvas.client_height().max(1) as u32);
+
+    let painting = Rc::new(RefCell::new(Painting {
+        raster: Raster::new(&context, canvas.width(), canvas.height())?,
+        brush: Brush::new(&context)?,
+        blitter: Blitter::new(&context)?,
+        stroke: None,
+    }));
+
+    context.blend_func(Gl::SRC_ALPHA, Gl::ONE_MINUS_SRC_ALPHA);
+
+    let canvas = Rc::new(canvas);
+    let context = Rc::new(context);
+
+    {
+        let painting = painting.clone();
+        utils::add_event_listener(&canvas, "mouseup", move |_event| {
+            painting.borrow_mut().end();
+        });
+    }
+    {
+        let painting = painting.clone();
+        utils::add_event_listener(&canvas, "mouseleave", move |_event| {
+            painting.borrow_mut().end();
+        });
+    }
+    {
+        let painting = painting.clone();
+        let context = context.clone();
+        utils::add_event_listener(&canvas, "mousedown", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let color = match Tool::parse(&tool_select.value()) {
+                Some(Tool::Eraser) => Vec4(0.0, 0.0, 0.0, 0.0),
+                _ => color::hex_to_rgba(&color_picker.value()).unwrap_or(Vec4(0.0, 0.0, 0.0, 1.0)),
+            };
+            let width = brush_size.value().parse().unwrap_or(10.0);
+            let p = Vec2(event.offset_x() as f32, event.offset_y() as f32);
+            painting.borrow_mut().begin(&context, p, color, width);
+        });
+    }
+    {
+        let painting = painting.clone();
+        let context = context.clone();
+        utils::add_event_listener(&canvas, "mousemove", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let p = Vec2(event.offset_x() as f32, event.offset_y() as f32);
+            painting.borrow_mut().extend(&context, p);
+        });
+    }
+    {
+        let painting = painting.clone();
+        let context = context.clone();
+        utils::add_event_listener(&clear_btn, "click", move |_event| {
+            painting.borrow_mut().clear(&context);
+        });
+    }
+    {
+        let painting = painting.clone();
+        let context = context.clone();
+        let canvas = canvas.clone();
+        let status = status.clone();
+        utils::add_event_listener(&save_btn, "click", move |_event| {
+            // Redraw the canvas with only the raster, transparent where
+            // nothing has been painted, for the encoder to read back before
+            // the next frame draws the background again
+            let painting = painting.borrow();
+            let (width, height) = (canvas.width(), canvas.height());
+            context.viewport(0, 0, width as i32, height as i32);
+            context.clear_color(0.0, 0.0, 0.0, 0.0);
+            context.clear(Gl::COLOR_BUFFER_BIT);
+            painting.blitter.draw(
+                &context,
+                painting.raster.texture(),
+                painting.raster.bounds(width, height),
+            );
+
+            let saved = {
+                let status = status.clone();
+                utils::canvas_to_png(&canvas, move |blob| {
+                    let saved = match blob.dyn_into::<web_sys::Blob>() {
+                        Ok(blob) => utils::download_blob("painting.png", &blob),
+                        Err(_) => Err(JsValue::from_str("failed to encode the painting")),
+                    };
+                    utils::report(&status, saved);
+                })
+            };
+            if saved.is_err() {
+                utils::report(&status, saved);
+            }
+        });
+    }
+
+    utils::render_loop(move || {
+        let (width, height) = (
+            canvas.client_width().max(1) as u32,
+            canvas.client_height().max(1) as u32,
+        );
+        if width != canvas.width() || height != canvas.height() {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+
+        let mut painting = painting.borrow_mut();
+        if let Err(e) = painting.resize(&context, width, height) {
+            status.set_text_content(Some(&utils::error_text(&e)));
+        }
+
+        context.viewport(0, 0, width as i32, height as i32);
+        context.clear_color(0.5, 0.5, 0.5, 1.0);
+        context.clear(Gl::COLOR_BUFFER_BIT);
+        context.enable(Gl::BLEND);
+        painting.blitter.draw(
+            &context,
+            painting.raster.texture(),
+            painting.raster.bounds(width, height),
+        );
+        context.disable(Gl::BLEND);
+    });
+
+    Ok(())
+}
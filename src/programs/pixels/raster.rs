@@ -0,0 +1,173 @@
+use crate::{
+    render_target::RenderTarget,
+    texture::{Filter, Options, Wrap},
+    utils,
+};
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl, WebGlTexture, WebGlUniformLocation,
+};
+
+const BLIT_VERTEX_SHADER_SRC: &str = r#"
+attribute vec2 a_corner;
+
+// Clip space rectangle to draw into: left, bottom, right, top
+uniform vec4 u_bounds;
+
+varying vec2 f_texcoord;
+
+void main() {
+    gl_Position = vec4(mix(u_bounds.xy, u_bounds.zw, a_corner), 0.0, 1.0);
+    f_texcoord = a_corner;
+}
+"#;
+
+const BLIT_FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_texture;
+
+varying vec2 f_texcoord;
+
+void main() {
+    gl_FragColor = texture2D(u_texture, f_texcoord);
+}
+"#;
+
+// Clip space bounds that draw a `width` by `height` image unscaled into the
+// top left corner of a `target_width` by `target_height` framebuffer. Parts
+// that don't fit fall outside clip space and are cut off.
+fn top_left(width: u32, height: u32, target_width: u32, target_height: u32) -> [f32; 4] {
+    let right = -1.0 + 2.0 * width as f32 / target_width as f32;
+    let bottom = 1.0 - 2.0 * height as f32 / target_height as f32;
+    [-1.0, bottom, right, 1.0]
+}
+
+// An offscreen RGBA texture that strokes are painted into and that keeps
+// them between frames. It starts out transparent.
+pub struct Raster {
+    target: RenderTarget,
+}
+
+impl Raster {
+    pub fn new(context: &Gl, width: u32, height: u32) -> Result<Raster, JsValue> {
+        let options = Options {
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+        };
+        let raster = Raster {
+            target: RenderTarget::new(context, width, height, options, false)?,
+        };
+        raster.clear(context);
+        Ok(raster)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.target.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.target.height
+    }
+
+    pub fn texture(&self) -> &WebGlTexture {
+        &self.target.color.texture
+    }
+
+    // Directs drawing into the raster
+    pub fn bind(&self, context: &Gl) {
+        self.target.bind(context);
+    }
+
+    pub fn clear(&self, context: &Gl) {
+        self.bind(context);
+        context.clear_color(0.0, 0.0, 0.0, 0.0);
+        context.clear(Gl::COLOR_BUFFER_BIT);
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+    }
+
+    // Where to draw the raster so that its pixels line up with those of a
+    // `width` by `height` framebuffer
+    pub fn bounds(&self, width: u32, height: u32) -> [f32; 4] {
+        top_left(self.width(), self.height(), width, height)
+    }
+
+    // A raster of the new size with this one's pixels copied to its top left
+    // corner, unscaled. Anything outside the new size is cut off.
+    pub fn resized(
+        &self,
+        context: &Gl,
+        blitter: &Blitter,
+        width: u32,
+        height: u32,
+    ) -> Result<Raster, JsValue> {
+        let resized = Raster::new(context, width, height)?;
+
+        resized.bind(context);
+        blitter.draw(context, self.texture(), self.bounds(width, height));
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        Ok(resized)
+    }
+
+    pub fn delete(self, context: &Gl) {
+        self.target.delete(context);
+    }
+}
+
+// Draws a texture into a rectangle of whatever framebuffer is bound
+pub struct Blitter {
+    program: WebGlProgram,
+    corners: WebGlBuffer,
+    a_corner: u32,
+    u_bounds: WebGlUniformLocation,
+}
+
+impl Blitter {
+    pub fn new(context: &Gl) -> Result<Blitter, JsValue> {
+        let vertex_shader =
+            utils::compile_shader(context, Gl::VERTEX_SHADER, BLIT_VERTEX_SHADER_SRC)?;
+        let fragment_shader =
+            utils::compile_shader(context, Gl::FRAGMENT_SHADER, BLIT_FRAGMENT_SHADER_SRC)?;
+        let program = utils::link_program(context, &vertex_shader, &fragment_shader)?;
+
+        let corners = context
+            .create_buffer()
+            .ok_or("failed to create a_corner buffer")?;
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&corners));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0][..]),
+            Gl::STATIC_DRAW,
+        );
+
+        let a_corner = match context.get_attrib_location(&program, "a_corner") {
+            -1 => Err("unable to get location for a_corner"),
+            p => Ok(p as u32),
+        }?;
+        let u_bounds = context
+            .get_uniform_location(&program, "u_bounds")
+            .ok_or("unable to get location for u_bounds")?;
+
+        Ok(Blitter {
+            program,
+            corners,
+            a_corner,
+            u_bounds,
+        })
+    }
+
+    pub fn draw(&self, context: &Gl, texture: &WebGlTexture, bounds: [f32; 4]) {
+        context.use_program(Some(&self.program));
+        context.active_texture(Gl::TEXTURE0);
+        context.bind_texture(Gl::TEXTURE_2D, Some(texture));
+        context.uniform4fv_with_f32_array(Some(&self.u_bounds), &bounds);
+
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.corners));
+        context.vertex_attrib_pointer_with_i32(self.a_corner, 2, Gl::FLOAT, false, 0, 0);
+        context.enable_vertex_attrib_array(self.a_corner);
+        context.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+        context.disable_vertex_attrib_array(self.a_corner);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    linear_algebra::{Mat4, Vec2, Vec3},
+    programs::sierpinski_3d,
+    render_target::RenderTarget,
+    texture::{Filter, Options, Wrap},
+    utils,
+};
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl};
+
+const GASKET_VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 a_position;
+attribute vec3 a_color;
+
+uniform mat4 u_model_view_projection;
+
+varying vec4 f_color;
+
+void main() {
+    gl_Position = u_model_view_projection * a_position;
+    f_color = vec4(a_color, 1.0);
+}
+"#;
+
+const GASKET_FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+varying vec4 f_color;
+
+void main() {
+    gl_FragColor = f_color;
+}
+"#;
+
+const QUAD_VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 a_position;
+attribute vec2 a_uv;
+
+uniform mat4 u_model_view_projection;
+
+varying vec2 f_uv;
+
+void main() {
+    gl_Position = u_model_view_projection * a_position;
+    f_uv = a_uv;
+}
+"#;
+
+const QUAD_FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_texture;
+
+varying vec2 f_uv;
+
+void main() {
+    gl_FragColor = texture2D(u_texture, f_uv);
+}
+"#;
+
+const SUBDIVISIONS: usize = 3;
+const TARGET_SIZE: u32 = 512;
+
+// Radians per second
+const GASKET_SPEED: f32 = PI / 4.0;
+const QUAD_SPEED: f32 = PI / 6.0;
+
+fn attribute(context: &Gl, program: &WebGlProgram, name: &str) -> Result<u32, String> {
+    match context.get_attrib_location(program, name) {
+        -1 => Err(format!("unable to get location for {}", name)),
+        p => Ok(p as u32),
+    }
+}
+
+fn static_buffer(context: &Gl, data: &js_sys::Float32Array) -> Result<WebGlBuffer, String> {
+    let buffer = context.create_buffer().ok_or("failed to create buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&buffer));
+    context.buffer_data_with_array_buffer_view(Gl::ARRAY_BUFFER, data, Gl::STATIC_DRAW);
+    Ok(buffer)
+}
+
+// The two programs share attribute locations, so each pass points them at
+// its own buffers
+fn bind_attribute(context: &Gl, buffer: &WebGlBuffer, location: u32, size: i32) {
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(buffer));
+    context.vertex_attrib_pointer_with_i32(location, size, Gl::FLOAT, false, 0, 0);
+    context.enable_vertex_attrib_array(location);
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let vertex_shader =
+        utils::compile_shader(&context, Gl::VERTEX_SHADER, GASKET_VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, GASKET_FRAGMENT_SHADER_SRC)?;
+    let gasket_program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+
+    let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, QUAD_VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, QUAD_FRAGMENT_SHADER_SRC)?;
+    let quad_program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+
+    let (points, colors) = sierpinski_3d::gasket(SUBDIVISIONS);
+    let gasket_positions = static_buffer(&context, &Vec3::flatten(&points))?;
+    let gasket_colors = static_buffer(&context, &Vec3::flatten(&colors))?;
+    let gasket_a_position = attribute(&context, &gasket_program, "a_position")?;
+    let gasket_a_color = attribute(&context, &gasket_program, "a_color")?;
+    let gasket_u_mvp = context
+        .get_uniform_location(&gasket_program, "u_model_view_projection")
+        .ok_or("unable to get location for u_model_view_projection")?;
+
+    let quad_positions = static_buffer(
+        &context,
+        &Vec3::flatten(&[
+            Vec3(-1.0, -1.0, 0.0),
+            Vec3(1.0, -1.0, 0.0),
+            Vec3(-1.0, 1.0, 0.0),
+            Vec3(1.0, 1.0, 0.0),
+        ]),
+    )?;
+    let quad_uvs = static_buffer(
+        &context,
+        &Vec2::flatten(&[
+            Vec2(0.0, 0.0),
+            Vec2(1.0, 0.0),
+            Vec2(0.0, 1.0),
+            Vec2(1.0, 1.0),
+        ]),
+    )?;
+    let quad_a_position = attribute(&context, &quad_program, "a_position")?;
+    let quad_a_uv = attribute(&context, &quad_program, "a_uv")?;
+    let quad_u_mvp = context
+        .get_uniform_location(&quad_program, "u_model_view_projection")
+        .ok_or("unable to get location for u_model_view_projection")?;
+    let quad_u_texture = context
+        .get_uniform_location(&quad_program, "u_texture")
+        .ok_or("unable to get location for u_texture")?;
+
+    let target = RenderTarget::new(
+        &context,
+        TARGET_SIZE,
+        TARGET_SIZE,
+        Options {
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+        },
+        true,
+    )?;
+
+    // Orthographic, flipping z so that larger z is nearer the viewer
+    let gasket_projection = Mat4::orthographic(-1.0, 1.0, -1.0, 1.0, -1.0, 1.0);
+    let quad_projection = Mat4::perspective(
+        PI / 4.0,
+        canvas.width() as f32 / canvas.height() as f32,
+        0.1,
+        100.0,
+    );
+    let view = Mat4::translation(Vec3(0.0, 0.0, -3.5));
+    let start = js_sys::Date::now();
+
+    context.enable(Gl::DEPTH_TEST);
+    utils::render_loop(move || {
+        let seconds = ((js_sys::Date::now() - start) / 1000.0) as f32;
+
+        // Draw the gasket into the texture
+        target.bind(&context);
+        context.use_program(Some(&gasket_program));
+        bind_attribute(&context, &gasket_positions, gasket_a_position, 3);
+        bind_attribute(&context, &gasket_colors, gasket_a_color, 3);
+        let model = Mat4::rotation_y(GASKET_SPEED * seconds) * Mat4::rotation_x(0.3);
+        context.uniform_matrix4fv_with_f32_array(
+            Some(&gasket_u_mvp),
+            false,
+            &(gasket_projection * model).0,
+        );
+        context.clear_color(1.0, 1.0, 1.0, 1.0);
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        context.draw_arrays(Gl::TRIANGLES, 0, points.len() as i32);
+
+        // Then show the texture on a quad turning in front of the camera
+        RenderTarget::unbind(&context, canvas.width(), canvas.height());
+        context.use_program(Some(&quad_program));
+        bind_attribute(&context, &quad_positions, quad_a_position, 3);
+        bind_attribute(&context, &quad_uvs, quad_a_uv, 2);
+        target.color.bind(&context, 0);
+        context.uniform1i(Some(&quad_u_texture), 0);
+        let model = Mat4::rotation_y(QUAD_SPEED * seconds);
+        context.uniform_matrix4fv_with_f32_array(
+            Some(&quad_u_mvp),
+            false,
+            &(quad_projection * view * model).0,
+        );
+        context.clear_color(0.2, 0.2, 0.2, 1.0);
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        context.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+    });
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use crate::utils;
 use wasm_bindgen::prelude::*;
 use web_sys::WebGlRenderingContext as Gl;
 
-const VERTEX_SHADER_SRC: &'static str = r#"
+const VERTEX_SHADER_SRC: &str = r#"
 attribute vec4 v_position;
 uniform float theta;
 
@@ -14,7 +14,7 @@ void main() {
 }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
+const FRAGMENT_SHADER_SRC: &str = r#"
 precision mediump float;
 
 void main() {
@@ -1,22 +1,21 @@
-use crate::linear_algebra::Vec2;
+use crate::linear_algebra::{Mat4, Vec2};
 use crate::utils;
+use std::f32::consts::PI;
 use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext as Gl;
 
-const VERTEX_SHADER_SRC: &'static str = r#"
+const VERTEX_SHADER_SRC: &str = r#"
 attribute vec4 v_position;
-uniform float theta;
+uniform mat4 u_model;
 
 void main() {
-    float x = -sin(theta) * v_position.x + cos(theta) * v_position.y;
-    float y = sin(theta) * v_position.y + cos(theta) * v_position.x;
-    gl_Position = vec4(x, y, 0.0, 1.0);
+    gl_Position = u_model * v_position;
 }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
+const FRAGMENT_SHADER_SRC: &str = r#"
 precision mediump float;
 
 void main() {
@@ -24,19 +23,83 @@ void main() {
 }
 "#;
 
+// Angular velocity limits in radians per second, matching the slider
+const MAX_SPEED: f32 = 2.0 * PI;
+// Change in speed per keypress
+const SPEED_STEP: f32 = 0.25;
+// Rotation applied by a single step
+const STEP_ANGLE: f32 = PI / 16.0;
+// Longest frame time the animation advances by, so that the square doesn't
+// jump after the tab has been in the background
+const MAX_FRAME_SECONDS: f32 = 0.1;
+
 fn get_element(id: &str) -> Option<web_sys::Element> {
     web_sys::window()
         .and_then(|w| w.document())
         .and_then(|d| d.get_element_by_id(id))
 }
 
+fn parse_speed(value: &str) -> Result<f32, String> {
+    let speed: f32 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid speed {:?}", value))?;
+    if (0.0..=MAX_SPEED).contains(&speed) {
+        Ok(speed)
+    } else {
+        Err(format!("speed {} is outside 0 to {:.2}", speed, MAX_SPEED))
+    }
+}
+
+struct Rotation {
+    theta: f32,
+    // Radians per second
+    speed: f32,
+    clockwise: bool,
+    paused: bool,
+}
+
+impl Rotation {
+    fn direction(&self) -> f32 {
+        if self.clockwise {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    fn advance(&mut self, seconds: f32) {
+        if !self.paused {
+            self.theta += self.direction() * self.speed * seconds.min(MAX_FRAME_SECONDS);
+        }
+    }
+
+    // Pauses and turns by a fixed angle
+    fn step(&mut self) {
+        self.paused = true;
+        self.theta += self.direction() * STEP_ANGLE;
+    }
+
+    fn model(&self) -> Mat4 {
+        Mat4::rotation_z(self.theta)
+    }
+}
+
 pub fn run(context: Gl) -> Result<(), JsValue> {
     let toggle_btn = get_element("direction-toggle").ok_or("direction-toggle not found")?;
     let toggle_btn = toggle_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
 
+    let pause_btn = get_element("pause").ok_or("pause not found")?;
+    let pause_btn = pause_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let step_btn = get_element("step").ok_or("step not found")?;
+    let step_btn = step_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
     let speed_slider = get_element("speed-slider").ok_or("speed-slider not found")?;
     let speed_slider = speed_slider.dyn_into::<web_sys::HtmlInputElement>()?;
 
+    let speed_text = get_element("speed").ok_or("speed not found")?;
+
     let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
     let fragment_shader =
         utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
@@ -66,40 +129,121 @@ pub fn run(context: Gl) -> Result<(), JsValue> {
     context.vertex_attrib_pointer_with_i32(v_position, 2, Gl::FLOAT, false, 0, 0);
     context.enable_vertex_attrib_array(v_position);
 
-    let theta_loc = context
-        .get_uniform_location(&program, "theta")
-        .ok_or("failed to get location for theta")?;
+    let u_model = context
+        .get_uniform_location(&program, "u_model")
+        .ok_or("failed to get location for u_model")?;
+
+    let rotation = Rc::new(RefCell::new(Rotation {
+        theta: 0.0,
+        speed: parse_speed(&speed_slider.value()).unwrap_or(PI / 2.0),
+        clockwise: true,
+        paused: false,
+    }));
+
+    let speed_slider = Rc::new(speed_slider);
+    let pause_btn = Rc::new(pause_btn);
+    // Shows the state in the controls after it changes
+    let update_controls = {
+        let rotation = rotation.clone();
+        let speed_slider = speed_slider.clone();
+        let pause_btn = pause_btn.clone();
+        Rc::new(move || {
+            let rotation = rotation.borrow();
+            speed_slider.set_value(&rotation.speed.to_string());
+            speed_text.set_text_content(Some(&format!("{:.2} rad/s", rotation.speed)));
+            pause_btn.set_text_content(Some(if rotation.paused { "Resume" } else { "Pause" }));
+        })
+    };
+    update_controls();
 
-    let direction = Rc::new(RefCell::new(false));
     {
-        let direction = direction.clone();
+        let rotation = rotation.clone();
+        let update_controls = update_controls.clone();
         utils::add_event_listener(&toggle_btn, "click", move |_event| {
-            *direction.borrow_mut() ^= true;
+            rotation.borrow_mut().clockwise ^= true;
+            update_controls();
+        });
+    }
+    {
+        let rotation = rotation.clone();
+        let update_controls = update_controls.clone();
+        utils::add_event_listener(&pause_btn, "click", move |_event| {
+            rotation.borrow_mut().paused ^= true;
+            update_controls();
+        });
+    }
+    {
+        let rotation = rotation.clone();
+        let update_controls = update_controls.clone();
+        utils::add_event_listener(&step_btn, "click", move |_event| {
+            rotation.borrow_mut().step();
+            update_controls();
         });
     }
-
-    let delay = Rc::new(RefCell::new(50));
-    let speed_slider = Rc::new(speed_slider);
     {
         let speed_slider_ref = speed_slider.clone();
-        let delay = delay.clone();
+        let rotation = rotation.clone();
+        let update_controls = update_controls.clone();
         utils::add_event_listener(&speed_slider, "input", move |_event| {
-            *delay.borrow_mut() = 100 - &speed_slider_ref.value().parse::<i32>().unwrap();
-        })
+            // Bad input leaves the speed as it was
+            if let Ok(speed) = parse_speed(&speed_slider_ref.value()) {
+                rotation.borrow_mut().speed = speed;
+            }
+            update_controls();
+        });
+    }
+    {
+        let rotation = rotation.clone();
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("document not found")?;
+        utils::add_event_listener(&document, "keydown", move |event| {
+            let event = event.dyn_into::<web_sys::KeyboardEvent>().unwrap();
+            let on_control = match event.target() {
+                Some(t) => {
+                    t.dyn_ref::<web_sys::HtmlInputElement>().is_some()
+                        || t.dyn_ref::<web_sys::HtmlSelectElement>().is_some()
+                        || t.dyn_ref::<web_sys::HtmlButtonElement>().is_some()
+                }
+                None => false,
+            };
+            // A focused slider or button keeps its own keys
+            if on_control {
+                return;
+            }
+            {
+                let mut rotation = rotation.borrow_mut();
+                match event.key().as_str() {
+                    "d" | "D" => rotation.clockwise ^= true,
+                    "ArrowUp" | "+" | "=" => {
+                        rotation.speed = (rotation.speed + SPEED_STEP).min(MAX_SPEED)
+                    }
+                    "ArrowDown" | "-" => rotation.speed = (rotation.speed - SPEED_STEP).max(0.0),
+                    " " => rotation.paused ^= true,
+                    "s" | "S" | "." => rotation.step(),
+                    _ => return,
+                }
+            }
+            event.prevent_default();
+            update_controls();
+        });
     }
 
-    let mut theta = 0.0f32;
+    let mut last = js_sys::Date::now();
 
     context.clear_color(1.0, 1.0, 1.0, 1.0);
-    utils::render_loop_with_delay(
-        move || {
-            context.clear(Gl::COLOR_BUFFER_BIT);
-            theta += if *direction.borrow() { 0.1 } else { -0.1 };
-            context.uniform1f(Some(&theta_loc), theta);
-            context.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
-        },
-        delay,
-    );
+    utils::render_loop(move || {
+        let now = js_sys::Date::now();
+        let seconds = ((now - last) / 1000.0) as f32;
+        last = now;
+
+        let mut rotation = rotation.borrow_mut();
+        rotation.advance(seconds);
+
+        context.clear(Gl::COLOR_BUFFER_BIT);
+        context.uniform_matrix4fv_with_f32_array(Some(&u_model), false, &rotation.model().0);
+        context.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+    });
 
     Ok(())
 }
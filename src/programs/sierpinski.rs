@@ -1,33 +1,50 @@
-use crate::{linear_algebra::Vec2, utils};
+use crate::{
+    linear_algebra::Vec2,
+    utils,
+    wireframe::{self, Wireframe},
+};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext;
 
 const SUBDIVISIONS: usize = 5;
 
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
 pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
+    let wireframe_select = get_element("wireframe-mode").ok_or("wireframe-mode not found")?;
+    let wireframe_select = wireframe_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
     let vert_shader = utils::compile_shader(
         &context,
         WebGlRenderingContext::VERTEX_SHADER,
         r#"
         attribute vec4 vPosition;
+        attribute vec3 vBarycentric;
+
+        varying vec4 fColor;
+        varying vec3 fBarycentric;
 
         void main() {
+            fColor = vec4(1.0, 0.0, 0.0, 1.0);
+            fBarycentric = vBarycentric;
             gl_PointSize = 1.0;
             gl_Position = vPosition;
         }
         "#,
     )?;
 
+    if !wireframe::enable_derivatives(&context)? {
+        wireframe::disable_overlay(&wireframe_select)?;
+    }
     let frag_shader = utils::compile_shader(
         &context,
         WebGlRenderingContext::FRAGMENT_SHADER,
-        r#"
-        precision mediump float;
-
-        void main() {
-            gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
-        }
-    "#,
+        wireframe::FRAGMENT_SHADER_SRC,
     )?;
 
     let program = utils::link_program(&context, &vert_shader, &frag_shader)?;
@@ -59,19 +76,25 @@ pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
         vp => Ok(vp as u32),
     }?;
 
-    context.vertex_attrib_pointer_with_i32(
-        v_position,
-        2,
-        WebGlRenderingContext::FLOAT,
-        false,
-        0,
-        0,
-    );
     context.enable_vertex_attrib_array(v_position);
 
+    let mut wireframe = Wireframe::new(&context, &program, v_position, &[])?;
+    wireframe.set_triangles(&context, &points);
+
     context.clear_color(1.0, 1.0, 1.0, 1.0);
-    context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
-    context.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, points.len() as i32);
+
+    let draw = move |mode| {
+        context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+        wireframe.draw(&context, mode, &buffer);
+    };
+    draw(wireframe::Mode::parse(&wireframe_select.value()).unwrap_or(wireframe::Mode::Fill));
+
+    let wireframe_select_ref = wireframe_select.clone();
+    utils::add_event_listener(&wireframe_select, "change", move |_event| {
+        if let Some(mode) = wireframe::Mode::parse(&wireframe_select_ref.value()) {
+            draw(mode);
+        }
+    });
 
     Ok(())
 }
@@ -1,30 +1,28 @@
-use crate::{linear_algebra::Vec3, utils};
+use crate::{
+    linear_algebra::Vec3,
+    utils,
+    wireframe::{self, Wireframe},
+};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext as Gl;
 
 const SUBDIVISIONS: usize = 3;
 
-const VERTEX_SHADER_SRC: &'static str = r#"
+const VERTEX_SHADER_SRC: &str = r#"
 attribute vec4 vPosition;
 attribute vec4 vColor;
+attribute vec3 vBarycentric;
 varying vec4 fColor;
+varying vec3 fBarycentric;
 
 void main() {
     fColor = vColor;
+    fBarycentric = vBarycentric;
     gl_Position = vPosition;
 }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
-precision mediump float;
-
-varying vec4 fColor;
-
-void main() {
-    gl_FragColor = fColor;
-}
-"#;
-
 const BASE_COLORS: [Vec3; 4] = [
     Vec3(1.0, 0.0, 0.0),
     Vec3(0.0, 1.0, 0.0),
@@ -32,33 +30,31 @@ const BASE_COLORS: [Vec3; 4] = [
     Vec3(0.0, 0.0, 0.0),
 ];
 
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
 pub fn run(context: Gl) -> Result<(), JsValue> {
+    let wireframe_select = get_element("wireframe-mode").ok_or("wireframe-mode not found")?;
+    let wireframe_select = wireframe_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
     let vert_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
 
-    let frag_shader = utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+    if !wireframe::enable_derivatives(&context)? {
+        wireframe::disable_overlay(&wireframe_select)?;
+    }
+    let frag_shader = utils::compile_shader(
+        &context,
+        Gl::FRAGMENT_SHADER,
+        wireframe::FRAGMENT_SHADER_SRC,
+    )?;
 
     let program = utils::link_program(&context, &vert_shader, &frag_shader)?;
     context.use_program(Some(&program));
 
-    let vertices = [
-        Vec3(0.0000, 0.0000, -1.0000),
-        Vec3(0.0000, 0.9428, 0.3333),
-        Vec3(-0.8165, -0.4714, 0.3333),
-        Vec3(0.8165, -0.4714, 0.3333),
-    ];
-
-    let mut points = vec![];
-    let mut colors = vec![];
-
-    divide_tetra(
-        &mut points,
-        &mut colors,
-        &vertices[0],
-        &vertices[1],
-        &vertices[2],
-        &vertices[3],
-        SUBDIVISIONS,
-    );
+    let (points, colors) = gasket(SUBDIVISIONS);
 
     let c_buffer = context.create_buffer().ok_or("failed to create c_buffer")?;
     context.bind_buffer(Gl::ARRAY_BUFFER, Some(&c_buffer));
@@ -85,17 +81,56 @@ pub fn run(context: Gl) -> Result<(), JsValue> {
         -1 => Err("unable to get location for vPosition"),
         vp => Ok(vp as u32),
     }?;
-    context.vertex_attrib_pointer_with_i32(v_position, 3, Gl::FLOAT, false, 0, 0);
     context.enable_vertex_attrib_array(v_position);
 
+    let mut wireframe = Wireframe::new(&context, &program, v_position, &[v_color])?;
+    wireframe.set_triangles(&context, &points);
+
     context.enable(Gl::DEPTH_TEST);
     context.clear_color(1.0, 1.0, 1.0, 1.0);
-    context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
-    context.draw_arrays(Gl::TRIANGLES, 0, points.len() as i32);
+
+    let draw = move |mode| {
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        wireframe.draw(&context, mode, &v_buffer);
+    };
+    draw(wireframe::Mode::parse(&wireframe_select.value()).unwrap_or(wireframe::Mode::Fill));
+
+    let wireframe_select_ref = wireframe_select.clone();
+    utils::add_event_listener(&wireframe_select, "change", move |_event| {
+        if let Some(mode) = wireframe::Mode::parse(&wireframe_select_ref.value()) {
+            draw(mode);
+        }
+    });
 
     Ok(())
 }
 
+// Positions and colors of a TRIANGLES stream for the gasket after `count`
+// subdivisions
+pub fn gasket(count: usize) -> (Vec<Vec3>, Vec<Vec3>) {
+    let vertices = [
+        Vec3(0.0000, 0.0000, -1.0000),
+        Vec3(0.0000, 0.9428, 0.3333),
+        Vec3(-0.8165, -0.4714, 0.3333),
+        Vec3(0.8165, -0.4714, 0.3333),
+    ];
+
+    let mut points = vec![];
+    let mut colors = vec![];
+
+    divide_tetra(
+        &mut points,
+        &mut colors,
+        &vertices[0],
+        &vertices[1],
+        &vertices[2],
+        &vertices[3],
+        count,
+    );
+
+    (points, colors)
+}
+
 fn triangle(
     points: &mut Vec<Vec3>,
     colors: &mut Vec<Vec3>,
@@ -1,11 +1,11 @@
-use crate::{linear_algebra::Vec3, utils};
-use rand::prelude::*;
+use crate::{ifs, linear_algebra::Vec3, utils};
+use rand::distributions::Uniform;
 use wasm_bindgen::prelude::*;
 use web_sys::WebGlRenderingContext;
 
 const NUM_POINTS: usize = 500_000;
 
-const VERTEX_SHADER_SRC: &'static str = r#"
+const VERTEX_SHADER_SRC: &str = r#"
 attribute vec4 vPosition;
 varying vec4 fColor;
 
@@ -16,7 +16,7 @@ void main() {
 }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
+const FRAGMENT_SHADER_SRC: &str = r#"
 precision mediump float;
 
 varying vec4 fColor;
@@ -26,7 +26,26 @@ void main() {
 }
 "#;
 
-pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
+// The chaos game on a regular tetrahedron, starting at its center
+fn points(seed: Option<u32>, count: usize) -> Vec<Vec3> {
+    let vertices = [
+        Vec3(0.0000, 0.0000, -1.0000),
+        Vec3(0.0000, 0.9428, 0.3333),
+        Vec3(-0.8165, -0.4714, 0.3333),
+        Vec3(0.8165, -0.4714, 0.3333),
+    ];
+
+    let mut rng = ifs::seeded_rng(seed);
+    ifs::chaos_game(
+        &mut rng,
+        &Uniform::new(0, vertices.len()),
+        Vec3(0.0, 0.0, 0.0),
+        count,
+        |i, p| p.mix(&vertices[i], 0.5),
+    )
+}
+
+pub fn run(context: WebGlRenderingContext, seed: Option<u32>) -> Result<(), JsValue> {
     let vert_shader = utils::compile_shader(
         &context,
         WebGlRenderingContext::VERTEX_SHADER,
@@ -42,21 +61,7 @@ pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
     let program = utils::link_program(&context, &vert_shader, &frag_shader)?;
     context.use_program(Some(&program));
 
-    let vertices = [
-        Vec3(0.0000, 0.0000, -1.0000),
-        Vec3(0.0000, 0.9428, 0.3333),
-        Vec3(-0.8165, -0.4714, 0.3333),
-        Vec3(0.8165, -0.4714, 0.3333),
-    ];
-
-    let mut points = vec![Vec3(0.0, 0.0, 0.0)];
-
-    let mut rng = rand::thread_rng();
-
-    for i in 0..NUM_POINTS {
-        let vertex = vertices.choose(&mut rng).unwrap();
-        points.push(points[i].mix(vertex, 0.5));
-    }
+    let points = points(seed, NUM_POINTS);
 
     let buffer = context.create_buffer().ok_or("failed to create buffer")?;
     context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer));
@@ -88,3 +93,30 @@ pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_points_are_reproducible() {
+        assert_eq!(points(Some(7), 1000), points(Some(7), 1000));
+        assert_ne!(points(Some(7), 1000), points(Some(8), 1000));
+    }
+
+    #[test]
+    fn seeded_points_match_snapshot() {
+        assert_eq!(
+            points(Some(42), 6),
+            vec![
+                Vec3(0.0, 0.0, 0.0),
+                Vec3(0.40825, -0.2357, 0.16665),
+                Vec3(0.612375, -0.35355, 0.249975),
+                Vec3(-0.10206249, -0.412475, 0.29163748),
+                Vec3(-0.051031247, 0.2651625, 0.31246874),
+                Vec3(-0.025515623, 0.13258125, -0.34376562),
+                Vec3(-0.4210078, -0.16940936, -0.005232811),
+            ]
+        );
+    }
+}
@@ -1,11 +1,27 @@
-use crate::{linear_algebra::Vec2, utils};
-use rand::prelude::*;
+use crate::{
+    ifs::{self, Ifs},
+    linear_algebra::Vec2,
+    utils,
+};
 use wasm_bindgen::prelude::*;
 use web_sys::WebGlRenderingContext;
 
 const NUM_POINTS: usize = 100_000;
 
-pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
+// The chaos game on the triangle, starting inside it
+fn points(seed: Option<u32>, count: usize) -> Vec<Vec2> {
+    let vertices = [Vec2(-1.0, -1.0), Vec2(0.0, 1.0), Vec2(1.0, -1.0)];
+
+    let u = (vertices[0] + vertices[1]) * 0.5;
+    let v = (vertices[0] + vertices[2]) * 0.5;
+
+    let p = (u + v) * 0.5;
+
+    let mut rng = ifs::seeded_rng(seed);
+    Ifs::sierpinski_gasket(&vertices).chaos_game(&mut rng, p, count)
+}
+
+pub fn run(context: WebGlRenderingContext, seed: Option<u32>) -> Result<(), JsValue> {
     let vert_shader = utils::compile_shader(
         &context,
         WebGlRenderingContext::VERTEX_SHADER,
@@ -34,22 +50,7 @@ pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
     let program = utils::link_program(&context, &vert_shader, &frag_shader)?;
     context.use_program(Some(&program));
 
-    let vertices = [Vec2(-1.0, -1.0), Vec2(0.0, 1.0), Vec2(1.0, -1.0)];
-
-    let u = (vertices[0] + vertices[1]) * 0.5;
-    let v = (vertices[0] + vertices[2]) * 0.5;
-
-    let mut p = (u + v) * 0.5;
-    let mut points = vec![p];
-
-    let mut rng = rand::thread_rng();
-
-    for i in 0..NUM_POINTS {
-        let vertex = vertices.choose(&mut rng).unwrap();
-        p = points[i] + *vertex;
-        p = p * 0.5;
-        points.push(p);
-    }
+    let points = points(seed, NUM_POINTS);
 
     let buffer = context.create_buffer().ok_or("failed to create buffer")?;
     context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer));
@@ -81,3 +82,30 @@ pub fn run(context: WebGlRenderingContext) -> Result<(), JsValue> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_points_are_reproducible() {
+        assert_eq!(points(Some(7), 1000), points(Some(7), 1000));
+        assert_ne!(points(Some(7), 1000), points(Some(8), 1000));
+    }
+
+    #[test]
+    fn seeded_points_match_snapshot() {
+        assert_eq!(
+            points(Some(42), 6),
+            vec![
+                Vec2(-0.25, -0.5),
+                Vec2(0.375, -0.75),
+                Vec2(0.6875, -0.875),
+                Vec2(-0.15625, -0.9375),
+                Vec2(0.421875, -0.96875),
+                Vec2(0.2109375, 0.015625),
+                Vec2(0.10546875, 0.5078125),
+            ]
+        );
+    }
+}
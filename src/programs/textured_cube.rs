@@ -0,0 +1,267 @@
+use crate::{
+    geometry,
+    linear_algebra::{Mat4, Vec2, Vec3},
+    texture::{self, Filter, Options, Texture, Wrap},
+    utils,
+};
+use std::f32::consts::PI;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::WebGlRenderingContext as Gl;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 a_position;
+attribute vec2 a_uv;
+
+uniform mat4 u_model_view_projection;
+// Repeats of the texture across each face
+uniform float u_uv_scale;
+
+varying vec2 f_uv;
+
+void main() {
+    gl_Position = u_model_view_projection * a_position;
+    f_uv = a_uv * u_uv_scale;
+}
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+precision mediump float;
+
+uniform sampler2D u_texture;
+
+varying vec2 f_uv;
+
+void main() {
+    gl_FragColor = texture2D(u_texture, f_uv);
+}
+"#;
+
+const CHECKERBOARD_SIZE: u32 = 256;
+const CHECKERBOARD_SQUARES: u32 = 8;
+
+// Radians per second about each axis
+const SPIN: Vec3 = Vec3(0.3, 0.5, 0.0);
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+fn get_select(id: &str) -> Result<web_sys::HtmlSelectElement, JsValue> {
+    get_element(id)
+        .ok_or(format!("{} not found", id))?
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .map_err(JsValue::from)
+}
+
+fn checkerboard(context: &Gl, options: Options) -> Result<Texture, JsValue> {
+    let pixels = texture::checkerboard(
+        CHECKERBOARD_SIZE,
+        CHECKERBOARD_SQUARES,
+        [255, 255, 255, 255],
+        [40, 40, 160, 255],
+    );
+    Texture::from_rgba(
+        context,
+        CHECKERBOARD_SIZE,
+        CHECKERBOARD_SIZE,
+        &pixels,
+        options,
+    )
+}
+
+// Shows the texture's size and anything WebGL 1 made us change
+fn describe(texture: &Texture, requested: &Options) -> String {
+    let mut text = format!("{}x{}", texture.width, texture.height);
+    if texture.options != *requested {
+        text.push_str(", not a power of two so clamped without mipmaps");
+    } else if texture.options.min_filter.uses_mipmaps() {
+        text.push_str(", mipmapped");
+    }
+    text
+}
+
+pub fn run(context: Gl) -> Result<(), JsValue> {
+    let wrap_select = get_select("wrap")?;
+    let min_filter_select = get_select("min-filter")?;
+    let mag_filter_select = get_select("mag-filter")?;
+
+    let uv_scale = get_element("uv-scale").ok_or("uv-scale not found")?;
+    let uv_scale = uv_scale.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let url_input = get_element("image-url").ok_or("image-url not found")?;
+    let url_input = url_input.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let load_btn = get_element("load-image").ok_or("load-image not found")?;
+    let load_btn = load_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let checkerboard_btn = get_element("checkerboard").ok_or("checkerboard not found")?;
+    let checkerboard_btn = checkerboard_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let status = get_element("status").ok_or("status not found")?;
+
+    let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+    let fragment_shader =
+        utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
+    let program = utils::link_program(&context, &vertex_shader, &fragment_shader)?;
+    context.use_program(Some(&program));
+
+    let cube = geometry::cube();
+
+    let a_position_buffer = context
+        .create_buffer()
+        .ok_or("failed to create a_position buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&a_position_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec3::flatten(&cube.positions),
+        Gl::STATIC_DRAW,
+    );
+    let a_position = match context.get_attrib_location(&program, "a_position") {
+        -1 => Err("unable to get location for a_position"),
+        p => Ok(p as u32),
+    }?;
+    context.vertex_attrib_pointer_with_i32(a_position, 3, Gl::FLOAT, false, 0, 0);
+    context.enable_vertex_attrib_array(a_position);
+
+    let a_uv_buffer = context
+        .create_buffer()
+        .ok_or("failed to create a_uv buffer")?;
+    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&a_uv_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec2::flatten(&cube.uvs),
+        Gl::STATIC_DRAW,
+    );
+    let a_uv = match context.get_attrib_location(&program, "a_uv") {
+        -1 => Err("unable to get location for a_uv"),
+        p => Ok(p as u32),
+    }?;
+    context.vertex_attrib_pointer_with_i32(a_uv, 2, Gl::FLOAT, false, 0, 0);
+    context.enable_vertex_attrib_array(a_uv);
+
+    let index_buffer = context
+        .create_buffer()
+        .ok_or("failed to create index buffer")?;
+    context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&index_buffer));
+    context.buffer_data_with_array_buffer_view(
+        Gl::ELEMENT_ARRAY_BUFFER,
+        &js_sys::Uint16Array::from(&cube.indices[..]),
+        Gl::STATIC_DRAW,
+    );
+
+    let u_model_view_projection = context
+        .get_uniform_location(&program, "u_model_view_projection")
+        .ok_or("unable to get location for u_model_view_projection")?;
+    let u_uv_scale = context
+        .get_uniform_location(&program, "u_uv_scale")
+        .ok_or("unable to get location for u_uv_scale")?;
+    let u_texture = context
+        .get_uniform_location(&program, "u_texture")
+        .ok_or("unable to get location for u_texture")?;
+    context.uniform1i(Some(&u_texture), 0);
+
+    let options = Rc::new(move || {
+        let wrap = Wrap::parse(&wrap_select.value()).unwrap_or(Wrap::Repeat);
+        Options {
+            wrap_s: wrap,
+            wrap_t: wrap,
+            min_filter: Filter::parse(&min_filter_select.value())
+                .unwrap_or(Filter::LinearMipmapLinear),
+            mag_filter: Filter::parse(&mag_filter_select.value()).unwrap_or(Filter::Linear),
+        }
+    });
+
+    let context = Rc::new(context);
+    let texture = checkerboard(&context, options())?;
+    status.set_text_content(Some(&describe(&texture, &options())));
+    let texture = Rc::new(RefCell::new(texture));
+    let status = Rc::new(status);
+
+    // Swaps in a newly made texture, sampled with the current options
+    let replace = {
+        let context = context.clone();
+        let texture = texture.clone();
+        let status = status.clone();
+        let options = options.clone();
+        Rc::new(move |result: Result<Texture, JsValue>| match result {
+            Ok(new) => {
+                status.set_text_content(Some(&describe(&new, &options())));
+                std::mem::replace(&mut *texture.borrow_mut(), new).delete(&context);
+            }
+            Err(e) => status.set_text_content(e.as_string().as_deref()),
+        })
+    };
+
+    for id in &["wrap", "min-filter", "mag-filter"] {
+        let select = get_select(id)?;
+        let context = context.clone();
+        let texture = texture.clone();
+        let status = status.clone();
+        let options = options.clone();
+        utils::add_event_listener(&select, "change", move |_event| {
+            let mut texture = texture.borrow_mut();
+            texture.set_options(&context, options());
+            status.set_text_content(Some(&describe(&texture, &options())));
+        });
+    }
+    {
+        let context = context.clone();
+        let replace = replace.clone();
+        let options = options.clone();
+        utils::add_event_listener(&checkerboard_btn, "click", move |_event| {
+            replace(checkerboard(&context, options()));
+        });
+    }
+    {
+        let context = context.clone();
+        let status = status.clone();
+        utils::add_event_listener(&load_btn, "click", move |_event| {
+            status.set_text_content(Some("Loading..."));
+            let replace = replace.clone();
+            let result = Texture::load(
+                context.clone(),
+                &url_input.value(),
+                options(),
+                move |result| replace(result),
+            );
+            if let Err(e) = result {
+                status.set_text_content(e.as_string().as_deref());
+            }
+        });
+    }
+
+    let projection = Mat4::perspective(PI / 4.0, 1.0, 0.1, 100.0);
+    let view = Mat4::translation(Vec3(0.0, 0.0, -3.0));
+    let start = js_sys::Date::now();
+
+    context.enable(Gl::DEPTH_TEST);
+    context.enable(Gl::CULL_FACE);
+    context.clear_color(0.5, 0.5, 0.5, 1.0);
+    utils::render_loop(move || {
+        let seconds = ((js_sys::Date::now() - start) / 1000.0) as f32;
+        let model = Mat4::rotation_x(SPIN.0 * seconds)
+            * Mat4::rotation_y(SPIN.1 * seconds)
+            * Mat4::rotation_z(SPIN.2 * seconds);
+        context.uniform_matrix4fv_with_f32_array(
+            Some(&u_model_view_projection),
+            false,
+            &(projection * view * model).0,
+        );
+        context.uniform1f(Some(&u_uv_scale), uv_scale.value().parse().unwrap_or(1.0));
+
+        texture.borrow().bind(&context, 0);
+        context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        context.draw_elements_with_i32(
+            Gl::TRIANGLES,
+            cube.indices.len() as i32,
+            Gl::UNSIGNED_SHORT,
+            0,
+        );
+    });
+
+    Ok(())
+}
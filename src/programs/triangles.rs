@@ -1,26 +1,30 @@
 use crate::{
-    linear_algebra::{Vec2, Vec3},
-    utils,
+    linear_algebra::{Vec2, Vec3, Vec4},
+    mesh, utils,
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::Cell, cell::RefCell, rc::Rc};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext as Gl;
 
-const VERTEX_SHADER_SRC: &'static str = r#"
+const VERTEX_SHADER_SRC: &str = r#"
 attribute vec4 v_Position;
 attribute vec3 v_Color;
 
+uniform float u_PointSize;
+// Replaces the vertex colors when its alpha is not zero
+uniform vec4 u_Highlight;
+
 varying vec4 f_Color;
 
 void main() {
     gl_Position = v_Position;
-    gl_PointSize = 5.0;
-    f_Color = vec4(v_Color, 1.0);
+    gl_PointSize = u_PointSize;
+    f_Color = u_Highlight.a > 0.0 ? u_Highlight : vec4(v_Color, 1.0);
 }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
+const FRAGMENT_SHADER_SRC: &str = r#"
 precision mediump float;
 
 varying vec4 f_Color;
@@ -43,10 +47,113 @@ const COLORS: [Vec3; 8] = [
     Vec3(1.0, 1.0, 1.0), // white
 ];
 
+const HIGHLIGHT: Vec4 = Vec4(1.0, 1.0, 1.0, 1.0);
+
+// Clicks this close to a vertex, in pixels, grab it instead of adding one
+const GRAB_RADIUS: f32 = 8.0;
+
+// Frames each primitive stays highlighted for
+const FRAMES_PER_PRIMITIVE: usize = 45;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+fn parse_mode(mode: &str) -> Option<u32> {
+    match mode {
+        "points" => Some(Gl::POINTS),
+        "lines" => Some(Gl::LINES),
+        "line-strip" => Some(Gl::LINE_STRIP),
+        "line-loop" => Some(Gl::LINE_LOOP),
+        "triangles" => Some(Gl::TRIANGLES),
+        "triangle-strip" => Some(Gl::TRIANGLE_STRIP),
+        "triangle-fan" => Some(Gl::TRIANGLE_FAN),
+        _ => None,
+    }
+}
+
+struct Strip {
+    // Canvas pixels, with y down, as clicked
+    points: Vec<Vec2>,
+    dragging: Option<usize>,
+    // Set when `points` changed and the buffer and labels need updating
+    dirty: bool,
+}
+
+impl Strip {
+    fn grab(&self, p: Vec2) -> Option<usize> {
+        // Last drawn is on top
+        (0..self.points.len())
+            .rev()
+            .find(|&i| (self.points[i] - p).length() <= GRAB_RADIUS)
+    }
+
+    fn mouse_down(&mut self, p: Vec2) {
+        match self.grab(p) {
+            Some(i) => self.dragging = Some(i),
+            None if self.points.len() < MAX_VERTICES as usize => {
+                self.points.push(p);
+                self.dirty = true;
+            }
+            None => {}
+        }
+    }
+
+    fn mouse_move(&mut self, p: Vec2) {
+        if let Some(i) = self.dragging {
+            self.points[i] = p;
+            self.dirty = true;
+        }
+    }
+
+    fn mouse_up(&mut self) {
+        self.dragging = None;
+    }
+
+    fn clear(&mut self) {
+        self.points.clear();
+        self.dragging = None;
+        self.dirty = true;
+    }
+}
+
+// Numbers each vertex with an absolutely positioned span over the canvas
+fn update_labels(labels: &web_sys::Element, points: &[Vec2]) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("document not found")?;
+    labels.set_inner_html("");
+    for (i, p) in points.iter().enumerate() {
+        let label = document.create_element("span")?;
+        label.set_attribute(
+            "style",
+            &format!(
+                "position: absolute; left: {}px; top: {}px; pointer-events: none;",
+                p.0 + 6.0,
+                p.1 - 18.0
+            ),
+        )?;
+        label.set_text_content(Some(&i.to_string()));
+        labels.append_child(&label)?;
+    }
+    Ok(())
+}
+
 pub fn run(context: Gl) -> Result<(), JsValue> {
     let canvas = context.canvas().unwrap();
     let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
 
+    let mode_select = get_element("mode").ok_or("mode not found")?;
+    let mode_select = mode_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
+    let clear_btn = get_element("clear").ok_or("clear not found")?;
+    let clear_btn = clear_btn.dyn_into::<web_sys::HtmlButtonElement>()?;
+
+    let labels = get_element("labels").ok_or("labels not found")?;
+    let primitive_text = get_element("primitive").ok_or("primitive not found")?;
+
     let vertex_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
     let fragment_shader =
         utils::compile_shader(&context, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SRC)?;
@@ -65,11 +172,20 @@ pub fn run(context: Gl) -> Result<(), JsValue> {
     context.vertex_attrib_pointer_with_i32(v_position, 2, Gl::FLOAT, false, 0, 0);
     context.enable_vertex_attrib_array(v_position);
 
+    // A vertex's color only depends on its index, so they are all uploaded
+    // up front
+    let colors: Vec<Vec3> = (0..MAX_VERTICES as usize)
+        .map(|i| COLORS[i % COLORS.len()])
+        .collect();
     let v_color_buffer = context
         .create_buffer()
         .ok_or("failed to create v_Color buffer")?;
     context.bind_buffer(Gl::ARRAY_BUFFER, Some(&v_color_buffer));
-    context.buffer_data_with_i32(Gl::ARRAY_BUFFER, Vec3::SIZE * MAX_VERTICES, Gl::STATIC_DRAW);
+    context.buffer_data_with_array_buffer_view(
+        Gl::ARRAY_BUFFER,
+        &Vec3::flatten(&colors),
+        Gl::STATIC_DRAW,
+    );
     let v_color = match context.get_attrib_location(&program, "v_Color") {
         -1 => Err("unable to get location for v_Color"),
         p => Ok(p as u32),
@@ -77,55 +193,123 @@ pub fn run(context: Gl) -> Result<(), JsValue> {
     context.vertex_attrib_pointer_with_i32(v_color, 3, Gl::FLOAT, false, 0, 0);
     context.enable_vertex_attrib_array(v_color);
 
+    // Indices of the highlighted primitive
+    let index_buffer = context
+        .create_buffer()
+        .ok_or("failed to create index buffer")?;
+    context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(&index_buffer));
+
+    let u_point_size = context
+        .get_uniform_location(&program, "u_PointSize")
+        .ok_or("unable to get location for u_PointSize")?;
+    let u_highlight = context
+        .get_uniform_location(&program, "u_Highlight")
+        .ok_or("unable to get location for u_Highlight")?;
+
     context.clear_color(0.5, 0.5, 0.5, 1.0);
 
-    let vertices = Rc::new(RefCell::new(0));
-    let context = Rc::new(context);
+    let strip = Rc::new(RefCell::new(Strip {
+        points: vec![],
+        dragging: None,
+        dirty: false,
+    }));
     let canvas = Rc::new(canvas);
     {
-        let canvas_ref = canvas.clone();
-        let context = context.clone();
-        let vertices = vertices.clone();
+        let strip = strip.clone();
         utils::add_event_listener(&canvas, "mousedown", move |event| {
             let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
-            let mut vertices = vertices.borrow_mut();
-
-            if *vertices == MAX_VERTICES {
-                return;
-            }
+            let p = Vec2(event.offset_x() as f32, event.offset_y() as f32);
+            strip.borrow_mut().mouse_down(p);
+        });
+    }
+    {
+        let strip = strip.clone();
+        utils::add_event_listener(&canvas, "mousemove", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            let p = Vec2(event.offset_x() as f32, event.offset_y() as f32);
+            strip.borrow_mut().mouse_move(p);
+        });
+    }
+    for event in &["mouseup", "mouseleave"] {
+        let strip = strip.clone();
+        utils::add_event_listener(&canvas, event, move |_event| {
+            strip.borrow_mut().mouse_up();
+        });
+    }
+    {
+        let strip = strip.clone();
+        utils::add_event_listener(&clear_btn, "click", move |_event| {
+            strip.borrow_mut().clear();
+        });
+    }
 
+    let frame = Cell::new(0);
+    utils::render_loop(move || {
+        let mut strip = strip.borrow_mut();
+        if strip.dirty {
+            let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+            let ndc: Vec<Vec2> = strip
+                .points
+                .iter()
+                .map(|p| Vec2(-1.0 + 2.0 * p.0 / width, 1.0 - 2.0 * p.1 / height))
+                .collect();
             context.bind_buffer(Gl::ARRAY_BUFFER, Some(&v_position_buffer));
-            let t = Vec2(
-                -1.0 + (2.0 * event.offset_x() as f32) / canvas_ref.width() as f32,
-                -1.0 + (2.0 * (canvas_ref.height() as f32 - event.offset_y() as f32))
-                    / canvas_ref.height() as f32,
-            );
-            context.buffer_sub_data_with_i32_and_array_buffer_view(
-                Gl::ARRAY_BUFFER,
-                Vec2::SIZE * *vertices,
-                &Vec2::flatten(&[t]),
-            );
-
-            context.bind_buffer(Gl::ARRAY_BUFFER, Some(&v_color_buffer));
             context.buffer_sub_data_with_i32_and_array_buffer_view(
                 Gl::ARRAY_BUFFER,
-                Vec3::SIZE * *vertices,
-                &Vec3::flatten(&[COLORS[(*vertices as usize % COLORS.len())]]),
+                0,
+                &Vec2::flatten(&ndc),
             );
+            update_labels(&labels, &strip.points).unwrap();
+            strip.dirty = false;
+        }
 
-            *vertices += 1;
-        });
-    }
+        let mode = parse_mode(&mode_select.value()).unwrap_or(Gl::TRIANGLE_STRIP);
+        let count = strip.points.len();
 
-    utils::render_loop(move || {
         context.clear(Gl::COLOR_BUFFER_BIT);
+        context.uniform4f(Some(&u_highlight), 0.0, 0.0, 0.0, 0.0);
+        context.uniform1f(Some(&u_point_size), 5.0);
+        context.draw_arrays(mode, 0, count as i32);
+        // Vertices are always shown so that they can be dragged
+        context.uniform1f(Some(&u_point_size), 7.0);
+        context.draw_arrays(Gl::POINTS, 0, count as i32);
+
+        let primitives = mesh::primitive_indices(mode, count).unwrap_or_default();
+        if primitives.is_empty() {
+            primitive_text.set_text_content(Some("No primitives"));
+            return;
+        }
+        let current = frame.get() / FRAMES_PER_PRIMITIVE % primitives.len();
+        frame.set(frame.get() + 1);
 
-        match *vertices.borrow() {
-            0 => {}
-            1 => context.draw_arrays(Gl::POINTS, 0, 1),
-            2 => context.draw_arrays(Gl::LINES, 0, 2),
-            vertices => context.draw_arrays(Gl::TRIANGLE_STRIP, 0, vertices),
+        let indices: Vec<u16> = primitives[current].iter().map(|&i| i as u16).collect();
+        context.buffer_data_with_array_buffer_view(
+            Gl::ELEMENT_ARRAY_BUFFER,
+            &js_sys::Uint16Array::from(&indices[..]),
+            Gl::STREAM_DRAW,
+        );
+        context.uniform4f(
+            Some(&u_highlight),
+            HIGHLIGHT.0,
+            HIGHLIGHT.1,
+            HIGHLIGHT.2,
+            HIGHLIGHT.3,
+        );
+        context.uniform1f(Some(&u_point_size), 11.0);
+        let outline = match indices.len() {
+            1 => Gl::POINTS,
+            2 => Gl::LINES,
+            _ => Gl::LINE_LOOP,
         };
+        context.draw_elements_with_i32(outline, indices.len() as i32, Gl::UNSIGNED_SHORT, 0);
+
+        let vertices: Vec<String> = primitives[current].iter().map(|i| i.to_string()).collect();
+        primitive_text.set_text_content(Some(&format!(
+            "Primitive {} of {}: vertices {}",
+            current + 1,
+            primitives.len(),
+            vertices.join(", ")
+        )));
     });
     Ok(())
 }
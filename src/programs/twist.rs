@@ -1,73 +1,226 @@
-use crate::{linear_algebra::Vec2, utils};
+use crate::{
+    linear_algebra::Vec2,
+    utils,
+    wireframe::{self, Wireframe},
+};
+use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::WebGlRenderingContext as Gl;
 
-const SUBDIVISIONS: usize = 4;
+const MAX_SUBDIVISIONS: usize = 7;
+// Angular frequency of the animated twist, in radians per second
+const ANIMATION_SPEED: f32 = 1.5;
+
+const VERTEX_SHADER_SRC: &str = r#"
+attribute vec4 vPosition;
+attribute vec3 vBarycentric;
+
+uniform float twist;
+uniform vec2 center;
+uniform vec4 color;
+
+varying vec4 fColor;
+varying vec3 fBarycentric;
+
+void main() {
+    fColor = color;
+    fBarycentric = vBarycentric;
+    vec2 p = vPosition.xy - center;
+    float theta = twist * length(p);
+    gl_Position = vec4(
+        center.x + p.x * cos(theta) - p.y * sin(theta),
+        center.y + p.x * sin(theta) + p.y * cos(theta),
+        vPosition.zw
+    );
+}
+"#;
+
+fn get_element(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+}
+
+// The vertex shader's warp on the CPU: rotates `p` about `center` by an angle
+// proportional to its distance from `center`. The tests check that the shader
+// still computes it the same way.
+#[cfg(test)]
+fn warp(p: Vec2, center: Vec2, twist: f32) -> Vec2 {
+    let d = p - center;
+    let (sin, cos) = (twist * d.length()).sin_cos();
+    Vec2(
+        center.0 + d.0 * cos - d.1 * sin,
+        center.1 + d.0 * sin + d.1 * cos,
+    )
+}
+
+struct Params {
+    twist: f32,
+    center: Vec2,
+    subdivisions: usize,
+    animate: bool,
+    wireframe: wireframe::Mode,
+}
 
 pub fn run(context: Gl) -> Result<(), JsValue> {
-    let vert_shader = utils::compile_shader(
-        &context,
-        Gl::VERTEX_SHADER,
-        r#"
-        attribute vec4 vPosition;
-
-        void main() {
-            float x = vPosition.x;
-            float y = vPosition.y;
-            float theta = sqrt(x * x + y * y);
-            gl_Position = vec4(
-                x * cos(theta) - y * sin(theta),
-                x * sin(theta) + y * cos(theta),
-                vPosition.zw
-            );
-        }
-        "#,
-    )?;
+    let canvas = context.canvas().unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let twist_slider = get_element("twist-slider").ok_or("twist-slider not found")?;
+    let twist_slider = twist_slider.dyn_into::<web_sys::HtmlInputElement>()?;
 
+    let depth_slider = get_element("depth-slider").ok_or("depth-slider not found")?;
+    let depth_slider = depth_slider.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let animate_toggle = get_element("animate-toggle").ok_or("animate-toggle not found")?;
+    let animate_toggle = animate_toggle.dyn_into::<web_sys::HtmlInputElement>()?;
+
+    let wireframe_select = get_element("wireframe-mode").ok_or("wireframe-mode not found")?;
+    let wireframe_select = wireframe_select.dyn_into::<web_sys::HtmlSelectElement>()?;
+
+    let vert_shader = utils::compile_shader(&context, Gl::VERTEX_SHADER, VERTEX_SHADER_SRC)?;
+    if !wireframe::enable_derivatives(&context)? {
+        wireframe::disable_overlay(&wireframe_select)?;
+    }
     let frag_shader = utils::compile_shader(
         &context,
         Gl::FRAGMENT_SHADER,
-        r#"
-        precision mediump float;
-
-        void main() {
-            gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
-        }
-    "#,
+        wireframe::FRAGMENT_SHADER_SRC,
     )?;
-
     let program = utils::link_program(&context, &vert_shader, &frag_shader)?;
     context.use_program(Some(&program));
 
-    let mut points = vec![];
-    tesselate(
-        &mut points,
-        &Vec2(0.0, 0.75),
-        &Vec2(-0.75, -0.75),
-        &Vec2(0.75, -0.75),
-        SUBDIVISIONS,
-    );
-
-    let buffer = context.create_buffer().ok_or("failed to create buffer")?;
-    context.bind_buffer(Gl::ARRAY_BUFFER, Some(&buffer));
-
-    context.buffer_data_with_array_buffer_view(
-        Gl::ARRAY_BUFFER,
-        &Vec2::flatten(&points),
-        Gl::STATIC_DRAW,
-    );
+    let triangle_buffer = context
+        .create_buffer()
+        .ok_or("failed to create triangle buffer")?;
 
     let v_position = match context.get_attrib_location(&program, "vPosition") {
         -1 => Err("unable to get location for vPosition"),
         vp => Ok(vp as u32),
     }?;
-
-    context.vertex_attrib_pointer_with_i32(v_position, 2, Gl::FLOAT, false, 0, 0);
     context.enable_vertex_attrib_array(v_position);
 
+    let mut wireframe = Wireframe::new(&context, &program, v_position, &[])?;
+
+    let twist_loc = context
+        .get_uniform_location(&program, "twist")
+        .ok_or("failed to get location for twist")?;
+    let center_loc = context
+        .get_uniform_location(&program, "center")
+        .ok_or("failed to get location for center")?;
+    let color_loc = context
+        .get_uniform_location(&program, "color")
+        .ok_or("failed to get location for color")?;
+    context.uniform4f(Some(&color_loc), 1.0, 0.0, 0.0, 1.0);
+
+    let params = Rc::new(RefCell::new(Params {
+        twist: twist_slider.value().parse().unwrap_or(1.0),
+        center: Vec2(0.0, 0.0),
+        subdivisions: depth_slider
+            .value()
+            .parse::<usize>()
+            .unwrap_or(4)
+            .min(MAX_SUBDIVISIONS),
+        animate: animate_toggle.checked(),
+        wireframe: wireframe::Mode::parse(&wireframe_select.value())
+            .unwrap_or(wireframe::Mode::Fill),
+    }));
+
+    let twist_slider = Rc::new(twist_slider);
+    {
+        let twist_slider_ref = twist_slider.clone();
+        let params = params.clone();
+        utils::add_event_listener(&twist_slider, "input", move |_event| {
+            if let Ok(twist) = twist_slider_ref.value().parse() {
+                params.borrow_mut().twist = twist;
+            }
+        });
+    }
+    let depth_slider = Rc::new(depth_slider);
+    {
+        let depth_slider_ref = depth_slider.clone();
+        let params = params.clone();
+        utils::add_event_listener(&depth_slider, "input", move |_event| {
+            if let Ok(subdivisions) = depth_slider_ref.value().parse::<usize>() {
+                params.borrow_mut().subdivisions = subdivisions.min(MAX_SUBDIVISIONS);
+            }
+        });
+    }
+    let animate_toggle = Rc::new(animate_toggle);
+    {
+        let animate_toggle_ref = animate_toggle.clone();
+        let params = params.clone();
+        utils::add_event_listener(&animate_toggle, "change", move |_event| {
+            params.borrow_mut().animate = animate_toggle_ref.checked();
+        });
+    }
+    let wireframe_select = Rc::new(wireframe_select);
+    {
+        let wireframe_select_ref = wireframe_select.clone();
+        let params = params.clone();
+        utils::add_event_listener(&wireframe_select, "change", move |_event| {
+            if let Some(mode) = wireframe::Mode::parse(&wireframe_select_ref.value()) {
+                params.borrow_mut().wireframe = mode;
+            }
+        });
+    }
+    let canvas = Rc::new(canvas);
+    {
+        let canvas_ref = canvas.clone();
+        let params = params.clone();
+        utils::add_event_listener(&canvas, "mousedown", move |event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            params.borrow_mut().center = Vec2(
+                -1.0 + (2.0 * event.offset_x() as f32) / canvas_ref.width() as f32,
+                -1.0 + (2.0 * (canvas_ref.height() as f32 - event.offset_y() as f32))
+                    / canvas_ref.height() as f32,
+            );
+        });
+    }
+
     context.clear_color(1.0, 1.0, 1.0, 1.0);
-    context.clear(Gl::COLOR_BUFFER_BIT);
-    context.draw_arrays(Gl::TRIANGLES, 0, points.len() as i32);
+
+    let mut tesselated = None;
+    let start = js_sys::Date::now();
+
+    utils::render_loop(move || {
+        let params = params.borrow();
+
+        if tesselated != Some(params.subdivisions) {
+            let mut points = vec![];
+            tesselate(
+                &mut points,
+                &Vec2(0.0, 0.75),
+                &Vec2(-0.75, -0.75),
+                &Vec2(0.75, -0.75),
+                params.subdivisions,
+            );
+
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(&triangle_buffer));
+            context.buffer_data_with_array_buffer_view(
+                Gl::ARRAY_BUFFER,
+                &Vec2::flatten(&points),
+                Gl::STATIC_DRAW,
+            );
+            wireframe.set_triangles(&context, &points);
+
+            tesselated = Some(params.subdivisions);
+        }
+
+        let twist = if params.animate {
+            let seconds = ((js_sys::Date::now() - start) / 1000.0) as f32;
+            params.twist * (ANIMATION_SPEED * seconds).sin()
+        } else {
+            params.twist
+        };
+        context.uniform1f(Some(&twist_loc), twist);
+        context.uniform2f(Some(&center_loc), params.center.0, params.center.1);
+
+        context.clear(Gl::COLOR_BUFFER_BIT);
+
+        wireframe.draw(&context, params.wireframe, &triangle_buffer);
+    });
 
     Ok(())
 }
@@ -88,3 +241,55 @@ fn tesselate(points: &mut Vec<Vec2>, a: &Vec2, b: &Vec2, c: &Vec2, count: usize)
         tesselate(points, c, &ac, &bc, count - 1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn shader_warps_like_the_cpu() {
+        let source: Vec<&str> = VERTEX_SHADER_SRC.lines().map(str::trim).collect();
+        for line in &[
+            "vec2 p = vPosition.xy - center;",
+            "float theta = twist * length(p);",
+            "center.x + p.x * cos(theta) - p.y * sin(theta),",
+            "center.y + p.x * sin(theta) + p.y * cos(theta),",
+        ] {
+            assert!(source.contains(line), "shader no longer has {:?}", line);
+        }
+    }
+
+    #[test]
+    fn warp_keeps_center_fixed() {
+        let center = Vec2(0.25, -0.5);
+        assert_close(warp(center, center, 3.0), center);
+    }
+
+    #[test]
+    fn warp_preserves_distance_from_center() {
+        let center = Vec2(0.1, 0.2);
+        for &p in &[Vec2(1.0, 0.0), Vec2(-0.3, 0.7), Vec2(0.5, -0.9)] {
+            let before = (p - center).length();
+            let after = (warp(p, center, 2.5) - center).length();
+            assert!((before - after).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn warp_turns_by_twist_times_distance() {
+        let center = Vec2(0.5, 0.5);
+        let (twist, r) = (1.5f32, 0.4);
+        let expected = center + Vec2((twist * r).cos(), (twist * r).sin()) * r;
+        assert_close(warp(center + Vec2(r, 0.0), center, twist), expected);
+    }
+
+    #[test]
+    fn warp_without_twist_is_identity() {
+        let p = Vec2(-0.7, 0.3);
+        assert_close(warp(p, Vec2(0.0, 0.0), 0.0), p);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::texture::{Options, Texture};
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGlFramebuffer, WebGlRenderbuffer, WebGlRenderingContext as Gl};
+
+fn status_name(status: u32) -> &'static str {
+    match status {
+        Gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "FRAMEBUFFER_INCOMPLETE_ATTACHMENT",
+        Gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+            "FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT"
+        }
+        Gl::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => "FRAMEBUFFER_INCOMPLETE_DIMENSIONS",
+        Gl::FRAMEBUFFER_UNSUPPORTED => "FRAMEBUFFER_UNSUPPORTED",
+        _ => "unknown status",
+    }
+}
+
+// A framebuffer drawing into a color texture, and optionally a depth buffer,
+// so that a scene can be rendered once and then sampled like any texture
+pub struct RenderTarget {
+    framebuffer: WebGlFramebuffer,
+    pub color: Texture,
+    depth: Option<WebGlRenderbuffer>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTarget {
+    // WebGL 1 can't mipmap or repeat textures whose sides aren't powers of
+    // two, so `options` are downgraded for those sizes
+    pub fn new(
+        context: &Gl,
+        width: u32,
+        height: u32,
+        options: Options,
+        depth: bool,
+    ) -> Result<RenderTarget, JsValue> {
+        let color = Texture::empty(context, width, height, options)?;
+
+        let framebuffer = context
+            .create_framebuffer()
+            .ok_or("failed to create framebuffer")?;
+        context.bind_framebuffer(Gl::FRAMEBUFFER, Some(&framebuffer));
+        context.framebuffer_texture_2d(
+            Gl::FRAMEBUFFER,
+            Gl::COLOR_ATTACHMENT0,
+            Gl::TEXTURE_2D,
+            Some(&color.texture),
+            0,
+        );
+
+        let depth = if depth {
+            let renderbuffer = context
+                .create_renderbuffer()
+                .ok_or("failed to create depth renderbuffer")?;
+            context.bind_renderbuffer(Gl::RENDERBUFFER, Some(&renderbuffer));
+            context.renderbuffer_storage(
+                Gl::RENDERBUFFER,
+                Gl::DEPTH_COMPONENT16,
+                width as i32,
+                height as i32,
+            );
+            context.framebuffer_renderbuffer(
+                Gl::FRAMEBUFFER,
+                Gl::DEPTH_ATTACHMENT,
+                Gl::RENDERBUFFER,
+                Some(&renderbuffer),
+            );
+            context.bind_renderbuffer(Gl::RENDERBUFFER, None);
+            Some(renderbuffer)
+        } else {
+            None
+        };
+
+        let target = RenderTarget {
+            framebuffer,
+            color,
+            depth,
+            width,
+            height,
+        };
+        let status = context.check_framebuffer_status(Gl::FRAMEBUFFER);
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        if status != Gl::FRAMEBUFFER_COMPLETE {
+            target.delete(context);
+            return Err(JsValue::from_str(&format!(
+                "framebuffer is incomplete: {} ({:#x})",
+                status_name(status),
+                status
+            )));
+        }
+        Ok(target)
+    }
+
+    // Reallocates the attachments at the new size. Their contents are lost.
+    pub fn resize(&mut self, context: &Gl, width: u32, height: u32) -> Result<(), JsValue> {
+        if width == self.width && height == self.height {
+            return Ok(());
+        }
+        let resized = RenderTarget::new(
+            context,
+            width,
+            height,
+            self.color.options,
+            self.depth.is_some(),
+        )?;
+        std::mem::replace(self, resized).delete(context);
+        Ok(())
+    }
+
+    // Directs drawing into the target until `unbind`
+    pub fn bind(&self, context: &Gl) {
+        context.bind_framebuffer(Gl::FRAMEBUFFER, Some(&self.framebuffer));
+        context.viewport(0, 0, self.width as i32, self.height as i32);
+    }
+
+    // Goes back to drawing to the canvas, `width` by `height` pixels
+    pub fn unbind(context: &Gl, width: u32, height: u32) {
+        context.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        context.viewport(0, 0, width as i32, height as i32);
+    }
+
+    pub fn delete(self, context: &Gl) {
+        context.delete_framebuffer(Some(&self.framebuffer));
+        if let Some(depth) = &self.depth {
+            context.delete_renderbuffer(Some(depth));
+        }
+        self.color.delete(context);
+    }
+}
@@ -0,0 +1,452 @@
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlImageElement, WebGlRenderingContext as Gl, WebGlTexture};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+impl Wrap {
+    pub fn parse(wrap: &str) -> Option<Wrap> {
+        match wrap {
+            "repeat" => Some(Wrap::Repeat),
+            "mirrored-repeat" => Some(Wrap::MirroredRepeat),
+            "clamp-to-edge" => Some(Wrap::ClampToEdge),
+            _ => None,
+        }
+    }
+
+    fn gl(self) -> i32 {
+        (match self {
+            Wrap::Repeat => Gl::REPEAT,
+            Wrap::MirroredRepeat => Gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => Gl::CLAMP_TO_EDGE,
+        }) as i32
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+    // Minification only, these read from the mipmaps
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Option<Filter> {
+        match filter {
+            "nearest" => Some(Filter::Nearest),
+            "linear" => Some(Filter::Linear),
+            "nearest-mipmap-nearest" => Some(Filter::NearestMipmapNearest),
+            "linear-mipmap-nearest" => Some(Filter::LinearMipmapNearest),
+            "nearest-mipmap-linear" => Some(Filter::NearestMipmapLinear),
+            "linear-mipmap-linear" => Some(Filter::LinearMipmapLinear),
+            _ => None,
+        }
+    }
+
+    pub fn uses_mipmaps(self) -> bool {
+        !matches!(self, Filter::Nearest | Filter::Linear)
+    }
+
+    // The same filter reading only the base level
+    fn without_mipmaps(self) -> Filter {
+        match self {
+            Filter::Nearest | Filter::NearestMipmapNearest | Filter::NearestMipmapLinear => {
+                Filter::Nearest
+            }
+            _ => Filter::Linear,
+        }
+    }
+
+    fn gl(self) -> i32 {
+        (match self {
+            Filter::Nearest => Gl::NEAREST,
+            Filter::Linear => Gl::LINEAR,
+            Filter::NearestMipmapNearest => Gl::NEAREST_MIPMAP_NEAREST,
+            Filter::LinearMipmapNearest => Gl::LINEAR_MIPMAP_NEAREST,
+            Filter::NearestMipmapLinear => Gl::NEAREST_MIPMAP_LINEAR,
+            Filter::LinearMipmapLinear => Gl::LINEAR_MIPMAP_LINEAR,
+        }) as i32
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Options {
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            min_filter: Filter::LinearMipmapLinear,
+            mag_filter: Filter::Linear,
+        }
+    }
+}
+
+impl Options {
+    // WebGL 1 only samples textures whose sides aren't powers of two with
+    // clamped wrapping and no mipmaps, and renders them black otherwise, so
+    // those options are downgraded
+    pub fn supported(&self, width: u32, height: u32) -> Options {
+        if width.is_power_of_two() && height.is_power_of_two() {
+            return *self;
+        }
+        Options {
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+            min_filter: self.min_filter.without_mipmaps(),
+            mag_filter: self.mag_filter.without_mipmaps(),
+        }
+    }
+}
+
+// A `size` by `size` RGBA image of `squares` by `squares` alternating
+// squares, starting with `a` in the corner. When `size` isn't a multiple of
+// `squares` some squares are a pixel wider than others.
+pub fn checkerboard(size: u32, squares: u32, a: [u8; 4], b: [u8; 4]) -> Vec<u8> {
+    let squares = u64::from(squares.clamp(1, size.max(1)));
+    let square = move |i: u32| u64::from(i) * squares / u64::from(size);
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (square(x) + square(y)) % 2 == 0))
+        .flat_map(|even| if even { a } else { b })
+        .collect()
+}
+
+pub struct Texture {
+    pub texture: WebGlTexture,
+    pub width: u32,
+    pub height: u32,
+    // What the texture is sampled with, after `Options::supported`
+    pub options: Options,
+}
+
+impl Texture {
+    fn create(context: &Gl) -> Result<WebGlTexture, JsValue> {
+        let texture = context.create_texture().ok_or("failed to create texture")?;
+        context.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+        Ok(texture)
+    }
+
+    // Uninitialized storage, to be drawn into through a framebuffer. If
+    // `options` read mipmaps, call `generate_mipmaps` once there is an image.
+    pub fn empty(
+        context: &Gl,
+        width: u32,
+        height: u32,
+        options: Options,
+    ) -> Result<Texture, JsValue> {
+        let texture = Texture::create(context)?;
+        context.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            Gl::RGBA as i32,
+            width as i32,
+            height as i32,
+            0,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            None,
+        )?;
+        Ok(Texture::configure(context, texture, width, height, options))
+    }
+
+    // `pixels` holds `width * height` RGBA pixels, the top row first
+    pub fn from_rgba(
+        context: &Gl,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: Options,
+    ) -> Result<Texture, JsValue> {
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(JsValue::from_str(&format!(
+                "{} bytes is not {}x{} RGBA pixels",
+                pixels.len(),
+                width,
+                height
+            )));
+        }
+        let texture = Texture::create(context)?;
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 1);
+        context.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            Gl::RGBA as i32,
+            width as i32,
+            height as i32,
+            0,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(pixels),
+        )?;
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 0);
+        let texture = Texture::configure(context, texture, width, height, options);
+        texture.generate_mipmaps(context);
+        Ok(texture)
+    }
+
+    // Replaces the whole image with `pixels`, laid out as for `from_rgba`
+    pub fn update(&self, context: &Gl, pixels: &[u8]) -> Result<(), JsValue> {
+        if pixels.len() != (self.width * self.height * 4) as usize {
+            return Err(JsValue::from_str(&format!(
+                "{} bytes is not {}x{} RGBA pixels",
+                pixels.len(),
+                self.width,
+                self.height
+            )));
+        }
+        context.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 1);
+        context.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(pixels),
+        )?;
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 0);
+        self.generate_mipmaps(context);
+        Ok(())
+    }
+
+    pub fn from_image(
+        context: &Gl,
+        image: &HtmlImageElement,
+        options: Options,
+    ) -> Result<Texture, JsValue> {
+        let texture = Texture::create(context)?;
+        // Images are stored top row first, textures bottom row first
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 1);
+        context.tex_image_2d_with_u32_and_u32_and_image(
+            Gl::TEXTURE_2D,
+            0,
+            Gl::RGBA as i32,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            image,
+        )?;
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 0);
+        let texture = Texture::configure(
+            context,
+            texture,
+            image.natural_width(),
+            image.natural_height(),
+            options,
+        );
+        texture.generate_mipmaps(context);
+        Ok(texture)
+    }
+
+    // Fetches the image at `url` and passes the texture made from it, or the
+    // reason it couldn't be loaded, to `closure`
+    pub fn load<F>(context: Rc<Gl>, url: &str, options: Options, closure: F) -> Result<(), JsValue>
+    where
+        F: 'static + FnOnce(Result<Texture, JsValue>),
+    {
+        let image = Rc::new(HtmlImageElement::new()?);
+        // Cross-origin images can only be uploaded if the server allows it
+        image.set_cross_origin(Some("anonymous"));
+
+        let closure = Rc::new(RefCell::new(Some(closure)));
+        let onload = {
+            let image = image.clone();
+            let closure = closure.clone();
+            Closure::once(move || {
+                if let Some(closure) = closure.borrow_mut().take() {
+                    closure(Texture::from_image(&context, &image, options));
+                }
+            })
+        };
+        let url_ref = String::from(url);
+        let onerror = Closure::once(move || {
+            if let Some(closure) = closure.borrow_mut().take() {
+                closure(Err(JsValue::from_str(&format!(
+                    "failed to load {}",
+                    url_ref
+                ))));
+            }
+        });
+        image.set_onload(Some(onload.as_ref().unchecked_ref()));
+        image.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+        onload.forget();
+        onerror.forget();
+
+        image.set_src(url);
+        Ok(())
+    }
+
+    fn configure(
+        context: &Gl,
+        texture: WebGlTexture,
+        width: u32,
+        height: u32,
+        options: Options,
+    ) -> Texture {
+        let mut texture = Texture {
+            texture,
+            width,
+            height,
+            options,
+        };
+        texture.set_parameters(context, options);
+        texture
+    }
+
+    // Changes how the texture is sampled, remaking the mipmaps from the
+    // current image if the new filter reads them
+    pub fn set_options(&mut self, context: &Gl, options: Options) {
+        self.set_parameters(context, options);
+        self.generate_mipmaps(context);
+    }
+
+    // Brings the mipmaps in step with the base image, when they are used.
+    // Regenerating is cheap next to the rest of a frame.
+    pub fn generate_mipmaps(&self, context: &Gl) {
+        if self.options.min_filter.uses_mipmaps() {
+            context.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+            context.generate_mipmap(Gl::TEXTURE_2D);
+        }
+    }
+
+    fn set_parameters(&mut self, context: &Gl, options: Options) {
+        let options = options.supported(self.width, self.height);
+        context.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+        context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_S, options.wrap_s.gl());
+        context.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_T, options.wrap_t.gl());
+        context.tex_parameteri(
+            Gl::TEXTURE_2D,
+            Gl::TEXTURE_MIN_FILTER,
+            options.min_filter.gl(),
+        );
+        context.tex_parameteri(
+            Gl::TEXTURE_2D,
+            Gl::TEXTURE_MAG_FILTER,
+            options.mag_filter.without_mipmaps().gl(),
+        );
+        self.options = options;
+    }
+
+    pub fn bind(&self, context: &Gl, unit: u32) {
+        context.active_texture(Gl::TEXTURE0 + unit);
+        context.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+    }
+
+    pub fn delete(self, context: &Gl) {
+        context.delete_texture(Some(&self.texture));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: [u8; 4] = [255, 255, 255, 255];
+    const B: [u8; 4] = [0, 0, 0, 255];
+
+    fn pixel(pixels: &[u8], size: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * size + x) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    }
+
+    #[test]
+    fn checkerboard_fills_every_pixel() {
+        assert_eq!(checkerboard(8, 4, A, B).len(), 8 * 8 * 4);
+        assert_eq!(checkerboard(7, 3, A, B).len(), 7 * 7 * 4);
+    }
+
+    #[test]
+    fn checkerboard_alternates_squares() {
+        let pixels = checkerboard(8, 4, A, B);
+        assert_eq!(pixel(&pixels, 8, 0, 0), A);
+        assert_eq!(pixel(&pixels, 8, 1, 1), A);
+        assert_eq!(pixel(&pixels, 8, 2, 0), B);
+        assert_eq!(pixel(&pixels, 8, 0, 2), B);
+        assert_eq!(pixel(&pixels, 8, 2, 2), A);
+        assert_eq!(pixel(&pixels, 8, 7, 0), B);
+        assert_eq!(pixel(&pixels, 8, 7, 7), A);
+    }
+
+    #[test]
+    fn checkerboard_has_the_requested_squares() {
+        for &(size, squares) in &[(7, 3), (10, 4), (8, 8), (5, 2)] {
+            let pixels = checkerboard(size, squares, A, B);
+            let row: Vec<[u8; 4]> = (0..size).map(|x| pixel(&pixels, size, x, 0)).collect();
+            let changes = row.windows(2).filter(|w| w[0] != w[1]).count();
+            assert_eq!(changes as u32 + 1, squares, "{} in {}", squares, size);
+        }
+        // More squares than pixels gives one per pixel
+        let pixels = checkerboard(4, 9, A, B);
+        assert_eq!(pixel(&pixels, 4, 3, 0), B);
+        assert_eq!(pixel(&pixels, 4, 3, 3), A);
+    }
+
+    #[test]
+    fn checkerboard_with_no_squares_is_one_color() {
+        let pixels = checkerboard(4, 0, A, B);
+        assert_eq!(pixels.len(), 4 * 4 * 4);
+        assert!(pixels.chunks(4).all(|p| p == A));
+    }
+
+    #[test]
+    fn power_of_two_textures_keep_their_options() {
+        let options = Options::default();
+        assert_eq!(options.supported(256, 64), options);
+        assert_eq!(options.supported(1, 1), options);
+    }
+
+    #[test]
+    fn other_sizes_are_clamped_without_mipmaps() {
+        let options = Options {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::MirroredRepeat,
+            min_filter: Filter::LinearMipmapNearest,
+            mag_filter: Filter::Nearest,
+        };
+        for &(width, height) in &[(300, 256), (256, 300), (3, 5)] {
+            assert_eq!(
+                options.supported(width, height),
+                Options {
+                    wrap_s: Wrap::ClampToEdge,
+                    wrap_t: Wrap::ClampToEdge,
+                    min_filter: Filter::Linear,
+                    mag_filter: Filter::Nearest,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn mipmap_filters_fall_back_to_their_base_filter() {
+        for &(filter, base) in &[
+            (Filter::Nearest, Filter::Nearest),
+            (Filter::Linear, Filter::Linear),
+            (Filter::NearestMipmapNearest, Filter::Nearest),
+            (Filter::NearestMipmapLinear, Filter::Nearest),
+            (Filter::LinearMipmapNearest, Filter::Linear),
+            (Filter::LinearMipmapLinear, Filter::Linear),
+        ] {
+            assert_eq!(filter.without_mipmaps(), base);
+            assert!(!base.uses_mipmaps());
+            assert_eq!(filter.uses_mipmaps(), filter != base);
+        }
+    }
+}
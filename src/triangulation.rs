@@ -0,0 +1,283 @@
+use crate::linear_algebra::Vec2;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+// Twice the signed area, positive for counter-clockwise polygons
+fn doubled_area(polygon: &[Vec2]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| polygon[i].cross(&polygon[(i + 1) % n]))
+        .sum()
+}
+
+#[cfg(test)]
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    doubled_area(polygon) / 2.0
+}
+
+pub fn winding(polygon: &[Vec2]) -> Winding {
+    if doubled_area(polygon) < 0.0 {
+        Winding::Clockwise
+    } else {
+        Winding::CounterClockwise
+    }
+}
+
+// Orientation of the turn a -> b -> c: positive when counter-clockwise
+fn orientation(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).cross(&(c - b))
+}
+
+fn on_segment(p: Vec2, a: Vec2, b: Vec2) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+// True if the closed segments ab and cd share at least one point
+pub fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let o1 = orientation(a, b, c);
+    let o2 = orientation(a, b, d);
+    let o3 = orientation(c, d, a);
+    let o4 = orientation(c, d, b);
+
+    if ((o1 > 0.0 && o2 < 0.0) || (o1 < 0.0 && o2 > 0.0))
+        && ((o3 > 0.0 && o4 < 0.0) || (o3 < 0.0 && o4 > 0.0))
+    {
+        return true;
+    }
+
+    (o1 == 0.0 && on_segment(c, a, b))
+        || (o2 == 0.0 && on_segment(d, a, b))
+        || (o3 == 0.0 && on_segment(a, c, d))
+        || (o4 == 0.0 && on_segment(b, c, d))
+}
+
+// Returns the first pair of non-adjacent edges (by starting vertex) that touch
+// or cross.
+pub fn self_intersection(polygon: &[Vec2]) -> Option<(usize, usize)> {
+    let n = polygon.len();
+    if n < 4 {
+        return None;
+    }
+
+    for i in 0..n {
+        for j in i + 2..n {
+            // The first and last edges share vertex 0
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            if segments_intersect(
+                polygon[i],
+                polygon[(i + 1) % n],
+                polygon[j],
+                polygon[(j + 1) % n],
+            ) {
+                return Some((i, j));
+            }
+        }
+    }
+
+    None
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    orientation(a, b, p) >= 0.0 && orientation(b, c, p) >= 0.0 && orientation(c, a, p) >= 0.0
+}
+
+// Ear clipping for simple polygons of either winding. Returns counter-clockwise
+// index triples into `polygon`, ready to be drawn as TRIANGLES. Repeated
+// consecutive vertices are ignored.
+pub fn triangulate(polygon: &[Vec2]) -> Result<Vec<[usize; 3]>, String> {
+    let n = polygon.len();
+    let unique: Vec<usize> = (0..n)
+        .filter(|&i| polygon[i] != polygon[(i + 1) % n])
+        .collect();
+    let points: Vec<Vec2> = unique.iter().map(|&i| polygon[i]).collect();
+
+    Ok(clip_ears(&points)?
+        .into_iter()
+        .map(|[a, b, c]| [unique[a], unique[b], unique[c]])
+        .collect())
+}
+
+fn clip_ears(polygon: &[Vec2]) -> Result<Vec<[usize; 3]>, String> {
+    if polygon.len() < 3 {
+        return Err(String::from("a polygon needs at least 3 vertices"));
+    }
+    if let Some((i, j)) = self_intersection(polygon) {
+        return Err(format!(
+            "polygon is self-intersecting: edge {} crosses edge {}",
+            i, j
+        ));
+    }
+    if doubled_area(polygon) == 0.0 {
+        return Err(String::from("polygon has no area"));
+    }
+
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    if winding(polygon) == Winding::Clockwise {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            )
+        };
+
+        let ear = (0..n).find(|&i| {
+            let (prev, curr, next) = corner(i);
+            let (a, b, c) = (polygon[prev], polygon[curr], polygon[next]);
+            orientation(a, b, c) > 0.0
+                && remaining.iter().all(|&other| {
+                    let p = polygon[other];
+                    other == prev
+                        || other == curr
+                        || other == next
+                        || p == a
+                        || p == b
+                        || p == c
+                        || !in_triangle(p, a, b, c)
+                })
+        });
+
+        match ear {
+            Some(i) => {
+                let (prev, curr, next) = corner(i);
+                triangles.push([prev, curr, next]);
+                remaining.remove(i);
+            }
+            None => {
+                // Only collinear corners are left to clip; they add no area
+                let flat = (0..n)
+                    .find(|&i| {
+                        let (prev, curr, next) = corner(i);
+                        orientation(polygon[prev], polygon[curr], polygon[next]) == 0.0
+                    })
+                    .ok_or("polygon could not be triangulated")?;
+                remaining.remove(flat);
+            }
+        }
+    }
+
+    let (a, b, c) = (remaining[0], remaining[1], remaining[2]);
+    if orientation(polygon[a], polygon[b], polygon[c]) != 0.0 {
+        triangles.push([a, b, c]);
+    }
+
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coordinates: &[(f32, f32)]) -> Vec<Vec2> {
+        coordinates.iter().map(|&(x, y)| Vec2(x, y)).collect()
+    }
+
+    // Checks that every triangle is counter-clockwise and that together they
+    // cover the polygon's area
+    fn assert_covers(polygon: &[Vec2], triangles: &[[usize; 3]]) {
+        let mut total = 0.0;
+        for &[a, b, c] in triangles {
+            let area = signed_area(&[polygon[a], polygon[b], polygon[c]]);
+            assert!(area > 0.0, "{:?} is not counter-clockwise", [a, b, c]);
+            total += area;
+        }
+        assert!((total - signed_area(polygon).abs()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn convex_quad_gives_two_triangles() {
+        let quad = points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 1.0)]);
+        let triangles = triangulate(&quad).unwrap();
+        assert_eq!(triangles.len(), 2);
+        assert_covers(&quad, &triangles);
+    }
+
+    #[test]
+    fn concave_polygons_give_n_minus_two_triangles() {
+        let l_shape = points(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let arrow = points(&[(0.0, 0.0), (2.0, 1.0), (0.0, 2.0), (0.5, 1.0)]);
+        for polygon in &[l_shape, arrow] {
+            let triangles = triangulate(polygon).unwrap();
+            assert_eq!(triangles.len(), polygon.len() - 2);
+            assert_covers(polygon, &triangles);
+        }
+    }
+
+    #[test]
+    fn clockwise_input_gives_counter_clockwise_triangles() {
+        let mut l_shape = points(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        l_shape.reverse();
+        assert_eq!(winding(&l_shape), Winding::Clockwise);
+        assert_eq!(signed_area(&l_shape), -3.0);
+
+        let triangles = triangulate(&l_shape).unwrap();
+        assert_eq!(triangles.len(), 4);
+        assert_covers(&l_shape, &triangles);
+    }
+
+    #[test]
+    fn collinear_and_repeated_vertices_add_no_triangles() {
+        let square = points(&[
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let triangles = triangulate(&square).unwrap();
+        assert!(triangles.len() <= 3);
+        assert!(triangles.iter().all(|t| !t.contains(&3)));
+        assert_covers(&square, &triangles);
+    }
+
+    #[test]
+    fn degenerate_polygons_are_rejected() {
+        assert!(triangulate(&points(&[(0.0, 0.0), (1.0, 1.0)])).is_err());
+        assert!(triangulate(&points(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)])).is_err());
+    }
+
+    #[test]
+    fn bow_tie_is_self_intersecting() {
+        let bow_tie = points(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        assert_eq!(self_intersection(&bow_tie), Some((0, 2)));
+        assert!(triangulate(&bow_tie).is_err());
+
+        let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(self_intersection(&square), None);
+    }
+
+    #[test]
+    fn touching_segments_intersect() {
+        let (a, b) = (Vec2(0.0, 0.0), Vec2(2.0, 0.0));
+        assert!(segments_intersect(a, b, Vec2(1.0, 0.0), Vec2(1.0, 1.0)));
+        assert!(!segments_intersect(a, b, Vec2(3.0, 0.0), Vec2(4.0, 0.0)));
+        assert!(!segments_intersect(a, b, Vec2(0.0, 1.0), Vec2(2.0, 1.0)));
+    }
+}
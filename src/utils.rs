@@ -3,6 +3,9 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{Event, EventTarget, WebGlProgram, WebGlRenderingContext, WebGlShader};
 
+// How long a download's object URL is kept before it is released
+const REVOKE_DELAY_MS: i32 = 10_000;
+
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -78,24 +81,6 @@ where
     request_animation_frame(g.borrow().as_ref().unwrap());
 }
 
-pub fn render_loop_with_delay<F>(mut closure: F, delay: Rc<RefCell<i32>>)
-where
-    F: 'static + FnMut(),
-{
-    let f = Rc::new(RefCell::new(None));
-    let g = f.clone();
-    *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        closure();
-        let h = f.clone();
-        set_timeout(
-            move || {
-                request_animation_frame(h.borrow().as_ref().unwrap());
-            },
-            *delay.borrow(),
-        );
-    }) as Box<dyn FnMut()>));
-    request_animation_frame(g.borrow().as_ref().unwrap());
-}
 fn request_animation_frame(f: &Closure<dyn FnMut()>) {
     web_sys::window()
         .unwrap()
@@ -103,18 +88,6 @@ fn request_animation_frame(f: &Closure<dyn FnMut()>) {
         .expect("should register `requestAnimationFrame` OK");
 }
 
-fn set_timeout<F>(closure: F, timeout: i32)
-where
-    F: 'static + FnMut(),
-{
-    let f = Closure::wrap(Box::new(closure) as Box<dyn FnMut()>);
-    web_sys::window()
-        .unwrap()
-        .set_timeout_with_callback_and_timeout_and_arguments_0(f.as_ref().unchecked_ref(), timeout)
-        .unwrap();
-    f.forget();
-}
-
 pub fn add_event_listener<F>(target: &EventTarget, event: &str, closure: F)
 where
     F: 'static + FnMut(Event),
@@ -125,3 +98,95 @@ where
         .unwrap();
     f.forget();
 }
+
+// Readable text for an error from a browser API, which is usually a JS
+// `Error` rather than a string
+pub fn error_text(error: &JsValue) -> String {
+    error
+        .as_string()
+        .or_else(|| {
+            error
+                .dyn_ref::<js_sys::Error>()
+                .map(|e| String::from(e.message()))
+        })
+        .unwrap_or_else(|| format!("{:?}", error))
+}
+
+// Shows an error in `status`, or clears it once an action succeeds
+pub fn report(status: &web_sys::Element, result: Result<(), JsValue>) {
+    match result {
+        Ok(()) => status.set_text_content(None),
+        Err(error) => status.set_text_content(Some(&error_text(&error))),
+    }
+}
+
+pub fn download(filename: &str, contents: &str, mime_type: &str) -> Result<(), JsValue> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    download_blob(filename, &blob)
+}
+
+pub fn download_blob(filename: &str, blob: &web_sys::Blob) -> Result<(), JsValue> {
+    let url = web_sys::Url::create_object_url_with_blob(blob)?;
+
+    let anchor = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("document not found")?
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    // Some browsers start the download after `click` returns, so the URL has
+    // to stay valid for a while
+    let revoke = Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    web_sys::window()
+        .ok_or("window not found")?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )?;
+    Ok(())
+}
+
+// Reads `file` as text, passing the text or the error to `closure`
+pub fn read_file<F>(file: &web_sys::File, closure: F)
+where
+    F: 'static + FnMut(Result<String, JsValue>),
+{
+    let closure = Rc::new(RefCell::new(closure));
+    let on_text = {
+        let closure = closure.clone();
+        Closure::wrap(Box::new(move |text: JsValue| {
+            (closure.borrow_mut())(text.as_string().ok_or(text))
+        }) as Box<dyn FnMut(JsValue)>)
+    };
+    let on_error = Closure::wrap(
+        Box::new(move |error: JsValue| (closure.borrow_mut())(Err(error)))
+            as Box<dyn FnMut(JsValue)>,
+    );
+    let _ = file.text().then2(&on_text, &on_error);
+    on_text.forget();
+    on_error.forget();
+}
+
+pub fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+// Encodes the canvas as a PNG, passing the Blob, or null on failure, to
+// `closure`. The image is taken from what has been drawn so far this frame.
+pub fn canvas_to_png<F>(canvas: &web_sys::HtmlCanvasElement, closure: F) -> Result<(), JsValue>
+where
+    F: 'static + FnMut(JsValue),
+{
+    let f = Closure::wrap(Box::new(closure) as Box<dyn FnMut(JsValue)>);
+    canvas.to_blob(f.as_ref().unchecked_ref())?;
+    f.forget();
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use crate::{
+    linear_algebra::Vec3,
+    mesh::{self, Vertex},
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    HtmlOptionElement, HtmlSelectElement, WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl,
+    WebGlUniformLocation,
+};
+
+// Width of the barycentric overlay lines, in pixels
+const WIRE_WIDTH: f32 = 1.5;
+const WIRE_COLOR: Vec3 = Vec3(0.0, 0.0, 0.0);
+
+// Shared by every program with a wireframe mode. The vertex shader has to pass
+// the fill color in `fColor` and the `vBarycentric` attribute in
+// `fBarycentric`. Call `enable_derivatives` before compiling it, the
+// Fill + Wire overlay is left out when the extension is missing.
+pub const FRAGMENT_SHADER_SRC: &str = r#"
+#ifdef GL_OES_standard_derivatives
+#extension GL_OES_standard_derivatives : enable
+#endif
+precision mediump float;
+
+uniform vec3 wireColor;
+uniform float wireWidth;
+uniform bool linePass;
+
+varying vec4 fColor;
+varying vec3 fBarycentric;
+
+void main() {
+    if (linePass) {
+        gl_FragColor = vec4(wireColor, 1.0);
+        return;
+    }
+    gl_FragColor = fColor;
+#ifdef GL_OES_standard_derivatives
+    if (wireWidth > 0.0) {
+        vec3 edge = smoothstep(vec3(0.0), fwidth(fBarycentric) * wireWidth, fBarycentric);
+        float wire = 1.0 - min(min(edge.x, edge.y), edge.z);
+        gl_FragColor = mix(fColor, vec4(wireColor, 1.0), wire);
+    }
+#endif
+}
+"#;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Mode {
+    Fill,
+    Wire,
+    FillAndWire,
+}
+
+impl Mode {
+    pub fn parse(mode: &str) -> Option<Mode> {
+        match mode {
+            "fill" => Some(Mode::Fill),
+            "wire" => Some(Mode::Wire),
+            "fill+wire" => Some(Mode::FillAndWire),
+            _ => None,
+        }
+    }
+}
+
+// Whether OES_standard_derivatives could be enabled, which the Fill + Wire
+// overlay needs. Wire alone draws the edge list and works without it.
+pub fn enable_derivatives(context: &Gl) -> Result<bool, JsValue> {
+    Ok(context.get_extension("OES_standard_derivatives")?.is_some())
+}
+
+// Greys out Fill + Wire in a mode select, switching to Fill if it was chosen
+pub fn disable_overlay(select: &HtmlSelectElement) -> Result<(), JsValue> {
+    if let Some(option) = select.query_selector("option[value='fill+wire']")? {
+        option.dyn_into::<HtmlOptionElement>()?.set_disabled(true);
+    }
+    if select.value() == "fill+wire" {
+        select.set_value("fill");
+    }
+    Ok(())
+}
+
+pub struct Wireframe {
+    barycentric_buffer: WebGlBuffer,
+    edge_buffer: WebGlBuffer,
+    triangle_count: i32,
+    edge_count: i32,
+    components: i32,
+    v_position: u32,
+    // Both unused, and so inactive, in a shader compiled without the overlay
+    v_barycentric: Option<u32>,
+    wire_width_loc: Option<WebGlUniformLocation>,
+    // Other per-vertex attributes, disabled while drawing the edge list
+    attributes: Vec<u32>,
+    wire_color_loc: WebGlUniformLocation,
+    line_pass_loc: WebGlUniformLocation,
+}
+
+impl Wireframe {
+    pub fn new(
+        context: &Gl,
+        program: &WebGlProgram,
+        v_position: u32,
+        attributes: &[u32],
+    ) -> Result<Wireframe, JsValue> {
+        let barycentric_buffer = context
+            .create_buffer()
+            .ok_or("failed to create barycentric buffer")?;
+        let edge_buffer = context
+            .create_buffer()
+            .ok_or("failed to create edge buffer")?;
+
+        let v_barycentric = match context.get_attrib_location(program, "vBarycentric") {
+            -1 => None,
+            vb => Some(vb as u32),
+        };
+
+        let wire_color_loc = context
+            .get_uniform_location(program, "wireColor")
+            .ok_or("failed to get location for wireColor")?;
+        let wire_width_loc = context.get_uniform_location(program, "wireWidth");
+        let line_pass_loc = context
+            .get_uniform_location(program, "linePass")
+            .ok_or("failed to get location for linePass")?;
+
+        Ok(Wireframe {
+            barycentric_buffer,
+            edge_buffer,
+            triangle_count: 0,
+            edge_count: 0,
+            components: 0,
+            v_position,
+            v_barycentric,
+            attributes: attributes.to_vec(),
+            wire_color_loc,
+            wire_width_loc,
+            line_pass_loc,
+        })
+    }
+
+    // Uploads the overlay data for a TRIANGLES stream whose positions the
+    // caller keeps in its own buffer.
+    pub fn set_triangles<V: Vertex>(&mut self, context: &Gl, triangles: &[V]) {
+        let edges = mesh::edges(Gl::TRIANGLES, triangles).unwrap();
+
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.barycentric_buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &Vec3::flatten(&mesh::barycentric(triangles.len() / 3)),
+            Gl::STATIC_DRAW,
+        );
+        context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.edge_buffer));
+        context.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &V::flatten(&edges),
+            Gl::STATIC_DRAW,
+        );
+
+        self.triangle_count = triangles.len() as i32;
+        self.edge_count = edges.len() as i32;
+        self.components = V::COMPONENTS;
+    }
+
+    pub fn draw(&self, context: &Gl, mode: Mode, position_buffer: &WebGlBuffer) {
+        let (r, g, b) = (WIRE_COLOR.0, WIRE_COLOR.1, WIRE_COLOR.2);
+        context.uniform3f(Some(&self.wire_color_loc), r, g, b);
+
+        if mode == Mode::Wire {
+            context.uniform1i(Some(&self.line_pass_loc), 1);
+            if let Some(v_barycentric) = self.v_barycentric {
+                context.disable_vertex_attrib_array(v_barycentric);
+            }
+            for &attribute in &self.attributes {
+                context.disable_vertex_attrib_array(attribute);
+            }
+
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.edge_buffer));
+            context.vertex_attrib_pointer_with_i32(
+                self.v_position,
+                self.components,
+                Gl::FLOAT,
+                false,
+                0,
+                0,
+            );
+            context.draw_arrays(Gl::LINES, 0, self.edge_count);
+
+            for &attribute in &self.attributes {
+                context.enable_vertex_attrib_array(attribute);
+            }
+        } else {
+            let wire_width = if mode == Mode::FillAndWire {
+                WIRE_WIDTH
+            } else {
+                0.0
+            };
+            context.uniform1i(Some(&self.line_pass_loc), 0);
+            context.uniform1f(self.wire_width_loc.as_ref(), wire_width);
+
+            if let Some(v_barycentric) = self.v_barycentric {
+                context.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.barycentric_buffer));
+                context.vertex_attrib_pointer_with_i32(v_barycentric, 3, Gl::FLOAT, false, 0, 0);
+                context.enable_vertex_attrib_array(v_barycentric);
+            }
+
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(position_buffer));
+            context.vertex_attrib_pointer_with_i32(
+                self.v_position,
+                self.components,
+                Gl::FLOAT,
+                false,
+                0,
+                0,
+            );
+            context.draw_arrays(Gl::TRIANGLES, 0, self.triangle_count);
+        }
+    }
+}